@@ -0,0 +1,127 @@
+use anyhow::{bail, Context, Result};
+use futures_util::stream::{self, StreamExt};
+use mistral_chat_cli_rs::tr;
+use mistral_chat_cli_rs::ChatClient;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+
+/// One line of a batch file.
+struct Entry {
+    custom_id: Value,
+    /// Index of the line's request in `Plan::requests`.
+    request: usize,
+}
+
+/// The lines of a batch file, with identical requests collapsed so each is sent once.
+pub struct Plan {
+    entries: Vec<Entry>,
+    /// The distinct request bodies, serialized, in the order they first appear.
+    requests: Vec<String>,
+}
+
+impl Plan {
+    /// Parses a batch file in the format of the batch API, one `{"custom_id": ..., "body": ...}`
+    /// object per line.
+    ///
+    /// Bodies that name no model get `model`, and all are sent without streaming. Two lines are
+    /// duplicates when their bodies are equal once that is done: same model, messages and
+    /// parameters, whatever the order of their keys.
+    pub fn parse(input: &str, model: &str) -> Result<Plan> {
+        let mut entries = Vec::new();
+        let mut requests = Vec::new();
+        let mut seen: HashMap<String, usize> = HashMap::new();
+        for (index, line) in input.lines().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let mut line: Value =
+                serde_json::from_str(line).with_context(|| tr!("batch_invalid_line", index + 1))?;
+            let Some(Value::Object(mut body)) = line.get_mut("body").map(Value::take) else {
+                bail!(tr!("batch_no_body", index + 1));
+            };
+            body.entry("model").or_insert_with(|| model.into());
+            body.insert("stream".to_string(), false.into());
+            // Object keys are kept sorted, so equal bodies serialize the same way.
+            let body = Value::Object(body).to_string();
+            let request = *seen.entry(body.clone()).or_insert_with(|| {
+                requests.push(body);
+                requests.len() - 1
+            });
+            entries.push(Entry {
+                custom_id: line["custom_id"].take(),
+                request,
+            });
+        }
+        Ok(Plan { entries, requests })
+    }
+
+    /// How many lines the file has.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// How many requests will be sent.
+    pub fn unique(&self) -> usize {
+        self.requests.len()
+    }
+
+    /// How many lines reuse the answer to an identical earlier line.
+    pub fn duplicates(&self) -> usize {
+        self.len() - self.unique()
+    }
+}
+
+/// Sends each distinct request of `plan` once, at most `concurrency` at a time, and returns one
+/// result per line in file order: `{"custom_id": ..., "response": ...}`, or `"error"` in place
+/// of `"response"` when the request failed.
+pub async fn run(client: &ChatClient, plan: &Plan, concurrency: usize) -> Vec<Value> {
+    let mut answers: Vec<Option<Result<Value, String>>> = vec![None; plan.unique()];
+    let mut responses = stream::iter(plan.requests.iter().enumerate())
+        .map(|(index, body)| async move {
+            let response = client.send_raw_json(body.clone().into_bytes()).await;
+            (index, response)
+        })
+        .buffer_unordered(concurrency.max(1));
+    while let Some((index, response)) = responses.next().await {
+        let response = response.map_err(|e| format!("{:#}", anyhow::Error::from(e)));
+        answers[index] = Some(response);
+    }
+    plan.entries
+        .iter()
+        .map(|entry| match &answers[entry.request] {
+            Some(Ok(response)) => json!({ "custom_id": entry.custom_id, "response": response }),
+            Some(Err(e)) => json!({ "custom_id": entry.custom_id, "error": e }),
+            None => json!({ "custom_id": entry.custom_id, "error": "not sent" }),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn identical_requests_are_sent_once() {
+        let input = concat!(
+            r#"{"custom_id": "a", "body": {"messages": [{"role": "user", "content": "Hi"}]}}"#,
+            "\n\n",
+            r#"{"custom_id": "b", "body": {"max_tokens": 5, "messages": [{"role": "user", "content": "Hi"}]}}"#,
+            "\n",
+            r#"{"body": {"messages": [{"content": "Hi", "role": "user"}], "model": "small"}, "custom_id": "c"}"#,
+            "\n",
+            r#"{"custom_id": "d", "body": {"model": "small", "messages": [{"role": "user", "content": "Hi"}]}}"#,
+        );
+        let plan = Plan::parse(input, "small").unwrap();
+        assert_eq!((plan.len(), plan.unique(), plan.duplicates()), (4, 2, 2));
+        let requests: Vec<usize> = plan.entries.iter().map(|entry| entry.request).collect();
+        assert_eq!(requests, [0, 1, 0, 0]);
+        assert_eq!(plan.entries[3].custom_id, "d");
+        assert!(plan.requests[0].contains(r#""stream":false"#));
+    }
+
+    #[test]
+    fn lines_without_a_body_are_rejected() {
+        assert!(Plan::parse(r#"{"custom_id": "a"}"#, "small").is_err());
+        assert!(Plan::parse("not json", "small").is_err());
+    }
+}
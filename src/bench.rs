@@ -0,0 +1,161 @@
+use futures_util::stream::{self, StreamExt};
+use mistral_chat_cli_rs::client::ChatParams;
+use mistral_chat_cli_rs::stream::StreamEvent;
+use mistral_chat_cli_rs::tr;
+use mistral_chat_cli_rs::{ChatClient, RequestMessage};
+use std::time::{Duration, Instant};
+
+/// Timings of one benchmark request.
+struct Sample {
+    time_to_first_token: Duration,
+    total: Duration,
+    /// Completion tokens reported by the API, if it sent usage.
+    completion_tokens: Option<u32>,
+}
+
+impl Sample {
+    fn tokens_per_second(&self) -> Option<f64> {
+        let seconds = self.total.as_secs_f64();
+        self.completion_tokens
+            .filter(|_| seconds > 0.0)
+            .map(|tokens| f64::from(tokens) / seconds)
+    }
+}
+
+/// Sends `requests` streaming requests, at most `concurrency` at a time, printing one line per
+/// request and summary statistics. Returns how many requests succeeded.
+pub async fn run(
+    client: &ChatClient,
+    model: &str,
+    prompt: &str,
+    requests: usize,
+    concurrency: usize,
+) -> usize {
+    let mut samples = Vec::new();
+    let mut results = stream::iter(1..=requests)
+        .map(|index| async move { (index, measure(client, model, prompt).await) })
+        .buffer_unordered(concurrency.max(1));
+    while let Some((index, result)) = results.next().await {
+        match result {
+            Ok(sample) => {
+                let ttft = format!("{:>6}", sample.time_to_first_token.as_millis());
+                let total = format!("{:>6}", sample.total.as_millis());
+                let tokens = sample
+                    .completion_tokens
+                    .map_or("?".to_string(), |tokens| tokens.to_string());
+                let tokens = format!("{:>5}", tokens);
+                let rate = sample
+                    .tokens_per_second()
+                    .map_or(tr!("bench_rate_unknown"), |rate| {
+                        tr!("bench_rate", format!("{:.1}", rate))
+                    });
+                println!(
+                    "#{:<4} {}",
+                    index,
+                    tr!("bench_sample", ttft, total, tokens, rate)
+                );
+                samples.push(sample);
+            }
+            Err(e) => {
+                let e = format!("{:#}", anyhow::Error::from(e));
+                println!("#{:<4} {}", index, tr!("bench_failed", e));
+            }
+        }
+    }
+
+    println!(
+        "\n{}",
+        tr!(
+            "bench_summary",
+            samples.len(),
+            requests,
+            model,
+            concurrency.max(1)
+        )
+    );
+    if samples.is_empty() {
+        return 0;
+    }
+    println!(
+        "{:<12} {:>10} {:>10} {:>10} {:>10}",
+        "",
+        tr!("bench_min"),
+        tr!("bench_median"),
+        tr!("bench_p95"),
+        tr!("bench_max")
+    );
+    let millis = |d: Duration| d.as_secs_f64() * 1000.0;
+    print_row(
+        &tr!("bench_ttft_ms"),
+        samples.iter().map(|s| millis(s.time_to_first_token)),
+    );
+    print_row(
+        &tr!("bench_total_ms"),
+        samples.iter().map(|s| millis(s.total)),
+    );
+    print_row(
+        &tr!("bench_tok_s"),
+        samples.iter().filter_map(Sample::tokens_per_second),
+    );
+    samples.len()
+}
+
+/// Times a single request from sending it until the stream ends.
+async fn measure(
+    client: &ChatClient,
+    model: &str,
+    prompt: &str,
+) -> mistral_chat_cli_rs::Result<Sample> {
+    let messages = vec![RequestMessage {
+        role: "user".to_string(),
+        content: prompt.to_string(),
+        ..Default::default()
+    }];
+    let start = Instant::now();
+    let mut stream = client
+        .chat_events(model, messages, &ChatParams::default())
+        .await?;
+    let mut first_token = None;
+    let mut completion_tokens = None;
+    while let Some(event) = stream.next().await {
+        match event? {
+            StreamEvent::Content(_) | StreamEvent::Reasoning(_) | StreamEvent::Refusal(_) => {
+                first_token.get_or_insert_with(|| start.elapsed());
+            }
+            StreamEvent::Usage(usage) => completion_tokens = Some(usage.completion_tokens),
+            StreamEvent::Finish(_) | StreamEvent::Citation(_) => {}
+        }
+    }
+    let total = start.elapsed();
+    Ok(Sample {
+        time_to_first_token: first_token.unwrap_or(total),
+        total,
+        completion_tokens,
+    })
+}
+
+/// Prints min, median, p95 and max of `values`, or dashes when there are none.
+fn print_row(label: &str, values: impl Iterator<Item = f64>) {
+    let mut values: Vec<f64> = values.collect();
+    if values.is_empty() {
+        println!(
+            "{:<12} {:>10} {:>10} {:>10} {:>10}",
+            label, "-", "-", "-", "-"
+        );
+        return;
+    }
+    values.sort_by(f64::total_cmp);
+    // Nearest-rank percentiles, so every figure is an observed value.
+    let percentile = |p: f64| {
+        let rank = (p * values.len() as f64).ceil() as usize;
+        values[rank.clamp(1, values.len()) - 1]
+    };
+    println!(
+        "{:<12} {:>10.1} {:>10.1} {:>10.1} {:>10.1}",
+        label,
+        values[0],
+        percentile(0.5),
+        percentile(0.95),
+        values[values.len() - 1]
+    );
+}
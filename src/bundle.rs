@@ -0,0 +1,104 @@
+//! Passphrase-encrypted bundles for moving a configuration file between machines.
+//!
+//! A bundle is a header followed by the ChaCha20-Poly1305 ciphertext of the file. The key is
+//! derived from the passphrase with Argon2id, whose parameters are stored in the header so they
+//! can be raised later without breaking older bundles. The header is authenticated as well.
+
+use crate::error::Result;
+use ::config::ConfigError;
+use argon2::{Algorithm, Argon2, Params, Version};
+use chacha20poly1305::aead::rand_core::RngCore;
+use chacha20poly1305::aead::{Aead, KeyInit, OsRng, Payload};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+
+/// Identifies a bundle file and its format version.
+const MAGIC: &[u8; 8] = b"MCCB\0\0\0\x01";
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 12;
+/// Magic, three little-endian `u32` Argon2 parameters, salt and nonce.
+const HEADER_LEN: usize = MAGIC.len() + 12 + SALT_LEN + NONCE_LEN;
+
+/// Argon2id memory cost in KiB, iterations and lanes for new bundles.
+const MEMORY_KIB: u32 = 64 * 1024;
+const ITERATIONS: u32 = 3;
+const LANES: u32 = 1;
+/// Largest memory cost accepted from a bundle header, so a crafted file cannot exhaust memory.
+const MAX_MEMORY_KIB: u32 = 1024 * 1024;
+const MAX_ITERATIONS: u32 = 64;
+
+/// Encrypts `plaintext` under `passphrase`.
+pub fn seal(plaintext: &[u8], passphrase: &str) -> Result<Vec<u8>> {
+    let mut salt = [0u8; SALT_LEN];
+    let mut nonce = [0u8; NONCE_LEN];
+    OsRng.fill_bytes(&mut salt);
+    OsRng.fill_bytes(&mut nonce);
+
+    let mut bundle = Vec::with_capacity(HEADER_LEN + plaintext.len() + 16);
+    bundle.extend_from_slice(MAGIC);
+    for param in [MEMORY_KIB, ITERATIONS, LANES] {
+        bundle.extend_from_slice(&param.to_le_bytes());
+    }
+    bundle.extend_from_slice(&salt);
+    bundle.extend_from_slice(&nonce);
+
+    let cipher = cipher(passphrase, &salt, [MEMORY_KIB, ITERATIONS, LANES])?;
+    let ciphertext = cipher
+        .encrypt(
+            Nonce::from_slice(&nonce),
+            Payload {
+                msg: plaintext,
+                aad: &bundle,
+            },
+        )
+        .map_err(|_| ConfigError::Message("failed to encrypt the bundle".to_string()))?;
+    bundle.extend_from_slice(&ciphertext);
+    Ok(bundle)
+}
+
+/// Decrypts a bundle written by [`seal`], failing on a wrong passphrase or any tampering.
+pub fn open(bundle: &[u8], passphrase: &str) -> Result<Vec<u8>> {
+    if bundle.len() < HEADER_LEN || !bundle.starts_with(MAGIC) {
+        return Err(ConfigError::Message("not a config bundle".to_string()).into());
+    }
+    let (header, ciphertext) = bundle.split_at(HEADER_LEN);
+    let param = |i: usize| {
+        let start = MAGIC.len() + 4 * i;
+        u32::from_le_bytes(header[start..start + 4].try_into().expect("4-byte slice"))
+    };
+    let salt = &header[MAGIC.len() + 12..MAGIC.len() + 12 + SALT_LEN];
+    let nonce = &header[HEADER_LEN - NONCE_LEN..];
+
+    if param(0) > MAX_MEMORY_KIB || param(1) > MAX_ITERATIONS {
+        return Err(ConfigError::Message(
+            "bundle asks for excessive key derivation parameters".to_string(),
+        )
+        .into());
+    }
+    let cipher = cipher(passphrase, salt, [param(0), param(1), param(2)])?;
+    cipher
+        .decrypt(
+            Nonce::from_slice(nonce),
+            Payload {
+                msg: ciphertext,
+                aad: header,
+            },
+        )
+        .map_err(|_| {
+            ConfigError::Message("wrong passphrase or corrupted bundle".to_string()).into()
+        })
+}
+
+/// Derives the bundle key from `passphrase` with the given Argon2id parameters.
+fn cipher(
+    passphrase: &str,
+    salt: &[u8],
+    [memory, iterations, lanes]: [u32; 3],
+) -> Result<ChaCha20Poly1305> {
+    let params = Params::new(memory, iterations, lanes, Some(32))
+        .map_err(|e| ConfigError::Message(format!("invalid key derivation parameters: {}", e)))?;
+    let mut key = [0u8; 32];
+    Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+        .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+        .map_err(|e| ConfigError::Message(format!("key derivation failed: {}", e)))?;
+    Ok(ChaCha20Poly1305::new(Key::from_slice(&key)))
+}
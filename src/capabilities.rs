@@ -0,0 +1,77 @@
+use serde_json::{json, Map, Value};
+
+/// Describes this build for tools that wrap the CLI: its version, the features compiled in, and
+/// every subcommand with its options, as read from the `clap` definition of `command`.
+///
+/// `command` must have been built, so that value counts and positional names are settled.
+pub fn describe(command: &clap::Command) -> Value {
+    json!({
+        "name": command.get_name(),
+        "version": env!("CARGO_PKG_VERSION"),
+        "features": {
+            "clipboard": true,
+            "config_bundle": true,
+            "serve": cfg!(unix),
+            "tui": true,
+        },
+        "sinks": ["stdout", "file", "clipboard", "command"],
+        "options": arguments(command),
+        "subcommands": subcommands(command),
+    })
+}
+
+/// The visible subcommands of `command`, keyed by name.
+fn subcommands(command: &clap::Command) -> Map<String, Value> {
+    command
+        .get_subcommands()
+        .filter(|subcommand| !subcommand.is_hide_set() && subcommand.get_name() != "help")
+        .map(|subcommand| {
+            let mut entry = json!({
+                "about": subcommand.get_about().map(ToString::to_string),
+                "options": arguments(subcommand),
+            });
+            let nested = subcommands(subcommand);
+            if !nested.is_empty() {
+                entry["subcommands"] = Value::Object(nested);
+            }
+            (subcommand.get_name().to_string(), entry)
+        })
+        .collect()
+}
+
+/// The visible arguments of `command`: `--long` flags and options, and positionals by value
+/// name, with whether each takes a value and which values it accepts.
+fn arguments(command: &clap::Command) -> Vec<Value> {
+    command
+        .get_arguments()
+        .filter(|arg| !arg.is_hide_set() && !matches!(arg.get_id().as_str(), "help" | "version"))
+        .map(|arg| {
+            let name = match arg.get_long() {
+                Some(long) => format!("--{}", long),
+                None => arg
+                    .get_value_names()
+                    .and_then(|names| names.first())
+                    .map_or_else(
+                        || arg.get_id().to_string().to_uppercase(),
+                        ToString::to_string,
+                    ),
+            };
+            let takes_value = arg.get_num_args().is_some_and(|range| range.takes_values());
+            let mut entry = json!({
+                "name": name,
+                "takes_value": takes_value,
+                "required": arg.is_required_set(),
+            });
+            let values: Vec<String> = arg
+                .get_possible_values()
+                .iter()
+                .filter(|value| !value.is_hide_set())
+                .map(|value| value.get_name().to_string())
+                .collect();
+            if !values.is_empty() {
+                entry["values"] = json!(values);
+            }
+            entry
+        })
+        .collect()
+}
@@ -0,0 +1,37 @@
+/// Splits `text` into pieces of at most `size` characters, each starting `overlap` characters
+/// before the previous one ended so context at the seams isn't lost.
+///
+/// Pieces break at the last newline inside the window when there is one. `overlap` must be
+/// smaller than `size`.
+pub fn split(text: &str, size: usize, overlap: usize) -> Vec<&str> {
+    assert!(
+        overlap < size,
+        "chunk overlap must be smaller than the chunk size"
+    );
+    let offsets: Vec<usize> = text
+        .char_indices()
+        .map(|(i, _)| i)
+        .chain(std::iter::once(text.len()))
+        .collect();
+    let count = offsets.len() - 1;
+
+    let mut chunks = Vec::new();
+    let mut start = 0;
+    loop {
+        let mut end = (start + size).min(count);
+        if end < count {
+            // Prefer ending on a line break, as long as the chunk still advances past the overlap.
+            if let Some(newline) = text[offsets[start]..offsets[end]].rfind('\n') {
+                let line_end = text[..offsets[start] + newline + 1].chars().count();
+                if line_end > start + overlap {
+                    end = line_end;
+                }
+            }
+        }
+        chunks.push(&text[offsets[start]..offsets[end]]);
+        if end == count {
+            return chunks;
+        }
+        start = end - overlap;
+    }
+}
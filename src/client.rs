@@ -0,0 +1,2447 @@
+use crate::config::Config;
+use crate::error::{Error, Result};
+use crate::redact::{self, OutputPatterns};
+use crate::sink::{shell, Sink};
+use crate::stream::{ChatStream, Citation, JsonPath, StreamEvent, Usage};
+use crate::timing::{ConnectTimings, RequestTimings, TimedConnectLayer, TimedResolver};
+use crate::tr;
+use crate::wrap::Wrapper;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use config::ConfigError;
+use futures_util::StreamExt;
+use log::{debug, error, info, warn};
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::{IsTerminal, Write};
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tokio::io::{AsyncWrite, AsyncWriteExt};
+use tokio_util::sync::CancellationToken;
+
+// Constants for API endpoints and model names.
+pub const MISTRAL_URL: &str = "https://api.mistral.ai/v1/chat/completions";
+pub const CODESTRAL_URL: &str = "https://codestral.mistral.ai/v1/chat/completions";
+pub const MISTRAL_MODEL: &str = "mistral-large-latest";
+pub const CODESTRAL_MODEL: &str = "codestral-latest";
+pub const VISION_MODEL: &str = "pixtral-large-latest";
+pub const MISTRAL_OCR_URL: &str = "https://api.mistral.ai/v1/ocr";
+pub const MISTRAL_FILES_URL: &str = "https://api.mistral.ai/v1/files";
+pub const MISTRAL_MODELS_URL: &str = "https://api.mistral.ai/v1/models";
+pub const MISTRAL_BATCH_JOBS_URL: &str = "https://api.mistral.ai/v1/batch/jobs";
+pub const CODESTRAL_MODELS_URL: &str = "https://codestral.mistral.ai/v1/models";
+const OCR_MODEL: &str = "mistral-ocr-latest";
+
+/// System message sent with code to Codestral unless `code_system_prompt` replaces it.
+pub const DEFAULT_CODE_SYSTEM_PROMPT: &str = "You are a senior software engineer reviewing code. \
+    Explain what the code does, then point out bugs, edge cases, security problems and unclear \
+    naming, most important first. Suggest concrete fixes with short code examples, and say so \
+    plainly when the code is fine.";
+
+/// Retries after a transport error, such as a refused or reset connection, and the fixed wait
+/// between them.
+pub const DEFAULT_RETRIES: u32 = 2;
+pub const DEFAULT_RETRY_DELAY: Duration = Duration::from_secs(2);
+/// Retries after a 429 response, and the wait before the first, doubled for each later one.
+pub const DEFAULT_RETRIES_ON_429: u32 = 3;
+pub const DEFAULT_RETRY_DELAY_ON_429: Duration = Duration::from_secs(5);
+/// Largest non-streaming response body that is read into memory.
+pub const DEFAULT_MAX_RESPONSE_BYTES: u64 = 64 * 1024 * 1024;
+/// Longest `Retry-After` worth waiting for; a longer one is reported as the error instead.
+const MAX_RETRY_AFTER: Duration = Duration::from_secs(120);
+
+/// How long a token from `auth_command` is reused when it carries no expiry.
+const OPAQUE_TOKEN_TTL: Duration = Duration::from_secs(300);
+/// How long before a JWT's expiry a fresh token is fetched.
+const TOKEN_EXPIRY_MARGIN: Duration = Duration::from_secs(30);
+
+/// Fields of a chat request that `request_template` can place with `{{name}}`.
+const TEMPLATE_FIELDS: [&str; 9] = [
+    "model",
+    "messages",
+    "stream",
+    "max_tokens",
+    "reasoning_effort",
+    "logprobs",
+    "top_logprobs",
+    "service_tier",
+    "metadata",
+];
+
+/// User-Agent sent unless the config overrides it.
+pub const USER_AGENT: &str = concat!("mistral-chat-cli/", env!("CARGO_PKG_VERSION"));
+
+/// Response headers that may carry the server-assigned request ID, in order of preference.
+const REQUEST_ID_HEADERS: [&str; 3] = [
+    "x-request-id",
+    "mistral-correlation-id",
+    "x-kong-request-id",
+];
+
+/// Struct representing a request message sent to the API.
+#[derive(Deserialize, Clone, Default)]
+pub struct RequestMessage {
+    pub role: String,
+    pub content: String,
+    /// Images sent along with the text, as `data:` or `https:` URLs.
+    #[serde(default)]
+    pub images: Vec<String>,
+    /// Mark the text with `cache_control` as a prefix worth caching, e.g. a long system prompt
+    /// sent on every turn.
+    #[serde(default)]
+    pub cache: bool,
+    /// Send a final assistant message as `prefix`, so the model continues it instead of
+    /// answering anew.
+    #[serde(default)]
+    pub prefix: bool,
+}
+
+/// A piece of a multi-part message, as sent when images are attached or caching is requested.
+#[derive(Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum ContentPart<'a> {
+    Text {
+        text: &'a str,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        cache_control: Option<CacheControl>,
+    },
+    ImageUrl {
+        image_url: &'a str,
+    },
+}
+
+/// Asks the API to cache the prompt up to and including the marked part.
+#[derive(Serialize)]
+struct CacheControl {
+    #[serde(rename = "type")]
+    kind: &'static str,
+}
+
+impl Serialize for RequestMessage {
+    /// Sends plain text as a string, and text with images or a cache marker as a list of content
+    /// parts.
+    fn serialize<S: serde::Serializer>(
+        &self,
+        serializer: S,
+    ) -> std::result::Result<S::Ok, S::Error> {
+        use serde::ser::SerializeStruct;
+
+        let mut message = serializer.serialize_struct("RequestMessage", 2)?;
+        message.serialize_field("role", &self.role)?;
+        if self.images.is_empty() && !self.cache {
+            message.serialize_field("content", &self.content)?;
+        } else {
+            let mut parts = vec![ContentPart::Text {
+                text: &self.content,
+                cache_control: self.cache.then_some(CacheControl { kind: "ephemeral" }),
+            }];
+            parts.extend(
+                self.images
+                    .iter()
+                    .map(|url| ContentPart::ImageUrl { image_url: url }),
+            );
+            message.serialize_field("content", &parts)?;
+        }
+        if self.prefix {
+            message.serialize_field("prefix", &true)?;
+        }
+        message.end()
+    }
+}
+
+/// Optional request parameters for streaming chat completions.
+#[derive(Debug, Clone, Default)]
+pub struct ChatParams {
+    /// Reasoning depth for reasoning models: `low`, `medium` or `high`.
+    pub reasoning_effort: Option<String>,
+    /// Most tokens the model may generate; unset leaves it to the API.
+    pub max_tokens: Option<u32>,
+    /// Service tier trading latency for cost, such as `flex`; unset leaves it to the API.
+    pub service_tier: Option<String>,
+}
+
+/// Whether `model` is a reasoning model that accepts `ChatParams::reasoning_effort`.
+pub fn is_reasoning_model(model: &str) -> bool {
+    model.starts_with("magistral")
+}
+
+/// Output settings for `ChatClient::chat_stream`.
+pub struct StreamOptions {
+    /// Reflow output to this many columns.
+    pub wrap: Option<usize>,
+    /// End the output with a newline once the stream completes, unless it already ends with one.
+    pub trailing_newline: bool,
+    /// Pause this long after each character for a typewriter effect.
+    pub typewriter: Option<Duration>,
+    /// On a terminal, batch text written to stdout and flush it at most this long after it was
+    /// written, instead of after every delta.
+    pub flush_interval: Option<Duration>,
+    /// Print the model's reasoning trace to stderr, apart from the answer.
+    pub show_reasoning: bool,
+    /// Stop the request or stream, keeping what was printed, once this is cancelled.
+    pub cancel: Option<CancellationToken>,
+    /// Print one JSON object per delta and a final `done` object instead of raw text.
+    pub jsonl: bool,
+    /// With `jsonl`, indent each object over several lines for reading instead of piping.
+    pub pretty: bool,
+    /// Write the response to stdout; turn off to send it only to `sinks`.
+    pub stdout: bool,
+    /// Further destinations that receive every content delta. The caller calls `Sink::finish`.
+    pub sinks: Vec<Box<dyn Sink>>,
+    /// Give up if the first event has not arrived this long after sending the request.
+    pub first_token_timeout: Option<Duration>,
+    /// Stop, keeping what was printed, if the stream goes quiet for this long between events.
+    pub idle_timeout: Option<Duration>,
+    /// Line ending for everything written to stdout; sinks get the text as received.
+    pub eol: LineEnding,
+    /// Print an assistant `prefix` the response continues, and send it to sinks, before the
+    /// continuation, so the output reads as one response.
+    pub print_prefix: bool,
+    /// Mask matches of these patterns in the response before it is printed or sent to sinks.
+    /// `StreamOutcome::content` holds the masked text.
+    pub redact: Option<OutputPatterns>,
+    /// Models to try in turn when the requested one is still rate limited after its retries, or
+    /// unknown or unavailable. `StreamOutcome::model` names the one that answered.
+    pub fallback_models: Vec<String>,
+    /// Models in order of growing context window, to move up to when the prompt is too long for
+    /// the model: those after it in the list, or all of them if it is not listed.
+    pub larger_context_models: Vec<String>,
+    /// When the whole request must be answered by, fallbacks and larger-context retries
+    /// included. Set it from `ChatClient::request_deadline` once per user request, so that
+    /// continuations and resumes share it; unset, each call starts its own.
+    pub deadline: Option<Instant>,
+}
+
+/// The newline sequence written to stdout.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LineEnding {
+    Lf,
+    Crlf,
+}
+
+impl LineEnding {
+    /// CRLF on Windows, LF elsewhere.
+    pub fn native() -> Self {
+        if cfg!(windows) {
+            LineEnding::Crlf
+        } else {
+            LineEnding::Lf
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            LineEnding::Lf => "\n",
+            LineEnding::Crlf => "\r\n",
+        }
+    }
+
+    /// Rewrites every LF, CRLF or CR-only line break in `text` to this ending.
+    ///
+    /// A CR at the end of `text` is held back in `pending_cr`, since the LF completing it may
+    /// arrive with the next delta; pass the same flag for every delta of a stream.
+    fn normalize(self, text: &str, pending_cr: &mut bool) -> String {
+        let mut normalized = String::with_capacity(text.len());
+        for ch in text.chars() {
+            if std::mem::take(pending_cr) && ch != '\n' {
+                normalized.push_str(self.as_str());
+            }
+            match ch {
+                '\r' => *pending_cr = true,
+                '\n' => normalized.push_str(self.as_str()),
+                _ => normalized.push(ch),
+            }
+        }
+        normalized
+    }
+}
+
+impl Default for StreamOptions {
+    fn default() -> Self {
+        StreamOptions {
+            wrap: None,
+            trailing_newline: true,
+            typewriter: None,
+            flush_interval: None,
+            show_reasoning: false,
+            cancel: None,
+            jsonl: false,
+            pretty: false,
+            stdout: true,
+            sinks: Vec::new(),
+            first_token_timeout: None,
+            idle_timeout: None,
+            eol: LineEnding::native(),
+            print_prefix: false,
+            redact: None,
+            fallback_models: Vec::new(),
+            larger_context_models: Vec::new(),
+            deadline: None,
+        }
+    }
+}
+
+/// What a completed stream produced.
+#[derive(Debug, Default)]
+pub struct StreamOutcome {
+    /// The full assistant text, including the prefix being continued, if any.
+    pub content: String,
+    /// The `finish_reason` reported by the API, if any.
+    pub finish_reason: Option<String>,
+    /// Token usage reported by the API, if any.
+    pub usage: Option<Usage>,
+    /// Whether the stream was stopped through `StreamOptions::cancel`.
+    pub cancelled: bool,
+    /// Whether the stream was given up on after a first-token or idle timeout, or its deadline.
+    pub timed_out: bool,
+    /// Whether the stream broke off with an error after it had started.
+    pub interrupted: bool,
+    /// How long the phases of the request took.
+    pub timings: RequestTimings,
+    /// How many matches of `StreamOptions::redact` were masked.
+    pub redactions: usize,
+    /// The ID the server assigned to the request, if it sent one.
+    pub request_id: Option<String>,
+    /// The model that served the response: the one requested or one of its fallbacks.
+    pub model: String,
+    /// The sources the answer cites, in order; `[n]` in `content` refers to the `n`th.
+    pub citations: Vec<Citation>,
+}
+
+/// Struct representing a response message received from the API.
+#[derive(Deserialize)]
+struct ResponseMessage {
+    content: String,
+}
+
+/// Struct representing a chat request sent to the API.
+#[derive(Serialize)]
+struct ChatRequest {
+    model: String,
+    messages: Vec<RequestMessage>,
+    stream: bool,
+    max_tokens: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    reasoning_effort: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    logprobs: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    top_logprobs: Option<u8>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    service_tier: Option<String>,
+    /// Key-value tags for attributing usage, from `metadata` in the config.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    metadata: BTreeMap<String, String>,
+}
+
+/// Struct representing a chat response received from the API.
+#[derive(Deserialize)]
+struct ChatResponse {
+    choices: Vec<Choice>,
+}
+
+/// Struct representing a choice in the chat response.
+#[derive(Deserialize)]
+struct Choice {
+    message: ResponseMessage,
+    #[serde(default)]
+    finish_reason: Option<String>,
+    #[serde(default)]
+    logprobs: Option<ChoiceLogprobs>,
+}
+
+/// The per-token log-probabilities of a choice, sent when `logprobs` was requested.
+#[derive(Deserialize)]
+struct ChoiceLogprobs {
+    #[serde(default)]
+    content: Vec<TokenLogprob>,
+}
+
+/// One generated token and how likely the model found it.
+#[derive(Debug, Clone, Deserialize)]
+pub struct TokenLogprob {
+    pub token: String,
+    /// Natural logarithm of the token's probability.
+    pub logprob: f64,
+    /// The most likely tokens at this position, when `top_logprobs` was requested.
+    #[serde(default)]
+    pub top_logprobs: Vec<TopLogprob>,
+}
+
+/// A complete answer from [`ChatClient::chat_logprobs`].
+#[derive(Debug, Clone)]
+pub struct LogprobsAnswer {
+    pub content: String,
+    pub finish_reason: Option<String>,
+    /// The answer's tokens; empty if the API does not report log-probabilities.
+    pub tokens: Vec<TokenLogprob>,
+}
+
+/// An alternative token the model considered at a position.
+#[derive(Debug, Clone, Deserialize)]
+pub struct TopLogprob {
+    pub token: String,
+    pub logprob: f64,
+}
+
+/// Struct representing an OCR request sent to the API.
+#[derive(Serialize)]
+struct OcrRequest {
+    model: String,
+    document: OcrDocument,
+}
+
+/// A document passed to the OCR endpoint as a base64 data URI.
+#[derive(Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum OcrDocument {
+    DocumentUrl { document_url: String },
+    ImageUrl { image_url: String },
+}
+
+/// Struct representing an OCR response received from the API.
+#[derive(Deserialize)]
+struct OcrResponse {
+    pages: Vec<OcrPage>,
+}
+
+/// Struct representing a single page of extracted OCR text.
+#[derive(Deserialize)]
+struct OcrPage {
+    markdown: String,
+}
+
+/// A file stored with the files API.
+#[derive(Debug, Clone, Deserialize)]
+pub struct FileObject {
+    pub id: String,
+    pub filename: String,
+    /// Size of the file in bytes.
+    pub bytes: u64,
+    /// What the file is for, e.g. `batch` or `fine-tune`.
+    pub purpose: String,
+}
+
+/// Struct representing the file listing received from the API.
+#[derive(Deserialize)]
+struct FileList {
+    data: Vec<FileObject>,
+}
+
+/// A batch inference job.
+#[derive(Debug, Clone, Deserialize)]
+pub struct BatchJob {
+    pub id: String,
+    /// `QUEUED`, `RUNNING`, `SUCCESS`, `FAILED`, `TIMEOUT_EXCEEDED`, `CANCELLATION_REQUESTED`
+    /// or `CANCELLED`.
+    pub status: String,
+    /// Seconds since the Unix epoch when the job was created.
+    pub created_at: i64,
+    #[serde(default)]
+    pub model: Option<String>,
+    #[serde(default)]
+    pub total_requests: u64,
+    #[serde(default)]
+    pub completed_requests: u64,
+    #[serde(default)]
+    pub succeeded_requests: u64,
+    #[serde(default)]
+    pub failed_requests: u64,
+    /// ID of the file holding the results, once there are any.
+    #[serde(default)]
+    pub output_file: Option<String>,
+    /// ID of the file holding the failed requests' errors, if any failed.
+    #[serde(default)]
+    pub error_file: Option<String>,
+}
+
+/// Struct representing the batch job listing received from the API.
+#[derive(Deserialize)]
+struct BatchJobList {
+    #[serde(default)]
+    data: Vec<BatchJob>,
+}
+
+/// A model offered by the API.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ModelInfo {
+    pub id: String,
+    /// Largest combined prompt and completion the model accepts, in tokens.
+    #[serde(default)]
+    pub max_context_length: Option<u64>,
+    /// Feature flags such as `completion_chat`, `function_calling` or `vision`.
+    #[serde(default)]
+    pub capabilities: BTreeMap<String, serde_json::Value>,
+}
+
+impl ModelInfo {
+    /// Names of the capabilities the model has, in alphabetical order.
+    pub fn enabled_capabilities(&self) -> Vec<&str> {
+        self.capabilities
+            .iter()
+            .filter(|(_, value)| value.as_bool() == Some(true))
+            .map(|(name, _)| name.as_str())
+            .collect()
+    }
+}
+
+/// Struct representing the model listing received from the API.
+#[derive(Deserialize)]
+struct ModelList {
+    data: Vec<ModelInfo>,
+}
+
+/// The result of pinging one API endpoint.
+pub struct PingResult {
+    /// Which API was pinged, e.g. `Mistral`.
+    pub name: &'static str,
+    pub url: &'static str,
+    /// The HTTP status and round-trip time, or why the endpoint could not be reached.
+    pub result: Result<(reqwest::StatusCode, Duration)>,
+}
+
+/// The result of `ChatClient::test_connection` for one API.
+pub struct ConnectionTest {
+    /// Which API was tested: `mistral` or `codestral`.
+    pub name: &'static str,
+    /// The API's name for people, e.g. `Mistral`.
+    pub title: &'static str,
+    /// The HTTP status of the response, if one arrived.
+    pub status: Option<reqwest::StatusCode>,
+    /// How long the request took, retries included.
+    pub latency: Duration,
+    /// Why the test failed, if it did.
+    pub error: Option<Error>,
+}
+
+/// Whether a cassette is being written to or read from.
+pub enum CassetteMode {
+    Record,
+    Replay,
+}
+
+/// A directory of numbered request/response recordings used as test fixtures.
+pub struct Cassette {
+    dir: PathBuf,
+    mode: CassetteMode,
+    counter: AtomicUsize,
+}
+
+impl Cassette {
+    /// Opens a cassette directory, creating it when recording.
+    pub fn new(dir: PathBuf, mode: CassetteMode) -> Result<Self> {
+        if let CassetteMode::Record = mode {
+            fs::create_dir_all(&dir)?;
+        }
+        Ok(Cassette {
+            dir,
+            mode,
+            counter: AtomicUsize::new(0),
+        })
+    }
+
+    /// Returns the request, status and body paths for the next recorded exchange.
+    fn next_paths(&self) -> (PathBuf, PathBuf, PathBuf) {
+        let index = self.counter.fetch_add(1, Ordering::SeqCst) + 1;
+        (
+            self.dir.join(format!("{:03}-request.json", index)),
+            self.dir.join(format!("{:03}-response.status", index)),
+            self.dir.join(format!("{:03}-response.body", index)),
+        )
+    }
+}
+
+/// How many times a kind of failure is retried and how long to wait first.
+#[derive(Debug, Clone, Copy)]
+pub struct RetryPolicy {
+    /// Retries after the first attempt; zero disables retrying.
+    pub retries: u32,
+    pub delay: Duration,
+}
+
+/// Allows at most a fixed number of requests in any one-minute window.
+///
+/// Each request holds a semaphore permit for a minute after it is sent, so concurrent callers
+/// sharing the client wait for the oldest request to age out once the budget is spent.
+struct RateLimiter {
+    permits: Arc<tokio::sync::Semaphore>,
+}
+
+impl RateLimiter {
+    fn new(requests_per_minute: u32) -> Self {
+        RateLimiter {
+            permits: Arc::new(tokio::sync::Semaphore::new(requests_per_minute as usize)),
+        }
+    }
+
+    /// Waits until another request may be sent.
+    async fn acquire(&self, debug: bool) {
+        if debug && self.permits.available_permits() == 0 {
+            debug!("Request rate limit reached; waiting");
+        }
+        let permit = Arc::clone(&self.permits)
+            .acquire_owned()
+            .await
+            .expect("rate limiter semaphore is never closed");
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_secs(60)).await;
+            drop(permit);
+        });
+    }
+}
+
+/// A client for interacting with the Mistral and Codestral APIs.
+pub struct ChatClient {
+    client: Client,
+    /// Used instead of `client` to fetch web pages when that one assumes HTTP/2.
+    page_client: Option<Client>,
+    mistral_api_key: String,
+    codestral_api_key: String,
+    debug: bool,
+    cassette: Option<Cassette>,
+    /// Command printing a bearer token, used instead of the API keys when set.
+    auth_command: Option<String>,
+    /// The last token from `auth_command` and when it stops being reused.
+    token: tokio::sync::Mutex<Option<(String, Instant)>>,
+    /// Command that rewrites chat request bodies before they are sent.
+    preprocess_command: Option<String>,
+    /// JSON template chat request bodies are rebuilt from, for non-standard backends.
+    request_template: Option<String>,
+    /// Where streamed text is read from in each event, for non-standard backends.
+    stream_content_path: Option<JsonPath>,
+    /// System message `analyze_code` prepends, if any.
+    code_system_prompt: Option<String>,
+    /// Retries for requests that never got a response.
+    transport_retry: RetryPolicy,
+    /// Retries for requests the API rejected with 429 Too Many Requests.
+    rate_limit_retry: RetryPolicy,
+    /// Caps outgoing requests, retries included, when `requests_per_minute` is set.
+    rate_limiter: Option<RateLimiter>,
+    /// Longest a request may take, all attempts and the waits between them included.
+    deadline: Option<Duration>,
+    /// DNS and connect times noted by the HTTP client, for `StreamOutcome::timings`.
+    connect_timings: ConnectTimings,
+    /// Log the server's ID for every response, not only in debug mode.
+    echo_request_ids: bool,
+    /// The only models requests may name, or any model when empty.
+    allowed_models: Vec<String>,
+    /// Largest non-streaming response body read before giving up.
+    max_response_bytes: u64,
+    /// Sent as the `metadata` object of every chat request, unless empty.
+    metadata: BTreeMap<String, String>,
+}
+
+impl ChatClient {
+    /// Creates a new `ChatClient` with the given API keys, debug mode and optional cassette.
+    pub fn new(
+        mistral_api_key: String,
+        codestral_api_key: String,
+        debug: bool,
+        cassette: Option<Cassette>,
+    ) -> Self {
+        redact::register(&mistral_api_key);
+        redact::register(&codestral_api_key);
+        let connect_timings = ConnectTimings::default();
+        ChatClient {
+            client: Client::builder()
+                .user_agent(USER_AGENT)
+                .dns_resolver(Arc::new(TimedResolver(connect_timings.clone())))
+                .connector_layer(TimedConnectLayer(connect_timings.clone()))
+                .build()
+                .expect("Failed to build HTTP client"),
+            page_client: None,
+            mistral_api_key,
+            codestral_api_key,
+            debug,
+            cassette,
+            auth_command: None,
+            token: tokio::sync::Mutex::new(None),
+            preprocess_command: None,
+            request_template: None,
+            stream_content_path: None,
+            code_system_prompt: Some(DEFAULT_CODE_SYSTEM_PROMPT.to_string()),
+            transport_retry: RetryPolicy {
+                retries: DEFAULT_RETRIES,
+                delay: DEFAULT_RETRY_DELAY,
+            },
+            rate_limit_retry: RetryPolicy {
+                retries: DEFAULT_RETRIES_ON_429,
+                delay: DEFAULT_RETRY_DELAY_ON_429,
+            },
+            rate_limiter: None,
+            deadline: None,
+            connect_timings,
+            echo_request_ids: false,
+            allowed_models: Vec::new(),
+            max_response_bytes: DEFAULT_MAX_RESPONSE_BYTES,
+            metadata: BTreeMap::new(),
+        }
+    }
+
+    /// Creates a `ChatClient` whose HTTP client honours the TLS and connection settings in `config`.
+    pub fn from_config(config: &Config, debug: bool, cassette: Option<Cassette>) -> Result<Self> {
+        let connect_timings = ConnectTimings::default();
+        if config.danger_accept_invalid_certs {
+            warn!("{}", tr!("tls_verification_disabled"));
+        }
+        let mut builder = Self::http_client_builder(config, &connect_timings)?;
+        // Only the API is known to speak HTTP/2; pages fetched for `summarize` can be on any host,
+        // so they get a client that negotiates the protocol.
+        let mut page_client = None;
+        if config.http2_prior_knowledge {
+            builder = builder.http2_prior_knowledge();
+            page_client = Some(Self::http_client_builder(config, &connect_timings)?.build()?);
+        }
+        if let Some(template) = &config.request_template {
+            // Fail on a broken template now rather than on the first request.
+            render_template(template, &serde_json::Value::Null)?;
+        }
+        let stream_content_path = match &config.stream_content_path {
+            Some(path) => Some(JsonPath::parse(path).map_err(|e| {
+                ConfigError::Message(format!("invalid stream_content_path: {}", e))
+            })?),
+            None => None,
+        };
+
+        redact::register(&config.mistral_api_key);
+        redact::register(&config.codestral_api_key);
+        Ok(ChatClient {
+            client: builder.build()?,
+            page_client,
+            mistral_api_key: config.mistral_api_key.clone(),
+            codestral_api_key: config.codestral_api_key.clone(),
+            debug,
+            cassette,
+            auth_command: config.auth_command.clone(),
+            token: tokio::sync::Mutex::new(None),
+            preprocess_command: config.preprocess_command.clone(),
+            request_template: config.request_template.clone(),
+            stream_content_path,
+            code_system_prompt: match &config.code_system_prompt {
+                Some(prompt) if prompt.trim().is_empty() => None,
+                Some(prompt) => Some(prompt.clone()),
+                None => Some(DEFAULT_CODE_SYSTEM_PROMPT.to_string()),
+            },
+            transport_retry: RetryPolicy {
+                retries: config.max_retries.unwrap_or(DEFAULT_RETRIES),
+                delay: config
+                    .retry_delay_ms
+                    .map_or(DEFAULT_RETRY_DELAY, Duration::from_millis),
+            },
+            rate_limit_retry: RetryPolicy {
+                retries: config.max_retries_on_429.unwrap_or(DEFAULT_RETRIES_ON_429),
+                delay: config
+                    .retry_delay_on_429_ms
+                    .map_or(DEFAULT_RETRY_DELAY_ON_429, Duration::from_millis),
+            },
+            rate_limiter: config
+                .requests_per_minute
+                .filter(|&limit| limit > 0)
+                .map(RateLimiter::new),
+            deadline: config
+                .deadline_secs
+                .filter(|&secs| secs > 0)
+                .map(Duration::from_secs),
+            connect_timings,
+            echo_request_ids: false,
+            allowed_models: config.allowed_models.clone(),
+            max_response_bytes: config
+                .max_response_bytes
+                .unwrap_or(DEFAULT_MAX_RESPONSE_BYTES),
+            metadata: config.metadata.clone(),
+        })
+    }
+
+    /// Starts an HTTP client with the TLS, proxy and connection settings of `config`, all but
+    /// `http2_prior_knowledge`.
+    fn http_client_builder(
+        config: &Config,
+        connect_timings: &ConnectTimings,
+    ) -> Result<reqwest::ClientBuilder> {
+        let mut builder = Client::builder()
+            .user_agent(config.user_agent.as_deref().unwrap_or(USER_AGENT))
+            .dns_resolver(Arc::new(TimedResolver(connect_timings.clone())))
+            .connector_layer(TimedConnectLayer(connect_timings.clone()));
+        if let Some(path) = &config.ca_cert {
+            let pem = fs::read(path)?;
+            let cert = reqwest::Certificate::from_pem(&pem).map_err(|e| {
+                ConfigError::Message(format!("invalid CA certificate {}: {}", path, e))
+            })?;
+            builder = builder.add_root_certificate(cert);
+        }
+        if config.danger_accept_invalid_certs {
+            builder = builder.danger_accept_invalid_certs(true);
+        }
+        if let Some(secs) = config.tcp_keepalive_secs {
+            builder = builder.tcp_keepalive((secs > 0).then(|| Duration::from_secs(secs)));
+        }
+        if let Some(ms) = config.connect_timeout_ms {
+            builder = builder.connect_timeout(Duration::from_millis(ms));
+        }
+        if config.no_proxy {
+            builder = builder.no_proxy();
+        }
+        Ok(builder)
+    }
+
+    /// Logs the server-assigned ID of every response, and adds it to the `done` line of JSON
+    /// Lines output, so requests can be matched with the server's logs.
+    pub fn with_request_ids(mut self) -> Self {
+        self.echo_request_ids = true;
+        self
+    }
+
+    /// Makes `analyze_code` send the code as is, without a system message.
+    pub fn without_code_system_prompt(mut self) -> Self {
+        self.code_system_prompt = None;
+        self
+    }
+
+    /// Returns the bearer token for a request: `api_key`, or the output of `auth_command`.
+    ///
+    /// A command's token is reused until its JWT `exp` claim (less a safety margin) or, for
+    /// opaque tokens, for `OPAQUE_TOKEN_TTL`. The lock makes concurrent requests share one run.
+    async fn bearer(&self, api_key: &str) -> Result<String> {
+        let Some(command) = &self.auth_command else {
+            return Ok(api_key.to_string());
+        };
+        let mut cached = self.token.lock().await;
+        if let Some((token, expires)) = cached.as_ref() {
+            if Instant::now() < *expires {
+                return Ok(token.clone());
+            }
+        }
+
+        if self.debug {
+            debug!("Running auth_command to obtain a token");
+        }
+        let output = tokio::process::Command::from(shell(command))
+            .output()
+            .await?;
+        if !output.status.success() {
+            return Err(ConfigError::Message(format!(
+                "auth_command failed with {}: {}",
+                output.status,
+                String::from_utf8_lossy(&output.stderr).trim()
+            ))
+            .into());
+        }
+        let token = String::from_utf8_lossy(&output.stdout).trim().to_string();
+        if token.is_empty() {
+            return Err(ConfigError::Message("auth_command printed no token".to_string()).into());
+        }
+        redact::register(&token);
+        let ttl = jwt_lifetime(&token)
+            .map(|lifetime| lifetime.saturating_sub(TOKEN_EXPIRY_MARGIN))
+            .unwrap_or(OPAQUE_TOKEN_TTL);
+        *cached = Some((token.clone(), Instant::now() + ttl));
+        Ok(token)
+    }
+
+    /// Helper for sending a request with retry logic.
+    ///
+    /// Transport errors and 429 responses are retried separately: transport errors quickly at a
+    /// fixed interval, 429s with exponential backoff or after the server's `Retry-After`. With
+    /// a deadline, an attempt is cut off when it passes, and a retry whose wait would outlast it
+    /// is not made.
+    async fn send_with_retry<F, Fut>(
+        &self,
+        request_func: F,
+        deadline: Option<Instant>,
+    ) -> Result<reqwest::Response>
+    where
+        F: Fn() -> Fut,
+        Fut: std::future::Future<Output = reqwest::Result<reqwest::Response>>,
+    {
+        let mut failures = 0;
+        let mut rate_limits = 0;
+        let remaining = || deadline.map(|at| at.saturating_duration_since(Instant::now()));
+        let deadline_exceeded = |deadline| Err(Error::DeadlineExceeded { deadline });
+        loop {
+            if let Some(limiter) = &self.rate_limiter {
+                limiter.acquire(self.debug).await;
+            }
+            let attempt = match (self.deadline, remaining()) {
+                (Some(deadline), Some(left)) => {
+                    match tokio::time::timeout(left, request_func()).await {
+                        Ok(attempt) => attempt,
+                        Err(_) => return deadline_exceeded(deadline),
+                    }
+                }
+                _ => request_func().await,
+            };
+            match attempt {
+                Ok(resp)
+                    if resp.status() == reqwest::StatusCode::TOO_MANY_REQUESTS
+                        && rate_limits < self.rate_limit_retry.retries =>
+                {
+                    let Some(delay) = rate_limit_delay(&resp, self.rate_limit_retry, rate_limits)
+                    else {
+                        return Ok(resp);
+                    };
+                    if let (Some(deadline), Some(left)) = (self.deadline, remaining()) {
+                        if delay >= left {
+                            return deadline_exceeded(deadline);
+                        }
+                    }
+                    rate_limits += 1;
+                    let delay_secs = format!("{:.1}", delay.as_secs_f64());
+                    let retries = self.rate_limit_retry.retries;
+                    warn!(
+                        "{}",
+                        tr!("rate_limited_retrying", delay_secs, rate_limits, retries)
+                    );
+                    tokio::time::sleep(delay).await;
+                }
+                Ok(resp) => {
+                    if let Some(id) = request_id(&resp) {
+                        if self.echo_request_ids {
+                            info!("{}", tr!("request_id", id));
+                        } else if self.debug {
+                            debug!("Request ID: {}", id);
+                        }
+                    }
+                    return Ok(resp);
+                }
+                Err(err) if failures < self.transport_retry.retries => {
+                    if let (Some(deadline), Some(left)) = (self.deadline, remaining()) {
+                        if self.transport_retry.delay >= left {
+                            error!("{}", tr!("request_failed", err));
+                            return deadline_exceeded(deadline);
+                        }
+                    }
+                    failures += 1;
+                    error!("{}", tr!("retry_attempt", failures, err));
+                    tokio::time::sleep(self.transport_retry.delay).await;
+                }
+                Err(err) => return Err(Error::Network(err)),
+            }
+        }
+    }
+
+    /// Posts a JSON body to an endpoint, recording or replaying the exchange when a cassette is set.
+    ///
+    /// Bodies carrying `messages` go through `preprocess_command` first and are then rebuilt
+    /// from `request_template`, if those are configured.
+    async fn post_json<T: Serialize>(
+        &self,
+        url: &str,
+        api_key: &str,
+        body: &T,
+    ) -> Result<reqwest::Response> {
+        self.post_json_until(url, api_key, body, self.request_deadline())
+            .await
+    }
+
+    /// `post_json` with a deadline shared with earlier requests.
+    async fn post_json_until<T: Serialize>(
+        &self,
+        url: &str,
+        api_key: &str,
+        body: &T,
+        deadline: Option<Instant>,
+    ) -> Result<reqwest::Response> {
+        let mut payload = serde_json::to_vec(body)?;
+        self.check_model_allowed(&payload)?;
+        if self.preprocess_command.is_some() || self.request_template.is_some() {
+            let mut body: serde_json::Value = serde_json::from_slice(&payload)?;
+            if body.get("messages").is_some() {
+                body = self.preprocess(body).await?;
+                if let Some(template) = &self.request_template {
+                    body = render_template(template, &body)?;
+                    if self.debug {
+                        debug!(
+                            "Templated request body: {}",
+                            redact::redact(&body.to_string())
+                        );
+                    }
+                }
+                payload = serde_json::to_vec(&body)?;
+            }
+        }
+        let request = || {
+            self.client
+                .post(url)
+                .header(reqwest::header::CONTENT_TYPE, "application/json")
+                .body(payload.clone())
+        };
+        self.dispatch_until(request, api_key, &payload, deadline)
+            .await
+    }
+
+    /// Sends a saved chat request body byte for byte, without `preprocess_command` or
+    /// `request_template`, and returns the response whatever its status.
+    ///
+    /// The endpoint and key are chosen from the body's `model`, as for any other chat request.
+    pub async fn send_raw(&self, body: Vec<u8>) -> Result<reqwest::Response> {
+        let request: serde_json::Value = serde_json::from_slice(&body)?;
+        let Some(model) = request.get("model").and_then(serde_json::Value::as_str) else {
+            return Err(Error::Input("the request has no \"model\" field".to_string()));
+        };
+        self.check_model_allowed(&body)?;
+        let (url, api_key) = if model.contains("codestral") {
+            (CODESTRAL_URL, &self.codestral_api_key)
+        } else {
+            (MISTRAL_URL, &self.mistral_api_key)
+        };
+        if self.debug {
+            debug!("Sending saved request to {}", url);
+        }
+        let request = || {
+            self.client
+                .post(url)
+                .header(reqwest::header::CONTENT_TYPE, "application/json")
+                .body(body.clone())
+        };
+        self.dispatch(request, api_key, &body).await
+    }
+
+    /// Sends a non-streaming chat request body like `send_raw` and returns the parsed response,
+    /// failing on an error status.
+    pub async fn send_raw_json(&self, body: Vec<u8>) -> Result<serde_json::Value> {
+        let response = Self::check_status(self.send_raw(body).await?).await?;
+        let body = self.read_body(response).await?;
+        Ok(serde_json::from_slice(&body)?)
+    }
+
+    /// Fails before anything is sent when `allowed_models` is set and the JSON `body` names a
+    /// model outside it.
+    fn check_model_allowed(&self, body: &[u8]) -> Result<()> {
+        if self.allowed_models.is_empty() {
+            return Ok(());
+        }
+        let body: serde_json::Value = serde_json::from_slice(body)?;
+        match body.get("model").and_then(serde_json::Value::as_str) {
+            Some(model) if !self.allowed_models.iter().any(|allowed| allowed == model) => {
+                Err(Error::Input(format!(
+                    "model {} is not in allowed_models ({})",
+                    model,
+                    self.allowed_models.join(", ")
+                )))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Runs a request body through `preprocess_command`, failing rather than sending the body
+    /// unchanged if the command fails, so a redaction step cannot be skipped silently.
+    async fn preprocess(&self, body: serde_json::Value) -> Result<serde_json::Value> {
+        let Some(command) = &self.preprocess_command else {
+            return Ok(body);
+        };
+        if self.debug {
+            debug!("Running preprocess_command on the request");
+        }
+        let mut child = tokio::process::Command::from(shell(command))
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()?;
+        // Feed stdin from a task so a large body cannot deadlock against the command's output.
+        let input = serde_json::to_vec(&body)?;
+        let stdin = child.stdin.take();
+        let writer = tokio::spawn(async move {
+            if let Some(mut stdin) = stdin {
+                // A command that ignores its input may exit before reading it; its output decides.
+                let _ = stdin.write_all(&input).await;
+            }
+        });
+        let output = child.wait_with_output().await?;
+        let _ = writer.await;
+        if !output.status.success() {
+            return Err(ConfigError::Message(format!(
+                "preprocess_command failed with {}: {}",
+                output.status,
+                String::from_utf8_lossy(&output.stderr).trim()
+            ))
+            .into());
+        }
+        let body: serde_json::Value = serde_json::from_slice(&output.stdout).map_err(|e| {
+            ConfigError::Message(format!("preprocess_command printed invalid JSON: {}", e))
+        })?;
+        if !body.is_object() {
+            return Err(ConfigError::Message(
+                "preprocess_command must print a JSON object".to_string(),
+            )
+            .into());
+        }
+        Ok(body)
+    }
+
+    /// Sends an authorized request built by `request`, recording or replaying it when a cassette
+    /// is set. `recorded` is what a recording stores as the request.
+    async fn dispatch<F>(
+        &self,
+        request: F,
+        api_key: &str,
+        recorded: &[u8],
+    ) -> Result<reqwest::Response>
+    where
+        F: Fn() -> reqwest::RequestBuilder,
+    {
+        self.dispatch_until(request, api_key, recorded, self.request_deadline())
+            .await
+    }
+
+    /// `dispatch` with a deadline shared with earlier requests.
+    async fn dispatch_until<F>(
+        &self,
+        request: F,
+        api_key: &str,
+        recorded: &[u8],
+        deadline: Option<Instant>,
+    ) -> Result<reqwest::Response>
+    where
+        F: Fn() -> reqwest::RequestBuilder,
+    {
+        // Replays never reach the network, so they need no token.
+        let token = match &self.cassette {
+            Some(Cassette {
+                mode: CassetteMode::Replay,
+                ..
+            }) => String::new(),
+            _ => self.bearer(api_key).await?,
+        };
+        let send = || {
+            request()
+                .header("Authorization", format!("Bearer {}", token))
+                .send()
+        };
+
+        let Some(cassette) = &self.cassette else {
+            return self.send_with_retry(send, deadline).await;
+        };
+        let (request_path, status_path, body_path) = cassette.next_paths();
+
+        match cassette.mode {
+            CassetteMode::Replay => {
+                if self.debug {
+                    debug!("Replaying response from {}", body_path.display());
+                }
+                let status = read_recording(&status_path)?;
+                let status = String::from_utf8_lossy(&status);
+                let status: u16 = status.trim().parse().map_err(|_| {
+                    Error::Parse(format!("invalid status in {}", status_path.display()))
+                })?;
+                let bytes = read_recording(&body_path)?;
+                http::Response::builder()
+                    .status(status)
+                    .body(bytes)
+                    .map(Into::into)
+                    .map_err(|e| Error::Parse(e.to_string()))
+            }
+            CassetteMode::Record => {
+                fs::write(&request_path, recorded)?;
+                let response = self.send_with_retry(send, deadline).await?;
+                fs::write(&status_path, response.status().as_str())?;
+                if self.debug {
+                    debug!("Recording response to {}", body_path.display());
+                }
+
+                let mut builder = http::Response::builder().status(response.status());
+                for (name, value) in response.headers() {
+                    builder = builder.header(name, value);
+                }
+                let mut file = fs::File::create(&body_path)?;
+                let stream = response.bytes_stream().inspect(move |chunk| {
+                    if let Ok(bytes) = chunk {
+                        if let Err(e) = file.write_all(bytes) {
+                            error!("{}", tr!("record_chunk_failed", e));
+                        }
+                    }
+                });
+                builder
+                    .body(reqwest::Body::wrap_stream(stream))
+                    .map(Into::into)
+                    .map_err(|e| Error::Parse(e.to_string()))
+            }
+        }
+    }
+
+    /// Turns a non-success response into an `Auth` or `Api` error.
+    async fn check_status(response: reqwest::Response) -> Result<reqwest::Response> {
+        let status = response.status();
+        if status.is_success() {
+            return Ok(response);
+        }
+        let request_id = request_id(&response);
+        let message = response.text().await.unwrap_or_default();
+        Err(Error::from_status(status, message, request_id))
+    }
+
+    /// Reads the whole body of a non-streaming response, chunk by chunk, failing as soon as it
+    /// passes `max_response_bytes` rather than buffering a pathological response in full.
+    pub async fn read_body(&self, mut response: reqwest::Response) -> Result<Vec<u8>> {
+        let limit = self.max_response_bytes;
+        let too_large = |bytes: u64| bytes > limit;
+        if response.content_length().is_some_and(too_large) {
+            return Err(Error::ResponseTooLarge { limit });
+        }
+        let mut body = Vec::new();
+        while let Some(chunk) = response.chunk().await? {
+            if too_large((body.len() + chunk.len()) as u64) {
+                return Err(Error::ResponseTooLarge { limit });
+            }
+            body.extend_from_slice(&chunk);
+        }
+        Ok(body)
+    }
+
+    /// Starts a streaming chat completion and returns its events as they arrive.
+    pub async fn chat_events(
+        &self,
+        model: &str,
+        messages: Vec<RequestMessage>,
+        params: &ChatParams,
+    ) -> Result<ChatStream> {
+        self.chat_events_until(model, messages, params, self.request_deadline())
+            .await
+    }
+
+    /// When a request started now must be answered by, if a deadline is configured.
+    pub fn request_deadline(&self) -> Option<Instant> {
+        self.deadline.map(|deadline| Instant::now() + deadline)
+    }
+
+    /// `chat_events` with a deadline shared with earlier requests.
+    async fn chat_events_until(
+        &self,
+        model: &str,
+        messages: Vec<RequestMessage>,
+        params: &ChatParams,
+        deadline: Option<Instant>,
+    ) -> Result<ChatStream> {
+        if self.debug {
+            debug!("Sending streaming request to {} API", model);
+            debug!(
+                "Using URL: {}",
+                if model.contains("codestral") {
+                    CODESTRAL_URL
+                } else {
+                    MISTRAL_URL
+                }
+            );
+        }
+
+        let request = ChatRequest {
+            model: model.to_string(),
+            messages,
+            stream: true,
+            max_tokens: params.max_tokens,
+            reasoning_effort: params
+                .reasoning_effort
+                .clone()
+                .filter(|_| is_reasoning_model(model)),
+            logprobs: None,
+            top_logprobs: None,
+            service_tier: params.service_tier.clone(),
+            metadata: self.metadata.clone(),
+        };
+        if params.reasoning_effort.is_some() && request.reasoning_effort.is_none() {
+            warn!("{}", tr!("not_reasoning_model", model));
+        }
+
+        if self.debug {
+            debug!(
+                "Request body: {}",
+                redact::redact(&serde_json::to_string(&request)?)
+            );
+        }
+
+        let url = if model.contains("codestral") {
+            CODESTRAL_URL
+        } else {
+            MISTRAL_URL
+        };
+
+        let api_key = if model.contains("codestral") {
+            &self.codestral_api_key
+        } else {
+            &self.mistral_api_key
+        };
+
+        let response = self
+            .post_json_until(url, api_key, &request, deadline)
+            .await?;
+
+        if self.debug {
+            debug!("Response status: {}", response.status());
+        }
+        let response = Self::check_status(response).await?;
+        Ok(ChatStream::new(response, self.debug)
+            .with_content_path(self.stream_content_path.clone()))
+    }
+
+    /// Streams chat completions from the API and prints them to stdout.
+    ///
+    /// When the last message is an assistant `prefix`, only the continuation is printed, even if
+    /// the model repeats the prefix first, unless `StreamOptions::print_prefix` asks for the
+    /// prefix to be printed ahead of it.
+    pub async fn chat_stream(
+        &self,
+        model: &str,
+        messages: Vec<RequestMessage>,
+        params: &ChatParams,
+        options: &StreamOptions,
+    ) -> Result<StreamOutcome> {
+        // When piped, nobody watches tokens arrive, so output is buffered and flushed at the end.
+        let interactive = std::io::stdout().is_terminal();
+        let stdout = tokio::io::stdout();
+        self.chat_stream_to(stdout, interactive, model, messages, params, options)
+            .await
+    }
+
+    /// `chat_stream`, printing to `output` instead of stdout.
+    async fn chat_stream_to<W: AsyncWrite + Unpin>(
+        &self,
+        output: W,
+        interactive: bool,
+        model: &str,
+        messages: Vec<RequestMessage>,
+        params: &ChatParams,
+        options: &StreamOptions,
+    ) -> Result<StreamOutcome> {
+        let prefix = messages
+            .last()
+            .filter(|message| message.role == "assistant" && message.prefix)
+            .map(|message| message.content.clone())
+            .unwrap_or_default();
+        let mut outcome = StreamOutcome {
+            content: prefix.clone(),
+            ..Default::default()
+        };
+        // Text held back while it could still be the model repeating the prefix.
+        let mut echo = (!prefix.is_empty()).then(String::new);
+        // Drop what earlier requests noted, so only this request's connection is reported.
+        self.connect_timings.take();
+        let started = Instant::now();
+        let mut fallbacks = options.fallback_models.iter();
+        // Larger-context models already tried, so a fallback back down the list cannot loop.
+        let mut tried_larger: Vec<String> = Vec::new();
+        let deadline = options.deadline.or_else(|| self.request_deadline());
+        outcome.model = model.to_string();
+        let stream = loop {
+            if let (Some(at), Some(limit)) = (deadline, self.deadline) {
+                if at <= Instant::now() {
+                    return Err(Error::DeadlineExceeded { deadline: limit });
+                }
+            }
+            let request = within(
+                options.first_token_timeout,
+                self.chat_events_until(&outcome.model, messages.clone(), params, deadline),
+            );
+            let stream = match &options.cancel {
+                Some(token) => tokio::select! {
+                    _ = token.cancelled() => {
+                        outcome.cancelled = true;
+                        return Ok(outcome);
+                    }
+                    stream = request => stream,
+                },
+                None => request.await,
+            };
+            let Some(stream) = stream else {
+                warn!("{}", tr!("first_token_timeout"));
+                outcome.timed_out = true;
+                return Ok(outcome);
+            };
+            let stream = match stream {
+                Err(e) if e.is_context_length_exceeded() => {
+                    // Climb from the model in use, which a fallback may have changed.
+                    let larger = &options.larger_context_models;
+                    let after = larger
+                        .iter()
+                        .position(|m| *m == outcome.model)
+                        .map_or(0, |index| index + 1);
+                    match larger[after..].iter().find(|m| !tried_larger.contains(m)) {
+                        Some(model) => {
+                            warn!("{}", tr!("larger_context_retry", outcome.model, e, model));
+                            tried_larger.push(model.clone());
+                            outcome.model = model.clone();
+                            continue;
+                        }
+                        None if tried_larger.is_empty() => return Err(e),
+                        None => {
+                            return Err(Error::ContextLengthExceeded {
+                                tried: tried_larger,
+                                error: Box::new(e),
+                            })
+                        }
+                    }
+                }
+                stream => stream,
+            };
+            match (stream, fallbacks.next()) {
+                (Err(e), Some(fallback)) if e.is_model_unavailable() => {
+                    warn!("{}", tr!("model_fallback", outcome.model, e, fallback));
+                    outcome.model = fallback.clone();
+                }
+                (stream, _) => break stream?,
+            }
+        };
+        let mut stream = match &options.cancel {
+            Some(token) => stream.with_cancellation(token.clone()),
+            None => stream,
+        };
+        outcome.timings.first_byte = Some(started.elapsed());
+        outcome.request_id = stream.request_id().map(str::to_string);
+        // The first event gets what is left of the first-token timeout, later ones the idle timeout.
+        let mut limit = options
+            .first_token_timeout
+            .map(|timeout| timeout.saturating_sub(started.elapsed()));
+        let mut stdout = tokio::io::BufWriter::new(output);
+        let mut stderr = tokio::io::stderr();
+        let mut refused = false;
+        // Whether a reasoning trace is being printed and still needs its closing newline.
+        let mut reasoning = false;
+        let mut wrapper = options.wrap.map(Wrapper::new);
+        // A CR that ended the previous delta, waiting to see whether an LF follows.
+        let mut pending_cr = false;
+        // Whether the text printed so far ends a line, so the final newline is written only once.
+        let mut at_line_start = true;
+        let mut redactor = options.redact.as_ref().map(OutputPatterns::redactor);
+        // When text written with `flush_interval` set must reach the terminal.
+        let mut flush_due = None;
+
+        // Printed once the response has started, so a failed request prints nothing.
+        if options.print_prefix && !prefix.is_empty() {
+            for sink in &options.sinks {
+                sink.write(&prefix)?;
+            }
+            if options.stdout && options.jsonl {
+                let line = serde_json::json!({ "content": prefix, "index": 0 });
+                write_json_line(&mut stdout, &line, options).await?;
+            } else if options.stdout {
+                let text = match &mut wrapper {
+                    Some(wrapper) => wrapper.push(&prefix),
+                    None => prefix.clone(),
+                };
+                let text = options.eol.normalize(&text, &mut pending_cr);
+                if !text.is_empty() {
+                    at_line_start = text.ends_with('\n');
+                }
+                write_paced(&mut stdout, &text, options, interactive, &mut flush_due).await?;
+            }
+        }
+
+        loop {
+            // The deadline covers the body too, so a stalled stream cannot run past it.
+            let remaining = deadline.map(|at| at.saturating_duration_since(Instant::now()));
+            let wait = match (limit, remaining) {
+                (Some(limit), Some(remaining)) => Some(limit.min(remaining)),
+                (limit, remaining) => limit.or(remaining),
+            };
+            let next = within(wait, stream.next());
+            tokio::pin!(next);
+            let event = loop {
+                let Some(due) = flush_due else {
+                    break next.await;
+                };
+                tokio::select! {
+                    event = &mut next => break event,
+                    _ = tokio::time::sleep_until(due) => {
+                        stdout.flush().await?;
+                        flush_due = None;
+                    }
+                }
+            };
+            let Some(event) = event else {
+                if deadline.is_some_and(|at| at <= Instant::now()) {
+                    warn!("{}", tr!("stream_deadline"));
+                } else {
+                    let idle = format!("{:?}", limit.unwrap_or_default());
+                    warn!("{}", tr!("stream_idle", idle));
+                }
+                outcome.timed_out = true;
+                break;
+            };
+            let Some(event) = event else {
+                break;
+            };
+            limit = options.idle_timeout;
+            // A citation becomes a `[n]` marker in the text, numbered by its first appearance.
+            let event = match event {
+                Ok(StreamEvent::Citation(citation)) => {
+                    let n = match outcome.citations.iter().position(|seen| *seen == citation) {
+                        Some(index) => index + 1,
+                        None => {
+                            outcome.citations.push(citation);
+                            outcome.citations.len()
+                        }
+                    };
+                    Ok(StreamEvent::Content(format!("[{}]", n)))
+                }
+                event => event,
+            };
+            if let Ok(
+                StreamEvent::Content(_) | StreamEvent::Reasoning(_) | StreamEvent::Refusal(_),
+            ) = &event
+            {
+                outcome
+                    .timings
+                    .first_token
+                    .get_or_insert_with(|| started.elapsed());
+            }
+            match event {
+                Ok(StreamEvent::Content(mut content)) => {
+                    if let Some(held) = &mut echo {
+                        held.push_str(&content);
+                        if prefix.starts_with(held.as_str()) {
+                            if held.len() == prefix.len() {
+                                echo = None;
+                            }
+                            continue;
+                        }
+                        content = match held.strip_prefix(prefix.as_str()) {
+                            Some(rest) => rest.to_string(),
+                            None => std::mem::take(held),
+                        };
+                        echo = None;
+                    }
+                    if let Some(redactor) = &mut redactor {
+                        content = redactor.push(&content);
+                    }
+                    // Nothing to show, as in the first delta, which usually carries only the role.
+                    if content.is_empty() {
+                        continue;
+                    }
+                    if reasoning {
+                        stderr.write_all(b"\n\n").await?;
+                        stderr.flush().await?;
+                        reasoning = false;
+                    }
+                    outcome.content.push_str(&content);
+                    for sink in &options.sinks {
+                        sink.write(&content)?;
+                    }
+                    if !options.stdout {
+                        continue;
+                    }
+                    if options.jsonl {
+                        // Only one choice is requested, so its index is always 0.
+                        let line = serde_json::json!({ "content": content, "index": 0 });
+                        write_json_line(&mut stdout, &line, options).await?;
+                        continue;
+                    }
+                    let text = match &mut wrapper {
+                        Some(wrapper) => wrapper.push(&content),
+                        None => content.clone(),
+                    };
+                    let text = options.eol.normalize(&text, &mut pending_cr);
+                    if !text.is_empty() {
+                        at_line_start = text.ends_with('\n');
+                    }
+                    write_paced(&mut stdout, &text, options, interactive, &mut flush_due).await?;
+                }
+                Ok(StreamEvent::Refusal(refusal)) => {
+                    // Refusals go to stderr so they never mix with the answer.
+                    if !refused {
+                        stderr.write_all(tr!("refused_prefix").as_bytes()).await?;
+                        refused = true;
+                    }
+                    stderr.write_all(refusal.as_bytes()).await?;
+                    stderr.flush().await?;
+                }
+                Ok(StreamEvent::Reasoning(trace)) => {
+                    if options.show_reasoning {
+                        if !reasoning {
+                            stderr.write_all(tr!("reasoning_prefix").as_bytes()).await?;
+                            reasoning = true;
+                        }
+                        stderr.write_all(trace.as_bytes()).await?;
+                        stderr.flush().await?;
+                    }
+                }
+                Ok(StreamEvent::Finish(reason)) => outcome.finish_reason = Some(reason),
+                Ok(StreamEvent::Usage(usage)) => outcome.usage = Some(usage),
+                Ok(StreamEvent::Citation(_)) => unreachable!("citations become content above"),
+                Err(e) => {
+                    error!("{}", tr!("streaming_failed", e));
+                    if self.debug {
+                        debug!("Chunk error: {:?}", e);
+                    }
+                    outcome.interrupted = true;
+                    break;
+                }
+            }
+        }
+
+        outcome.timings.total = Some(started.elapsed());
+        (outcome.timings.dns, outcome.timings.connect) = self.connect_timings.take();
+        // Text the redactor held back is settled now that nothing more can arrive.
+        let held = match &mut redactor {
+            Some(redactor) => {
+                let held = redactor.finish();
+                outcome.redactions = redactor.count();
+                held
+            }
+            None => String::new(),
+        };
+        if !held.is_empty() {
+            if reasoning {
+                stderr.write_all(b"\n\n").await?;
+                stderr.flush().await?;
+                reasoning = false;
+            }
+            outcome.content.push_str(&held);
+            for sink in &options.sinks {
+                sink.write(&held)?;
+            }
+            if options.stdout && options.jsonl {
+                let line = serde_json::json!({ "content": held, "index": 0 });
+                write_json_line(&mut stdout, &line, options).await?;
+            } else if options.stdout {
+                let text = match &mut wrapper {
+                    Some(wrapper) => wrapper.push(&held),
+                    None => held,
+                };
+                let text = options.eol.normalize(&text, &mut pending_cr);
+                if !text.is_empty() {
+                    at_line_start = text.ends_with('\n');
+                }
+                write_paced(&mut stdout, &text, options, interactive, &mut flush_due).await?;
+            }
+        }
+        // The sources are listed after the answer; JSON Lines output has them in its `done` line.
+        if !outcome.citations.is_empty() {
+            let mut references = format!("\n\n{}\n", tr!("references"));
+            for (index, citation) in outcome.citations.iter().enumerate() {
+                references.push_str(&format!("[{}] {}\n", index + 1, citation));
+            }
+            for sink in &options.sinks {
+                sink.write(&references)?;
+            }
+            if options.stdout && !options.jsonl {
+                let text = match &mut wrapper {
+                    Some(wrapper) => wrapper.push(&references),
+                    None => references,
+                };
+                let text = options.eol.normalize(&text, &mut pending_cr);
+                at_line_start = text.ends_with('\n');
+                write_paced(&mut stdout, &text, options, interactive, &mut flush_due).await?;
+            }
+        }
+        outcome.cancelled = options
+            .cancel
+            .as_ref()
+            .is_some_and(|token| token.is_cancelled());
+        let ended = !outcome.cancelled && !outcome.timed_out && !outcome.interrupted;
+        // Some servers drop the connection on errors without `[DONE]` or a finish reason.
+        if ended && !stream.saw_done() && outcome.finish_reason.is_none() {
+            warn!("{}", tr!("stream_no_done"));
+        }
+        if ended && outcome.content.is_empty() && !refused {
+            warn!("{}", tr!("empty_response"));
+        }
+        if options.stdout {
+            let mut rest = match &mut wrapper {
+                Some(wrapper) => options.eol.normalize(&wrapper.finish(), &mut pending_cr),
+                None => String::new(),
+            };
+            if pending_cr {
+                rest.push_str(options.eol.as_str());
+            }
+            if !rest.is_empty() {
+                at_line_start = rest.ends_with('\n');
+            }
+            write_paced(&mut stdout, &rest, options, interactive, &mut flush_due).await?;
+            if options.jsonl {
+                let mut line = serde_json::json!({
+                    "done": true,
+                    "finish_reason": outcome.finish_reason,
+                    "usage": outcome.usage,
+                });
+                if self.echo_request_ids {
+                    line["request_id"] = serde_json::json!(outcome.request_id);
+                }
+                if !outcome.citations.is_empty() {
+                    line["citations"] = serde_json::json!(outcome.citations);
+                }
+                write_json_line(&mut stdout, &line, options).await?;
+            } else if options.trailing_newline && !at_line_start {
+                stdout.write_all(options.eol.as_str().as_bytes()).await?;
+            }
+            stdout.flush().await?;
+        }
+        if refused || reasoning {
+            stderr.write_all(b"\n").await?;
+            stderr.flush().await?;
+        }
+
+        Ok(outcome)
+    }
+
+    /// Tests API connectivity with a minimal request to each API, logging how it went.
+    ///
+    /// Both APIs are always tried; a failure of one is recorded in its result, not returned.
+    pub async fn test_connection(&self) -> Result<Vec<ConnectionTest>> {
+        if self.debug {
+            debug!("Testing API connection...");
+        }
+        let messages = vec![RequestMessage {
+            role: "user".to_string(),
+            content: "Test".to_string(),
+            ..Default::default()
+        }];
+        let endpoints = [
+            (
+                "mistral",
+                "Mistral",
+                MISTRAL_MODEL,
+                MISTRAL_URL,
+                &self.mistral_api_key,
+                Some(1),
+            ),
+            (
+                "codestral",
+                "Codestral",
+                CODESTRAL_MODEL,
+                CODESTRAL_URL,
+                &self.codestral_api_key,
+                None,
+            ),
+        ];
+        let mut results = Vec::new();
+        for (name, title, model, url, api_key, max_tokens) in endpoints {
+            let label = name.to_uppercase();
+            let request = ChatRequest {
+                model: model.to_string(),
+                messages: messages.clone(),
+                stream: false,
+                max_tokens,
+                reasoning_effort: None,
+                logprobs: None,
+                top_logprobs: None,
+                service_tier: None,
+                metadata: BTreeMap::new(),
+            };
+            if self.debug {
+                debug!(
+                    "{} request body: {}",
+                    label,
+                    redact::redact(&serde_json::to_string(&request)?)
+                );
+            }
+
+            let start = Instant::now();
+            let response = match self.post_json(url, api_key, &request).await {
+                Ok(response) => response,
+                Err(e) => {
+                    error!("{}", tr!("connection_failed", label, e));
+                    results.push(ConnectionTest {
+                        name,
+                        title,
+                        status: None,
+                        latency: start.elapsed(),
+                        error: Some(e),
+                    });
+                    continue;
+                }
+            };
+            let latency = start.elapsed();
+            let status = response.status();
+            if self.debug {
+                debug!("{} status: {}", label, status);
+            }
+            let error = if status.is_success() {
+                info!("{}", tr!("connection_succeeded", label));
+                None
+            } else {
+                error!("{}", tr!("connection_failed", label, status));
+                let id = request_id(&response);
+                if let Some(id) = &id {
+                    error!("{}", tr!("connection_request_id", label, id));
+                }
+                let text = response.text().await.unwrap_or_default();
+                if self.debug {
+                    debug!("{} response body: {}", label, redact::redact(&text));
+                }
+                Some(Error::from_status(status, text, id))
+            };
+            results.push(ConnectionTest {
+                name,
+                title,
+                status: Some(status),
+                latency,
+                error,
+            });
+        }
+        Ok(results)
+    }
+
+    /// Checks that both APIs are reachable by listing their models, which costs no tokens.
+    ///
+    /// Each endpoint is tried once, without retries or cassettes, so the latency is real.
+    pub async fn ping(&self) -> Vec<PingResult> {
+        let endpoints = [
+            ("Mistral", MISTRAL_MODELS_URL, &self.mistral_api_key),
+            ("Codestral", CODESTRAL_MODELS_URL, &self.codestral_api_key),
+        ];
+        let mut results = Vec::new();
+        for (name, url, api_key) in endpoints {
+            let token = match self.bearer(api_key).await {
+                Ok(token) => token,
+                Err(e) => {
+                    results.push(PingResult {
+                        name,
+                        url,
+                        result: Err(e),
+                    });
+                    continue;
+                }
+            };
+            let start = Instant::now();
+            let result = self
+                .client
+                .get(url)
+                .header("Authorization", format!("Bearer {}", token))
+                .timeout(Duration::from_secs(10))
+                .send()
+                .await
+                .map(|response| (response.status(), start.elapsed()))
+                .map_err(Error::from);
+            if self.debug {
+                debug!(
+                    "Ping {}: {:?}",
+                    url,
+                    result.as_ref().map(|(status, _)| status)
+                );
+            }
+            results.push(PingResult { name, url, result });
+        }
+        results
+    }
+
+    /// Downloads a web page and returns its readable text, reading at most `max_bytes` of it.
+    ///
+    /// This is a plain GET without the API key or cassette, since the page is not an API call.
+    pub async fn fetch_page(&self, url: &str, max_bytes: usize) -> Result<String> {
+        if self.debug {
+            debug!("Fetching {}", url);
+        }
+        let client = self.page_client.as_ref().unwrap_or(&self.client);
+        let response = client.get(url).send().await?;
+        let status = response.status();
+        if !status.is_success() {
+            return Err(Error::Input(format!(
+                "fetching {} failed with {}",
+                url, status
+            )));
+        }
+        let content_type = response
+            .headers()
+            .get(reqwest::header::CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .unwrap_or("text/html")
+            .to_lowercase();
+        if !content_type.starts_with("text/") && !content_type.contains("html") {
+            return Err(Error::Input(format!(
+                "{} is {}, not a text or HTML page",
+                url, content_type
+            )));
+        }
+
+        let mut body = Vec::new();
+        let mut chunks = response.bytes_stream();
+        while let Some(chunk) = chunks.next().await {
+            body.extend_from_slice(&chunk?);
+            if body.len() >= max_bytes {
+                warn!("{}", tr!("page_too_large", url, max_bytes));
+                body.truncate(max_bytes);
+                break;
+            }
+        }
+        let body = String::from_utf8_lossy(&body);
+        if content_type.contains("html") {
+            Ok(crate::html::to_text(&body))
+        } else {
+            Ok(body.into_owned())
+        }
+    }
+
+    /// Builds the messages sent to Codestral for a code snippet.
+    pub fn code_messages(code: String) -> Vec<RequestMessage> {
+        vec![RequestMessage {
+            role: "user".to_string(),
+            content: code,
+            ..Default::default()
+        }]
+    }
+
+    /// Analyzes code with `model`, normally Codestral, prepending the code system prompt if one
+    /// is set.
+    pub async fn analyze_code(
+        &self,
+        model: &str,
+        mut messages: Vec<RequestMessage>,
+        params: &ChatParams,
+    ) -> Result<String> {
+        if self.debug {
+            debug!("Sending code to {} API", model);
+        }
+        if let Some(system) = &self.code_system_prompt {
+            messages.insert(
+                0,
+                RequestMessage {
+                    role: "system".to_string(),
+                    content: system.clone(),
+                    ..Default::default()
+                },
+            );
+        }
+
+        let request = ChatRequest {
+            model: model.to_string(),
+            messages,
+            stream: false,
+            max_tokens: params.max_tokens,
+            reasoning_effort: params
+                .reasoning_effort
+                .clone()
+                .filter(|_| is_reasoning_model(model)),
+            logprobs: None,
+            top_logprobs: None,
+            service_tier: params.service_tier.clone(),
+            metadata: self.metadata.clone(),
+        };
+
+        if self.debug {
+            debug!(
+                "Analyze code request: {}",
+                redact::redact(&serde_json::to_string(&request)?)
+            );
+        }
+
+        let (url, api_key) = if model.contains("codestral") {
+            (CODESTRAL_URL, &self.codestral_api_key)
+        } else {
+            (MISTRAL_URL, &self.mistral_api_key)
+        };
+        let response = self.post_json(url, api_key, &request).await?;
+        let body = self.read_body(Self::check_status(response).await?).await?;
+        let response: ChatResponse = serde_json::from_slice(&body)?;
+
+        if let Some(choice) = response.choices.first() {
+            Ok(choice.message.content.clone())
+        } else {
+            Err(Error::Parse(format!(
+                "Empty response received from {} API",
+                model
+            )))
+        }
+    }
+
+    /// Sends a non-streaming chat request asking for log-probabilities.
+    pub async fn chat_logprobs(
+        &self,
+        model: &str,
+        messages: Vec<RequestMessage>,
+        params: &ChatParams,
+        top_logprobs: Option<u8>,
+    ) -> Result<LogprobsAnswer> {
+        let request = ChatRequest {
+            model: model.to_string(),
+            messages,
+            stream: false,
+            max_tokens: params.max_tokens,
+            reasoning_effort: params
+                .reasoning_effort
+                .clone()
+                .filter(|_| is_reasoning_model(model)),
+            logprobs: Some(true),
+            top_logprobs,
+            service_tier: params.service_tier.clone(),
+            metadata: self.metadata.clone(),
+        };
+        if self.debug {
+            debug!(
+                "Logprobs request: {}",
+                redact::redact(&serde_json::to_string(&request)?)
+            );
+        }
+
+        let (url, api_key) = if model.contains("codestral") {
+            (CODESTRAL_URL, &self.codestral_api_key)
+        } else {
+            (MISTRAL_URL, &self.mistral_api_key)
+        };
+        let response = self.post_json(url, api_key, &request).await?;
+        let body = self.read_body(Self::check_status(response).await?).await?;
+        let response: ChatResponse = serde_json::from_slice(&body)?;
+        let Some(choice) = response.choices.into_iter().next() else {
+            return Err(Error::Parse("Empty response received from the API".to_string()));
+        };
+        Ok(LogprobsAnswer {
+            content: choice.message.content,
+            finish_reason: choice.finish_reason,
+            tokens: choice.logprobs.map(|l| l.content).unwrap_or_default(),
+        })
+    }
+
+    /// Extracts text from a PDF or image using the OCR endpoint, returning Markdown.
+    pub async fn ocr(&self, path: &Path) -> Result<String> {
+        let extension = path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .map(str::to_lowercase)
+            .unwrap_or_default();
+        let mime = match extension.as_str() {
+            "pdf" => "application/pdf",
+            "png" => "image/png",
+            "jpg" | "jpeg" => "image/jpeg",
+            "gif" => "image/gif",
+            "webp" => "image/webp",
+            "avif" => "image/avif",
+            _ => {
+                return Err(Error::Input(format!(
+                    "unsupported document type for OCR: {}",
+                    path.display()
+                )))
+            }
+        };
+
+        let data = fs::read(path)?;
+        let uri = format!("data:{};base64,{}", mime, BASE64.encode(data));
+        let document = if mime == "application/pdf" {
+            OcrDocument::DocumentUrl { document_url: uri }
+        } else {
+            OcrDocument::ImageUrl { image_url: uri }
+        };
+        let request = OcrRequest {
+            model: OCR_MODEL.to_string(),
+            document,
+        };
+
+        if self.debug {
+            debug!("Sending {} ({}) to OCR API", path.display(), mime);
+        }
+
+        let response = self
+            .post_json(MISTRAL_OCR_URL, &self.mistral_api_key, &request)
+            .await?;
+        let body = self.read_body(Self::check_status(response).await?).await?;
+        let response: OcrResponse = serde_json::from_slice(&body)?;
+
+        let pages: Vec<String> = response
+            .pages
+            .into_iter()
+            .map(|page| page.markdown)
+            .collect();
+        Ok(pages.join("\n\n"))
+    }
+
+    /// Uploads a file to the files API for the given purpose, e.g. `batch` or `fine-tune`.
+    pub async fn upload_file(&self, path: &Path, purpose: &str) -> Result<FileObject> {
+        let data = fs::read(path)?;
+        let filename = path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .ok_or_else(|| Error::Input(format!("not a file: {}", path.display())))?;
+
+        if self.debug {
+            debug!(
+                "Uploading {} ({} bytes) for {}",
+                filename,
+                data.len(),
+                purpose
+            );
+        }
+
+        let request = || {
+            let part = reqwest::multipart::Part::bytes(data.clone()).file_name(filename.clone());
+            let form = reqwest::multipart::Form::new()
+                .text("purpose", purpose.to_string())
+                .part("file", part);
+            self.client.post(MISTRAL_FILES_URL).multipart(form)
+        };
+        let recorded = serde_json::json!({ "file": filename, "purpose": purpose });
+        let response = self
+            .dispatch(
+                request,
+                &self.mistral_api_key,
+                &serde_json::to_vec(&recorded)?,
+            )
+            .await?;
+        let body = self.read_body(Self::check_status(response).await?).await?;
+        Ok(serde_json::from_slice(&body)?)
+    }
+
+    /// Lists the models available with the Mistral API key.
+    pub async fn list_models(&self) -> Result<Vec<ModelInfo>> {
+        let request = || self.client.get(MISTRAL_MODELS_URL);
+        let response = self.dispatch(request, &self.mistral_api_key, b"").await?;
+        let body = self.read_body(Self::check_status(response).await?).await?;
+        let list: ModelList = serde_json::from_slice(&body)?;
+        Ok(list.data)
+    }
+
+    /// Lists the files stored with the files API.
+    pub async fn list_files(&self) -> Result<Vec<FileObject>> {
+        let request = || self.client.get(MISTRAL_FILES_URL);
+        let response = self.dispatch(request, &self.mistral_api_key, b"").await?;
+        let body = self.read_body(Self::check_status(response).await?).await?;
+        let list: FileList = serde_json::from_slice(&body)?;
+        Ok(list.data)
+    }
+
+    /// Deletes a file from the files API.
+    pub async fn delete_file(&self, id: &str) -> Result<()> {
+        let url = format!("{}/{}", MISTRAL_FILES_URL, id);
+        if self.debug {
+            debug!("Deleting file {}", id);
+        }
+        let request = || self.client.delete(&url);
+        let response = self
+            .dispatch(request, &self.mistral_api_key, id.as_bytes())
+            .await?;
+        Self::check_status(response).await?;
+        Ok(())
+    }
+
+    /// Lists the batch jobs of the Mistral API key, most recent first.
+    pub async fn list_batch_jobs(&self) -> Result<Vec<BatchJob>> {
+        let request = || self.client.get(MISTRAL_BATCH_JOBS_URL);
+        let response = self.dispatch(request, &self.mistral_api_key, b"").await?;
+        let body = self.read_body(Self::check_status(response).await?).await?;
+        let list: BatchJobList = serde_json::from_slice(&body)?;
+        Ok(list.data)
+    }
+
+    /// Fetches the current state of a batch job.
+    pub async fn batch_job(&self, id: &str) -> Result<BatchJob> {
+        let url = format!("{}/{}", MISTRAL_BATCH_JOBS_URL, id);
+        let request = || self.client.get(&url);
+        let response = self
+            .dispatch(request, &self.mistral_api_key, id.as_bytes())
+            .await?;
+        let body = self.read_body(Self::check_status(response).await?).await?;
+        Ok(serde_json::from_slice(&body)?)
+    }
+
+    /// Asks the API to cancel a batch job, returning the job as it stands afterwards.
+    pub async fn cancel_batch_job(&self, id: &str) -> Result<BatchJob> {
+        let url = format!("{}/{}/cancel", MISTRAL_BATCH_JOBS_URL, id);
+        if self.debug {
+            debug!("Cancelling batch job {}", id);
+        }
+        let request = || self.client.post(&url);
+        let response = self
+            .dispatch(request, &self.mistral_api_key, id.as_bytes())
+            .await?;
+        let body = self.read_body(Self::check_status(response).await?).await?;
+        Ok(serde_json::from_slice(&body)?)
+    }
+}
+
+/// Returns the server-assigned request ID of a response, if it carries one.
+pub(crate) fn request_id(response: &reqwest::Response) -> Option<String> {
+    REQUEST_ID_HEADERS.iter().find_map(|name| {
+        response
+            .headers()
+            .get(*name)
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_string)
+    })
+}
+
+/// How long to wait before retry number `retry` (counting from zero) of a 429 response: the
+/// server's `Retry-After` in seconds if it sent one, otherwise `policy.delay` doubled per retry.
+/// `None` means the server asked for a wait too long to be worth it.
+fn rate_limit_delay(
+    response: &reqwest::Response,
+    policy: RetryPolicy,
+    retry: u32,
+) -> Option<Duration> {
+    let retry_after = response
+        .headers()
+        .get(reqwest::header::RETRY_AFTER)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.trim().parse::<u64>().ok())
+        .map(Duration::from_secs);
+    match retry_after {
+        Some(wait) if wait > MAX_RETRY_AFTER => None,
+        Some(wait) => Some(wait),
+        None => Some(policy.delay.saturating_mul(2u32.saturating_pow(retry))),
+    }
+}
+
+/// Writes `text` to stdout, pausing after each character when a typewriter delay is set.
+///
+/// Output is flushed right away only when `interactive`. Cancelling `options.cancel` cuts the
+/// pacing short, so Ctrl-C takes effect immediately.
+async fn write_paced<W: AsyncWrite + Unpin>(
+    stdout: &mut tokio::io::BufWriter<W>,
+    text: &str,
+    options: &StreamOptions,
+    interactive: bool,
+    flush_due: &mut Option<tokio::time::Instant>,
+) -> Result<()> {
+    match options.typewriter {
+        Some(delay) => {
+            let cancelled = options.cancel.clone().unwrap_or_default();
+            let mut buf = [0; 4];
+            for ch in text.chars() {
+                if cancelled.is_cancelled() {
+                    break;
+                }
+                stdout
+                    .write_all(ch.encode_utf8(&mut buf).as_bytes())
+                    .await?;
+                stdout.flush().await?;
+                tokio::select! {
+                    _ = cancelled.cancelled() => {}
+                    _ = tokio::time::sleep(delay) => {}
+                }
+            }
+        }
+        None => {
+            stdout.write_all(text.as_bytes()).await?;
+            if !interactive || text.is_empty() {
+                return Ok(());
+            }
+            let now = tokio::time::Instant::now();
+            match (options.flush_interval, *flush_due) {
+                (None, _) => stdout.flush().await?,
+                (Some(_), Some(due)) if due <= now => {
+                    stdout.flush().await?;
+                    *flush_due = None;
+                }
+                (Some(_), Some(_)) => {}
+                (Some(interval), None) => *flush_due = Some(now + interval),
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Awaits `future`, giving up with `None` after `limit` if one is set.
+async fn within<F: std::future::Future>(limit: Option<Duration>, future: F) -> Option<F::Output> {
+    match limit {
+        Some(limit) => tokio::time::timeout(limit, future).await.ok(),
+        None => Some(future.await),
+    }
+}
+
+/// Writes `value` as one line of JSON, or indented with `options.pretty`, and flushes it, since
+/// consumers read the objects as they come.
+async fn write_json_line<W: AsyncWrite + Unpin>(
+    stdout: &mut tokio::io::BufWriter<W>,
+    value: &serde_json::Value,
+    options: &StreamOptions,
+) -> Result<()> {
+    let mut line = if options.pretty {
+        options
+            .eol
+            .normalize(&serde_json::to_string_pretty(value)?, &mut false)
+    } else {
+        serde_json::to_string(value)?
+    };
+    line.push_str(options.eol.as_str());
+    stdout.write_all(line.as_bytes()).await?;
+    stdout.flush().await?;
+    Ok(())
+}
+
+/// Builds a request body from `template`, replacing each `{{field}}` with the JSON value of
+/// that field of `body` (`null` when it is absent), and checks the result is a JSON object.
+fn render_template(template: &str, body: &serde_json::Value) -> Result<serde_json::Value> {
+    let mut rendered = String::new();
+    let mut rest = template;
+    while let Some(start) = rest.find("{{") {
+        rendered.push_str(&rest[..start]);
+        let Some(len) = rest[start + 2..].find("}}") else {
+            return Err(
+                ConfigError::Message("unterminated {{ in request_template".to_string()).into(),
+            );
+        };
+        let name = rest[start + 2..start + 2 + len].trim();
+        if !TEMPLATE_FIELDS.contains(&name) {
+            return Err(ConfigError::Message(format!(
+                "unknown placeholder {{{{{}}}}} in request_template (expected one of {})",
+                name,
+                TEMPLATE_FIELDS.join(", ")
+            ))
+            .into());
+        }
+        let value = body.get(name).unwrap_or(&serde_json::Value::Null);
+        rendered.push_str(&value.to_string());
+        rest = &rest[start + 4 + len..];
+    }
+    rendered.push_str(rest);
+    let body: serde_json::Value = serde_json::from_str(&rendered).map_err(|e| {
+        ConfigError::Message(format!(
+            "request_template is not valid JSON after substitution: {}",
+            e
+        ))
+    })?;
+    if !body.is_object() {
+        return Err(ConfigError::Message(
+            "request_template must produce a JSON object".to_string(),
+        )
+        .into());
+    }
+    Ok(body)
+}
+
+/// Reads a recorded cassette file, naming the file in the error.
+fn read_recording(path: &Path) -> Result<Vec<u8>> {
+    fs::read(path).map_err(|e| {
+        Error::Io(std::io::Error::new(
+            e.kind(),
+            format!("failed to read recording {}: {}", path.display(), e),
+        ))
+    })
+}
+
+/// Returns how long a JWT remains valid according to its `exp` claim, or `None` if `token`
+/// is not a JWT with one.
+fn jwt_lifetime(token: &str) -> Option<Duration> {
+    let payload = token.split('.').nth(1)?;
+    let claims = base64::engine::general_purpose::URL_SAFE_NO_PAD
+        .decode(payload.trim_end_matches('='))
+        .ok()?;
+    let claims: serde_json::Value = serde_json::from_slice(&claims).ok()?;
+    let expires = UNIX_EPOCH + Duration::from_secs(claims.get("exp")?.as_u64()?);
+    Some(
+        expires
+            .duration_since(SystemTime::now())
+            .unwrap_or_default(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn builds_with(config: Config) {
+        if let Err(e) = ChatClient::from_config(&config, false, None) {
+            panic!("client failed to build with {:?}: {}", config, e);
+        }
+    }
+
+    #[test]
+    fn client_builds_with_each_transport_option() {
+        builds_with(Config::default());
+        builds_with(Config {
+            http2_prior_knowledge: true,
+            ..Default::default()
+        });
+        for secs in [0, 30] {
+            builds_with(Config {
+                tcp_keepalive_secs: Some(secs),
+                ..Default::default()
+            });
+        }
+        builds_with(Config {
+            connect_timeout_ms: Some(200),
+            ..Default::default()
+        });
+        builds_with(Config {
+            http2_prior_knowledge: true,
+            tcp_keepalive_secs: Some(10),
+            connect_timeout_ms: Some(500),
+            ..Default::default()
+        });
+    }
+
+    /// Keeps every warning logged, so tests can check one was given.
+    struct WarningLog(std::sync::Mutex<Vec<String>>);
+
+    impl log::Log for WarningLog {
+        fn enabled(&self, metadata: &log::Metadata) -> bool {
+            metadata.level() <= log::Level::Warn
+        }
+
+        fn log(&self, record: &log::Record) {
+            if self.enabled(record.metadata()) {
+                self.0.lock().unwrap().push(record.args().to_string());
+            }
+        }
+
+        fn flush(&self) {}
+    }
+
+    static WARNINGS: WarningLog = WarningLog(std::sync::Mutex::new(Vec::new()));
+
+    fn warned(message: &str) -> bool {
+        let _ = log::set_logger(&WARNINGS);
+        log::set_max_level(log::LevelFilter::Warn);
+        WARNINGS
+            .0
+            .lock()
+            .unwrap()
+            .iter()
+            .any(|warning| warning == message)
+    }
+
+    /// Streams `events`, replayed as the body of a streaming response, through `chat_stream`
+    /// with the default options and returns what it printed.
+    async fn print_stream(name: &str, events: &[&str]) -> String {
+        // Installs the logger before anything is logged.
+        warned("");
+        let dir = std::env::temp_dir().join(format!(
+            "mistral-chat-cli-rs-test-{}-{}",
+            name,
+            std::process::id()
+        ));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("001-response.status"), "200").unwrap();
+        let body: String = events
+            .iter()
+            .map(|data| format!("data: {}\n\n", data))
+            .collect();
+        fs::write(dir.join("001-response.body"), body).unwrap();
+        let cassette = Cassette::new(dir.clone(), CassetteMode::Replay).unwrap();
+        let client = ChatClient::new(String::new(), String::new(), false, Some(cassette));
+        let mut output = Vec::new();
+        let messages = vec![RequestMessage {
+            role: "user".to_string(),
+            content: "Hi".to_string(),
+            ..Default::default()
+        }];
+        let params = ChatParams::default();
+        let options = StreamOptions {
+            eol: LineEnding::Lf,
+            ..Default::default()
+        };
+        let result = client
+            .chat_stream_to(
+                &mut output,
+                false,
+                MISTRAL_MODEL,
+                messages,
+                &params,
+                &options,
+            )
+            .await;
+        fs::remove_dir_all(&dir).unwrap();
+        result.unwrap();
+        String::from_utf8(output).unwrap()
+    }
+
+    fn content(text: &str) -> String {
+        serde_json::json!({ "choices": [{ "index": 0, "delta": { "content": text } }] }).to_string()
+    }
+
+    #[tokio::test]
+    async fn output_ends_with_exactly_one_newline() {
+        let (hello, world) = (content("Hello"), content(" world"));
+        let printed = print_stream("newline", &[&hello, &world, "[DONE]"]).await;
+        assert_eq!(printed, "Hello world\n");
+        let line = content("Hello\n");
+        let printed = print_stream("ended-line", &[&line, "[DONE]"]).await;
+        assert_eq!(printed, "Hello\n");
+    }
+
+    #[tokio::test]
+    async fn empty_response_is_warned() {
+        let printed = print_stream("empty", &[&content(""), "[DONE]"]).await;
+        assert_eq!(printed, "");
+        assert!(warned("empty response from server"));
+    }
+}
@@ -0,0 +1,83 @@
+use futures_util::future;
+use mistral_chat_cli_rs::client::ChatParams;
+use mistral_chat_cli_rs::stream::{StreamEvent, Usage};
+use mistral_chat_cli_rs::tr;
+use mistral_chat_cli_rs::{ChatClient, RequestMessage};
+use std::time::{Duration, Instant};
+
+/// One model's complete answer.
+struct Answer {
+    content: String,
+    latency: Duration,
+    /// Token usage reported by the API, if it sent usage.
+    usage: Option<Usage>,
+}
+
+/// Sends `prompt` to every model at once and prints each answer in its own section, in the
+/// order the models were given, once all have finished. Returns how many requests succeeded.
+///
+/// Answers are buffered rather than streamed so that sections never interleave and the output
+/// of two runs can be diffed.
+pub async fn run(client: &ChatClient, models: &[String], prompt: &str) -> usize {
+    let answers = future::join_all(models.iter().map(|model| ask(client, model, prompt))).await;
+    let mut succeeded = 0;
+    for (index, (model, answer)) in models.iter().zip(answers).enumerate() {
+        if index > 0 {
+            println!();
+        }
+        println!("## {}\n", model);
+        match answer {
+            Ok(answer) => {
+                succeeded += 1;
+                println!("{}\n", answer.content.trim_end());
+                let tokens = answer.usage.map_or(tr!("compare_tokens_unknown"), |usage| {
+                    tr!(
+                        "compare_tokens",
+                        usage.prompt_tokens,
+                        usage.completion_tokens
+                    )
+                });
+                println!("_{} ms, {}_", answer.latency.as_millis(), tokens);
+            }
+            Err(e) => {
+                let e = format!("{:#}", anyhow::Error::from(e));
+                println!("{}", tr!("bench_failed", e));
+            }
+        }
+    }
+    succeeded
+}
+
+/// Collects the whole streamed answer of `model` to `prompt`.
+async fn ask(
+    client: &ChatClient,
+    model: &str,
+    prompt: &str,
+) -> mistral_chat_cli_rs::Result<Answer> {
+    let messages = vec![RequestMessage {
+        role: "user".to_string(),
+        content: prompt.to_string(),
+        ..Default::default()
+    }];
+    let start = Instant::now();
+    let mut stream = client
+        .chat_events(model, messages, &ChatParams::default())
+        .await?;
+    let mut content = String::new();
+    let mut usage = None;
+    while let Some(event) = stream.next().await {
+        match event? {
+            StreamEvent::Content(delta) => content.push_str(&delta),
+            StreamEvent::Usage(u) => usage = Some(u),
+            StreamEvent::Reasoning(_)
+            | StreamEvent::Refusal(_)
+            | StreamEvent::Finish(_)
+            | StreamEvent::Citation(_) => {}
+        }
+    }
+    Ok(Answer {
+        content,
+        latency: start.elapsed(),
+        usage,
+    })
+}
@@ -0,0 +1,659 @@
+use crate::error::Result;
+use ::config::{Config as ConfigFile, ConfigError, Environment, File, Value, ValueKind};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use toml_edit::{value, DocumentMut};
+
+/// The type of value a config key holds, which `config set` parses its argument as.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum KeyKind {
+    Bool,
+    /// A non-negative integer no larger than the given bound.
+    Int(i64),
+    String,
+}
+
+impl KeyKind {
+    const U32: KeyKind = KeyKind::Int(u32::MAX as i64);
+    const U64: KeyKind = KeyKind::Int(i64::MAX);
+}
+
+/// Keys accepted by `config set`/`config unset`, and the type of value each holds.
+const KEYS: [(&str, KeyKind); 27] = [
+    ("mistral_api_key", KeyKind::String),
+    ("codestral_api_key", KeyKind::String),
+    ("debug", KeyKind::Bool),
+    ("danger_accept_invalid_certs", KeyKind::Bool),
+    ("ca_cert", KeyKind::String),
+    ("user_agent", KeyKind::String),
+    ("auth_command", KeyKind::String),
+    ("preprocess_command", KeyKind::String),
+    ("request_template", KeyKind::String),
+    ("stream_content_path", KeyKind::String),
+    ("code_system_prompt", KeyKind::String),
+    ("tts_command", KeyKind::String),
+    ("pager", KeyKind::Bool),
+    ("model", KeyKind::String),
+    ("reasoning_effort", KeyKind::String),
+    ("max_retries", KeyKind::U32),
+    ("retry_delay_ms", KeyKind::U64),
+    ("max_retries_on_429", KeyKind::U32),
+    ("retry_delay_on_429_ms", KeyKind::U64),
+    ("deadline_secs", KeyKind::U64),
+    ("requests_per_minute", KeyKind::U32),
+    ("http2_prior_knowledge", KeyKind::Bool),
+    ("tcp_keepalive_secs", KeyKind::U64),
+    ("connect_timeout_ms", KeyKind::U64),
+    ("no_proxy", KeyKind::Bool),
+    ("max_input_bytes", KeyKind::U64),
+    ("max_response_bytes", KeyKind::U64),
+];
+
+/// Settings that hold lists, which `config set` cannot write but `config explain` reports.
+const LIST_KEYS: [&str; 4] = [
+    "redact_patterns",
+    "fallback_models",
+    "larger_context_models",
+    "allowed_models",
+];
+
+/// Where the effective value of a setting came from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Source {
+    /// Set nowhere, so the built-in default applies.
+    Default,
+    /// The config file.
+    File,
+    /// This `APP_*` environment variable.
+    Environment(String),
+    /// This command-line flag.
+    Flag(String),
+}
+
+/// Struct representing configuration for the CLI.
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct Config {
+    #[serde(default)]
+    pub mistral_api_key: String,
+    #[serde(default)]
+    pub codestral_api_key: String,
+    pub debug: bool,
+    /// Skip TLS certificate verification.
+    #[serde(default)]
+    pub danger_accept_invalid_certs: bool,
+    /// Path to an extra PEM root certificate to trust, e.g. a corporate proxy CA.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ca_cert: Option<String>,
+    /// User-Agent header to send instead of `mistral-chat-cli/<version>`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub user_agent: Option<String>,
+    /// Shell command printing a bearer token to use instead of the API keys.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub auth_command: Option<String>,
+    /// Shell command that rewrites every chat request: it reads the JSON body on stdin and prints
+    /// the body to send. A failing command aborts the request.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub preprocess_command: Option<String>,
+    /// JSON template for chat request bodies, with `{{messages}}`, `{{model}}` and the other
+    /// request fields as placeholders, for backends that expect a different shape.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub request_template: Option<String>,
+    /// Where streamed chat responses carry their text, e.g. `choices[0].message.content`, for
+    /// backends that do not send it at `choices[0].delta.content`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stream_content_path: Option<String>,
+    /// System message framing code sent by `code`, replacing the built-in reviewer prompt.
+    /// An empty string sends the code without one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub code_system_prompt: Option<String>,
+    /// Shell command that reads text on stdin and speaks it, used by `chat --speak`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tts_command: Option<String>,
+    /// Show `chat` responses taller than the terminal in `$PAGER` (default false); `--no-pager`
+    /// turns it off for one run.
+    #[serde(default)]
+    pub pager: bool,
+    /// Model used when a subcommand is not given one, instead of the built-in choice.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    /// Reasoning effort sent to reasoning models when no `--reasoning` flag is given.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reasoning_effort: Option<String>,
+    /// Retries after a transport error such as a refused connection (default 2).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_retries: Option<u32>,
+    /// Milliseconds between transport-error retries (default 2000).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retry_delay_ms: Option<u64>,
+    /// Retries after a 429 Too Many Requests response (default 3).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_retries_on_429: Option<u32>,
+    /// Milliseconds before the first 429 retry, doubled for each later one unless the server
+    /// sends `Retry-After` (default 5000).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retry_delay_on_429_ms: Option<u64>,
+    /// Seconds a request may take in all, every attempt and the waits between retries included,
+    /// before it fails as timed out (default: no limit).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub deadline_secs: Option<u64>,
+    /// Most requests sent in any one minute, retries included, shared by everything a command
+    /// sends concurrently.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub requests_per_minute: Option<u32>,
+    /// Speak HTTP/2 from the first byte instead of negotiating it, for servers and proxies that
+    /// only accept HTTP/2 (default false; TLS connections negotiate HTTP/2 anyway).
+    #[serde(default)]
+    pub http2_prior_knowledge: bool,
+    /// Seconds between TCP keepalive probes on idle connections, or 0 to send none (default 15).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tcp_keepalive_secs: Option<u64>,
+    /// Milliseconds to wait for a connection, TLS handshake included, before the attempt counts
+    /// as a transport error (default: no limit beyond the operating system's).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub connect_timeout_ms: Option<u64>,
+    /// Connect to the APIs directly, ignoring `HTTPS_PROXY` and the other proxy variables
+    /// (default false). Without it, hosts listed in `NO_PROXY` are already reached directly.
+    #[serde(default)]
+    pub no_proxy: bool,
+    /// Largest prompt, input file or stdin in bytes that is sent, unless `--max-input-bytes`
+    /// gives another.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_input_bytes: Option<u64>,
+    /// Largest non-streaming response in bytes that is read, such as a `code` analysis or an OCR
+    /// result, before the request fails (default 64 MiB).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_response_bytes: Option<u64>,
+    /// Regular expressions masked in responses by `chat --redact`, instead of the built-in email
+    /// and card number patterns.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub redact_patterns: Vec<String>,
+    /// Models `chat` tries in turn when the model is still rate limited after its retries, or
+    /// unavailable, unless `--fallback-models` gives others.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub fallback_models: Vec<String>,
+    /// Models with growing context windows that `chat` moves up to when a prompt is too long for
+    /// the model, unless `--larger-context-models` gives others.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub larger_context_models: Vec<String>,
+    /// The only models any command may send requests to, for shared configs; any `--model`,
+    /// fallback or built-in choice outside it is refused before sending. Empty allows all.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub allowed_models: Vec<String>,
+    /// Tags sent as the `metadata` object of every chat request under `[metadata]`, for
+    /// gateways that attribute usage to projects or users. `--meta` adds to and overrides them.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub metadata: BTreeMap<String, String>,
+    /// Overrides of the settings above for `chat`, under `[chat]`.
+    #[serde(default, skip_serializing_if = "Defaults::is_empty")]
+    pub chat: Defaults,
+    /// Overrides of the settings above for `code`, under `[code]`.
+    #[serde(default, skip_serializing_if = "Defaults::is_empty")]
+    pub code: Defaults,
+    /// Named chat settings, selected with `chat --preset <name>`.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub presets: BTreeMap<String, Preset>,
+}
+
+/// Settings a subcommand table such as `[chat]` can override.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct Defaults {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reasoning_effort: Option<String>,
+}
+
+impl Defaults {
+    fn is_empty(&self) -> bool {
+        self.model.is_none() && self.reasoning_effort.is_none()
+    }
+
+    /// Lays the settings given as flags over these: a flag wins over any table or global value.
+    pub fn with_flags(self, flags: Defaults) -> Defaults {
+        Defaults {
+            model: flags.model.or(self.model),
+            reasoning_effort: flags.reasoning_effort.or(self.reasoning_effort),
+        }
+    }
+}
+
+/// A named bundle of chat settings defined under `[presets.<name>]`.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct Preset {
+    /// One line shown by `config presets`.
+    pub description: Option<String>,
+    /// Model to use instead of the one picked from the prompt.
+    pub model: Option<String>,
+    /// System message sent before the conversation.
+    pub system: Option<String>,
+}
+
+impl Config {
+    pub fn from_file(file_path: &str) -> Result<Self> {
+        let settings = ConfigFile::builder()
+            // Add configuration from a file.
+            .add_source(File::with_name(file_path))
+            // Add configuration from environment variables.
+            .add_source(Environment::with_prefix("APP"))
+            .build()?;
+
+        // Expand `${VAR}` references so the file itself can stay free of secrets.
+        let mut settings: Value = settings.try_deserialize()?;
+        interpolate_strings(&mut settings)?;
+
+        // Try to deserialize the configuration into the `Config` struct.
+        Ok(settings.try_deserialize()?)
+    }
+
+    pub fn generate_sample_config(file_path: &str) -> Result<()> {
+        let sample_config = Config {
+            mistral_api_key: "your_mistral_api_key".to_string(),
+            codestral_api_key: "your_codestral_api_key".to_string(),
+            debug: false,
+            ..Default::default()
+        };
+
+        let config_content = toml::to_string(&sample_config)
+            .map_err(|e| ConfigError::Message(e.to_string()))?;
+        fs::write(file_path, config_content)?;
+        Ok(())
+    }
+
+    /// Sets a single key in the config file, keeping the rest of the file as written.
+    pub fn set_key(file_path: &str, key: &str, new_value: &str) -> Result<()> {
+        let kind = Config::key_kind(key)?;
+        let invalid = |expected: String| {
+            ConfigError::Message(format!("{} must be {}, got {:?}", key, expected, new_value))
+        };
+        let mut item = match kind {
+            KeyKind::Bool => value(
+                new_value
+                    .parse::<bool>()
+                    .map_err(|_| invalid("true or false".to_string()))?,
+            ),
+            KeyKind::Int(max) => {
+                let expected = || format!("a whole number from 0 to {}", max);
+                let number: i64 = new_value.trim().parse().map_err(|_| invalid(expected()))?;
+                if !(0..=max).contains(&number) {
+                    return Err(invalid(expected()).into());
+                }
+                value(number)
+            }
+            KeyKind::String => value(new_value),
+        };
+        let mut doc = Config::read_document(file_path)?;
+        // Keep the spacing and trailing comment of an existing entry.
+        if let (Some(new), Some(old)) =
+            (item.as_value_mut(), doc.get(key).and_then(|i| i.as_value()))
+        {
+            *new.decor_mut() = old.decor().clone();
+        }
+        doc[key] = item;
+        fs::write(file_path, doc.to_string())?;
+        Ok(())
+    }
+
+    /// Removes a single key from the config file, keeping the rest of the file as written.
+    pub fn unset_key(file_path: &str, key: &str) -> Result<()> {
+        Config::key_kind(key)?;
+        let mut doc = Config::read_document(file_path)?;
+        if doc.remove(key).is_none() {
+            return Err(
+                ConfigError::Message(format!("{} is not set in {}", key, file_path)).into(),
+            );
+        }
+        fs::write(file_path, doc.to_string())?;
+        Ok(())
+    }
+
+    /// Returns the type of value `key` holds, rejecting unknown keys.
+    fn key_kind(key: &str) -> Result<KeyKind> {
+        match KEYS.iter().find(|(name, _)| *name == key) {
+            Some((_, kind)) => Ok(*kind),
+            None => {
+                let known: Vec<&str> = KEYS.iter().map(|(name, _)| *name).collect();
+                Err(ConfigError::Message(format!(
+                    "unknown config key {:?} (expected one of: {})",
+                    key,
+                    known.join(", ")
+                ))
+                .into())
+            }
+        }
+    }
+
+    /// Reports, for every key `config set` accepts and every list setting, whether `from_file`
+    /// takes it from the environment, from `file_path` or from the built-in defaults. With no
+    /// file, as with `--no-config`, only the environment counts.
+    pub fn sources(file_path: Option<&str>) -> Result<Vec<(&'static str, Source)>> {
+        let doc = match file_path {
+            Some(path) => Some(Config::read_document(path)?),
+            None => None,
+        };
+        let keys = KEYS.iter().map(|(name, _)| *name).chain(LIST_KEYS);
+        Ok(keys
+            .map(|key| {
+                let variable = format!("APP_{}", key.to_uppercase());
+                let source = if std::env::var_os(&variable).is_some() {
+                    Source::Environment(variable)
+                } else if doc.as_ref().is_some_and(|doc| doc.contains_key(key)) {
+                    Source::File
+                } else {
+                    Source::Default
+                };
+                (key, source)
+            })
+            .collect())
+    }
+
+    /// Parses the config file for editing; a missing file is treated as empty.
+    fn read_document(file_path: &str) -> Result<DocumentMut> {
+        let content = match fs::read_to_string(file_path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+            Err(e) => return Err(e.into()),
+        };
+        content
+            .parse::<DocumentMut>()
+            .map_err(|e| ConfigError::Message(format!("{}: {}", file_path, e)).into())
+    }
+
+    // Mask API keys by showing only the first few characters.
+    pub fn mask_key(key: &str) -> String {
+        if key.len() > 5 {
+            format!("{}{}", &key[..5], "*".repeat(key.len() - 5))
+        } else {
+            key.to_string()
+        }
+    }
+
+    /// Resolves the defaults for `chat`: the `[chat]` table first, then the global settings.
+    pub fn chat_defaults(&self) -> Defaults {
+        self.merged_defaults(&self.chat)
+    }
+
+    /// Resolves the defaults for `code`: the `[code]` table first, then the global settings.
+    pub fn code_defaults(&self) -> Defaults {
+        self.merged_defaults(&self.code)
+    }
+
+    fn merged_defaults(&self, section: &Defaults) -> Defaults {
+        Defaults {
+            model: section.model.clone().or_else(|| self.model.clone()),
+            reasoning_effort: section
+                .reasoning_effort
+                .clone()
+                .or_else(|| self.reasoning_effort.clone()),
+        }
+    }
+
+    /// The settings `config view` shows, as `(setting, value)` pairs in display order.
+    ///
+    /// API keys are masked, and unset settings and switches that are off are left out. Settings
+    /// of a subcommand table are named by their path, e.g. `chat.model`, and each metadata entry
+    /// is a `metadata` pair with the value `key=value`.
+    pub fn settings(&self) -> Vec<(&'static str, String)> {
+        let mut settings = Vec::new();
+        let mut add = |name, value: Option<String>| {
+            if let Some(value) = value {
+                settings.push((name, value));
+            }
+        };
+        let text = |value: &Option<String>| value.clone();
+        let number = |value: Option<u64>| value.map(|n| n.to_string());
+        let switch = |on: bool| on.then(|| true.to_string());
+        let list = |items: &Vec<String>| (!items.is_empty()).then(|| items.join(", "));
+
+        add(
+            "mistral_api_key",
+            Some(Config::mask_key(&self.mistral_api_key)),
+        );
+        add(
+            "codestral_api_key",
+            Some(Config::mask_key(&self.codestral_api_key)),
+        );
+        add("debug", Some(self.debug.to_string()));
+        add(
+            "danger_accept_invalid_certs",
+            Some(self.danger_accept_invalid_certs.to_string()),
+        );
+        add("ca_cert", text(&self.ca_cert));
+        add("user_agent", text(&self.user_agent));
+        add("auth_command", text(&self.auth_command));
+        add("preprocess_command", text(&self.preprocess_command));
+        add("request_template", text(&self.request_template));
+        add("stream_content_path", text(&self.stream_content_path));
+        add("code_system_prompt", text(&self.code_system_prompt));
+        add("tts_command", text(&self.tts_command));
+        add("pager", switch(self.pager));
+        add("model", text(&self.model));
+        add("reasoning_effort", text(&self.reasoning_effort));
+        add("max_retries", number(self.max_retries.map(u64::from)));
+        add("retry_delay_ms", number(self.retry_delay_ms));
+        add(
+            "max_retries_on_429",
+            number(self.max_retries_on_429.map(u64::from)),
+        );
+        add("retry_delay_on_429_ms", number(self.retry_delay_on_429_ms));
+        add("deadline_secs", number(self.deadline_secs));
+        add(
+            "requests_per_minute",
+            number(self.requests_per_minute.map(u64::from)),
+        );
+        add("http2_prior_knowledge", switch(self.http2_prior_knowledge));
+        add("tcp_keepalive_secs", number(self.tcp_keepalive_secs));
+        add("connect_timeout_ms", number(self.connect_timeout_ms));
+        add("no_proxy", switch(self.no_proxy));
+        add("max_input_bytes", number(self.max_input_bytes));
+        add("max_response_bytes", number(self.max_response_bytes));
+        add("redact_patterns", list(&self.redact_patterns));
+        add("fallback_models", list(&self.fallback_models));
+        add("larger_context_models", list(&self.larger_context_models));
+        add("allowed_models", list(&self.allowed_models));
+        for (key, value) in &self.metadata {
+            add("metadata", Some(format!("{}={}", key, value)));
+        }
+        add("chat.model", text(&self.chat.model));
+        add("chat.reasoning_effort", text(&self.chat.reasoning_effort));
+        add("code.model", text(&self.code.model));
+        add("code.reasoning_effort", text(&self.code.reasoning_effort));
+        settings
+    }
+}
+
+/// Interpolates every string in `value`, however deeply it is nested in tables and arrays.
+fn interpolate_strings(value: &mut Value) -> Result<()> {
+    match &mut value.kind {
+        ValueKind::String(text) => *text = interpolate(text)?,
+        ValueKind::Table(table) => {
+            for item in table.values_mut() {
+                interpolate_strings(item)?;
+            }
+        }
+        ValueKind::Array(items) => {
+            for item in items {
+                interpolate_strings(item)?;
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+/// Replaces every `${VAR}` in `value` with that environment variable, failing if one is unset.
+fn interpolate(value: &str) -> Result<String> {
+    let mut result = String::new();
+    let mut rest = value;
+    while let Some(start) = rest.find("${") {
+        result.push_str(&rest[..start]);
+        let Some(len) = rest[start + 2..].find('}') else {
+            return Err(
+                ConfigError::Message("unterminated ${ in a config value".to_string()).into(),
+            );
+        };
+        let name = &rest[start + 2..start + 2 + len];
+        let env_value = std::env::var(name).map_err(|_| {
+            ConfigError::Message(format!(
+                "environment variable {} referenced in the config is not set",
+                name
+            ))
+        })?;
+        result.push_str(&env_value);
+        rest = &rest[start + 3 + len..];
+    }
+    result.push_str(rest);
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A config file path unique to one test, removed when dropped.
+    struct TempConfig(std::path::PathBuf);
+
+    impl TempConfig {
+        fn new(name: &str, content: &str) -> Self {
+            let path = std::env::temp_dir().join(format!(
+                "mistral-chat-cli-rs-{}-{}.toml",
+                name,
+                std::process::id()
+            ));
+            fs::write(&path, content).unwrap();
+            TempConfig(path)
+        }
+
+        fn path(&self) -> &str {
+            self.0.to_str().unwrap()
+        }
+
+        fn content(&self) -> String {
+            fs::read_to_string(&self.0).unwrap()
+        }
+    }
+
+    impl Drop for TempConfig {
+        fn drop(&mut self) {
+            let _ = fs::remove_file(&self.0);
+        }
+    }
+
+    #[test]
+    fn set_key_writes_integers_as_numbers() {
+        let file = TempConfig::new("set-int", "debug = false\n");
+        Config::set_key(file.path(), "max_retries", "5").unwrap();
+        Config::set_key(file.path(), "connect_timeout_ms", "2500").unwrap();
+        assert!(file.content().contains("max_retries = 5\n"));
+        let config = Config::from_file(file.path()).unwrap();
+        assert_eq!(config.max_retries, Some(5));
+        assert_eq!(config.connect_timeout_ms, Some(2500));
+    }
+
+    #[test]
+    fn set_key_rejects_values_of_the_wrong_type() {
+        let file = TempConfig::new("set-invalid", "debug = false\nmax_retries = 2\n");
+        for (key, bad) in [
+            ("max_retries", "abc"),
+            ("max_retries", "-1"),
+            ("max_retries", "4294967296"),
+            ("retry_delay_ms", "1.5"),
+            ("debug", "yes"),
+        ] {
+            let err = Config::set_key(file.path(), key, bad).unwrap_err();
+            assert!(
+                matches!(err, crate::Error::Config(_)),
+                "{} = {:?}",
+                key,
+                bad
+            );
+        }
+        assert_eq!(file.content(), "debug = false\nmax_retries = 2\n");
+        assert_eq!(Config::from_file(file.path()).unwrap().max_retries, Some(2));
+    }
+
+    #[test]
+    fn set_key_writes_booleans_and_strings() {
+        let file = TempConfig::new("set-other", "");
+        Config::set_key(file.path(), "pager", "true").unwrap();
+        Config::set_key(file.path(), "model", "42").unwrap();
+        let content = file.content();
+        assert!(content.contains("pager = true\n"));
+        assert!(content.contains("model = \"42\"\n"));
+    }
+
+    #[test]
+    fn set_key_rejects_unknown_keys() {
+        let file = TempConfig::new("set-unknown", "");
+        assert!(Config::set_key(file.path(), "max_retry", "5").is_err());
+        assert_eq!(file.content(), "");
+    }
+
+    #[test]
+    fn subcommand_tables_override_global_defaults() {
+        let file = TempConfig::new(
+            "defaults",
+            "debug = false\nmodel = \"global-model\"\nreasoning_effort = \"low\"\n\n\
+             [chat]\nmodel = \"chat-model\"\n\n[code]\nreasoning_effort = \"high\"\n",
+        );
+        let config = Config::from_file(file.path()).unwrap();
+        let chat = config.chat_defaults();
+        assert_eq!(chat.model.as_deref(), Some("chat-model"));
+        assert_eq!(chat.reasoning_effort.as_deref(), Some("low"));
+        let code = config.code_defaults();
+        assert_eq!(code.model.as_deref(), Some("global-model"));
+        assert_eq!(code.reasoning_effort.as_deref(), Some("high"));
+    }
+
+    #[test]
+    fn flags_override_subcommand_tables_and_global_defaults() {
+        let file = TempConfig::new(
+            "flag-defaults",
+            "debug = false\nmodel = \"global-model\"\nreasoning_effort = \"low\"\n\n\
+             [chat]\nreasoning_effort = \"medium\"\n",
+        );
+        let config = Config::from_file(file.path()).unwrap();
+        let flags = Defaults {
+            reasoning_effort: Some("high".to_string()),
+            ..Default::default()
+        };
+        let chat = config.chat_defaults().with_flags(flags);
+        assert_eq!(chat.reasoning_effort.as_deref(), Some("high"));
+        assert_eq!(chat.model.as_deref(), Some("global-model"));
+        let chat = config.chat_defaults().with_flags(Defaults::default());
+        assert_eq!(chat.reasoning_effort.as_deref(), Some("medium"));
+    }
+
+    #[test]
+    fn defaults_are_unset_without_global_or_table_values() {
+        let file = TempConfig::new("no-defaults", "debug = false\n[chat]\n");
+        let chat = Config::from_file(file.path()).unwrap().chat_defaults();
+        assert!(chat.model.is_none() && chat.reasoning_effort.is_none());
+    }
+
+    #[test]
+    fn env_references_are_expanded_in_every_string() {
+        std::env::set_var("MISTRAL_CHAT_CLI_RS_TEST_TEAM", "search");
+        let file = TempConfig::new(
+            "interpolate",
+            "debug = false\nmistral_api_key = \"k-${MISTRAL_CHAT_CLI_RS_TEST_TEAM}\"\n\
+             auth_command = \"vault read ${MISTRAL_CHAT_CLI_RS_TEST_TEAM}\"\n\
+             fallback_models = [\"${MISTRAL_CHAT_CLI_RS_TEST_TEAM}-small\"]\n\n\
+             [metadata]\nteam = \"${MISTRAL_CHAT_CLI_RS_TEST_TEAM}\"\n",
+        );
+        let config = Config::from_file(file.path()).unwrap();
+        assert_eq!(config.mistral_api_key, "k-search");
+        assert_eq!(config.auth_command.as_deref(), Some("vault read search"));
+        assert_eq!(config.fallback_models, ["search-small"]);
+        assert_eq!(config.metadata["team"], "search");
+    }
+
+    #[test]
+    fn unset_env_references_fail_to_load() {
+        let file = TempConfig::new(
+            "interpolate-unset",
+            "debug = false\nuser_agent = \"${MISTRAL_CHAT_CLI_RS_TEST_UNSET}\"\n",
+        );
+        assert!(Config::from_file(file.path()).is_err());
+    }
+}
@@ -0,0 +1,167 @@
+use mistral_chat_cli_rs::client::{CODESTRAL_MODELS_URL, MISTRAL_MODELS_URL};
+use mistral_chat_cli_rs::tr;
+use mistral_chat_cli_rs::{ChatClient, Config, Error};
+use reqwest::StatusCode;
+
+/// Key values written by `config generate`, which still need replacing.
+const PLACEHOLDER_KEYS: [&str; 2] = ["your_mistral_api_key", "your_codestral_api_key"];
+
+/// Tallies the checklist while printing one line per check.
+#[derive(Default)]
+struct Report {
+    failed: usize,
+}
+
+impl Report {
+    fn pass(&mut self, check: &str, detail: &str) {
+        println!("[ ok ] {:<24} {}", check, detail);
+    }
+
+    fn warn(&mut self, check: &str, detail: &str, hint: &str) {
+        println!("[warn] {:<24} {}", check, detail);
+        println!("       {:<24} {}", "", tr!("doctor_hint", hint));
+    }
+
+    fn fail(&mut self, check: &str, detail: &str, hint: &str) {
+        self.failed += 1;
+        println!("[FAIL] {:<24} {}", check, detail);
+        println!("       {:<24} {}", "", tr!("doctor_hint", hint));
+    }
+}
+
+/// Runs the setup checklist and returns whether every critical check passed.
+///
+/// `config_file` is the file that would be read, or `None` with `--no-config`; `config` is the
+/// effective configuration after flags and environment variables were applied.
+pub async fn run(
+    config_file: Option<&str>,
+    config: mistral_chat_cli_rs::Result<Config>,
+    debug: bool,
+) -> bool {
+    let mut report = Report::default();
+
+    let config_check = tr!("doctor_config_file");
+    match config_file {
+        None => report.pass(&config_check, &tr!("doctor_skipped")),
+        Some(path) => match Config::from_file(path) {
+            Ok(_) => report.pass(&config_check, path),
+            Err(e) => {
+                let detail = format!("{}: {:#}", path, anyhow::Error::from(e));
+                let hint = tr!("doctor_config_hint");
+                // Keys may still come from flags or the environment.
+                if config.is_ok() {
+                    report.warn(&config_check, &detail, &hint)
+                } else {
+                    report.fail(&config_check, &detail, &hint)
+                }
+            }
+        },
+    }
+    let Ok(config) = config else {
+        println!("\n{}", tr!("doctor_failed", report.failed));
+        return false;
+    };
+
+    if let Some(command) = &config.auth_command {
+        report.pass(
+            &tr!("doctor_api_keys"),
+            &tr!("doctor_auth_command", command),
+        );
+    } else {
+        for (name, key, flag) in [
+            (
+                "doctor_mistral_key",
+                &config.mistral_api_key,
+                "--mistral-key",
+            ),
+            (
+                "doctor_codestral_key",
+                &config.codestral_api_key,
+                "--codestral-key",
+            ),
+        ] {
+            let name = tr!(name);
+            let hint = tr!("doctor_key_hint", flag);
+            if key.trim().is_empty() {
+                report.fail(&name, &tr!("doctor_missing"), &hint);
+            } else if PLACEHOLDER_KEYS.contains(&key.as_str()) {
+                report.fail(&name, &tr!("doctor_placeholder"), &hint);
+            } else {
+                report.pass(&name, &Config::mask_key(key));
+            }
+        }
+    }
+
+    let proxied = !config.no_proxy
+        && ["HTTPS_PROXY", "https_proxy", "ALL_PROXY", "all_proxy"]
+            .iter()
+            .any(|name| std::env::var_os(name).is_some_and(|value| !value.is_empty()));
+    for url in [MISTRAL_MODELS_URL, CODESTRAL_MODELS_URL] {
+        let host = reqwest::Url::parse(url)
+            .ok()
+            .and_then(|url| url.host_str().map(str::to_string))
+            .unwrap_or_default();
+        let check = format!("DNS {}", host);
+        let addresses = tokio::net::lookup_host((host.as_str(), 443)).await;
+        match addresses.map(|mut addresses| addresses.next()) {
+            Ok(Some(address)) => report.pass(&check, &address.ip().to_string()),
+            Ok(None) => report.fail(&check, &tr!("doctor_no_addresses"), &tr!("doctor_dns_hint")),
+            // Through a proxy, only the proxy needs to resolve the API hosts.
+            Err(e) if proxied => report.warn(&check, &e.to_string(), &tr!("doctor_proxy_hint")),
+            Err(e) => report.fail(&check, &e.to_string(), &tr!("doctor_network_dns_hint")),
+        }
+    }
+
+    let client = match ChatClient::from_config(&config, debug, None) {
+        Ok(client) => client,
+        Err(e) => {
+            report.fail(
+                &tr!("doctor_http_client"),
+                &format!("{:#}", anyhow::Error::from(e)),
+                &tr!("doctor_ca_hint"),
+            );
+            println!("\n{}", tr!("doctor_failed", report.failed));
+            return false;
+        }
+    };
+    for ping in client.ping().await {
+        let reachable = tr!("doctor_endpoint", ping.name);
+        let auth = tr!("doctor_authentication", ping.name);
+        match ping.result {
+            Ok((status, latency)) => {
+                report.pass(
+                    &reachable,
+                    &format!("{} ms ({})", latency.as_millis(), ping.url),
+                );
+                if status.is_success() {
+                    report.pass(&auth, &tr!("doctor_key_accepted"));
+                } else if status == StatusCode::UNAUTHORIZED || status == StatusCode::FORBIDDEN {
+                    report.fail(
+                        &auth,
+                        &tr!("doctor_key_rejected", status),
+                        &tr!("doctor_key_rejected_hint"),
+                    );
+                } else {
+                    report.warn(
+                        &auth,
+                        &tr!("doctor_unexpected_status", status),
+                        &tr!("doctor_degraded_hint"),
+                    );
+                }
+            }
+            Err(e @ Error::Config(_)) => report.fail(
+                &auth,
+                &format!("{:#}", anyhow::Error::from(e)),
+                &tr!("doctor_auth_command_hint"),
+            ),
+            Err(e) => report.fail(&reachable, &e.to_string(), &tr!("doctor_network_hint")),
+        }
+    }
+
+    if report.failed == 0 {
+        println!("\n{}", tr!("doctor_passed"));
+    } else {
+        println!("\n{}", tr!("doctor_failed", report.failed));
+    }
+    report.failed == 0
+}
@@ -0,0 +1,174 @@
+use crate::tr;
+use reqwest::StatusCode;
+use std::error::Error as _;
+use std::io::ErrorKind;
+
+/// Errors returned by the chat client.
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    /// The configuration could not be read or written.
+    #[error("{}", tr!("error_config"))]
+    Config(#[from] ::config::ConfigError),
+
+    /// The request could not be delivered to the API.
+    #[error("{}", tr!("error_network", describe_network_error(.0), .0))]
+    Network(#[from] reqwest::Error),
+
+    /// The API answered with a non-success status.
+    #[error("{}", tr!("error_api", .status, .message, request_id_note(.request_id)))]
+    Api {
+        status: StatusCode,
+        message: String,
+        /// The ID the server assigned to the request, for support tickets.
+        request_id: Option<String>,
+    },
+
+    /// A request or response body could not be (de)serialized.
+    #[error("{}", tr!("error_parse", .0))]
+    Parse(String),
+
+    /// The API rejected the credentials.
+    #[error("{}", tr!("error_auth", .status, .message, request_id_note(.request_id)))]
+    Auth {
+        status: StatusCode,
+        message: String,
+        /// The ID the server assigned to the request, for support tickets.
+        request_id: Option<String>,
+    },
+
+    /// The caller supplied input that cannot be sent.
+    #[error("{}", tr!("error_input", .0))]
+    Input(String),
+
+    /// A non-streaming response grew past the size it may be buffered to.
+    #[error("{}", tr!("error_response_too_large", .limit))]
+    ResponseTooLarge { limit: u64 },
+
+    /// A request, retries and the waits between them included, ran past `deadline_secs`.
+    #[error("{}", tr!("error_deadline", format!("{:?}", .deadline)))]
+    DeadlineExceeded { deadline: std::time::Duration },
+
+    /// The prompt was too long for the model and for every larger-context model tried after it.
+    #[error("{}", tr!("error_context_length", .error, .tried.join(", ")))]
+    ContextLengthExceeded {
+        /// The larger-context models that were tried, in order.
+        tried: Vec<String>,
+        /// The error the last of them answered with.
+        error: Box<Error>,
+    },
+
+    /// Reading or writing local files or streams failed.
+    #[error("{}", tr!("error_io"))]
+    Io(#[from] std::io::Error),
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(err: serde_json::Error) -> Self {
+        Error::Parse(err.to_string())
+    }
+}
+
+impl Error {
+    /// Builds an `Auth` or `Api` error from a failed response status, body and request ID.
+    pub fn from_status(status: StatusCode, message: String, request_id: Option<String>) -> Self {
+        if status == StatusCode::UNAUTHORIZED || status == StatusCode::FORBIDDEN {
+            Error::Auth {
+                status,
+                message,
+                request_id,
+            }
+        } else {
+            Error::Api {
+                status,
+                message,
+                request_id,
+            }
+        }
+    }
+
+    /// Whether the API turned the request away because the model is rate limited, unknown or
+    /// unavailable, so another model may still serve it.
+    pub fn is_model_unavailable(&self) -> bool {
+        match self {
+            Error::Api { status, .. } => {
+                *status == StatusCode::TOO_MANY_REQUESTS
+                    || *status == StatusCode::NOT_FOUND
+                    || status.is_server_error()
+            }
+            _ => false,
+        }
+    }
+
+    /// Whether the API rejected the request because the prompt does not fit the model's context
+    /// window, so a model with a larger one may still take it.
+    pub fn is_context_length_exceeded(&self) -> bool {
+        const MARKERS: [&str; 5] = [
+            "context length",
+            "context window",
+            "maximum context",
+            "context_length_exceeded",
+            "too large for model",
+        ];
+        match self {
+            Error::ContextLengthExceeded { .. } => true,
+            Error::Api {
+                status, message, ..
+            } if status.is_client_error() => {
+                let message = message.to_lowercase();
+                MARKERS.iter().any(|marker| message.contains(marker))
+            }
+            _ => false,
+        }
+    }
+}
+
+fn request_id_note(request_id: &Option<String>) -> String {
+    match request_id {
+        Some(id) => tr!("error_request_id", id),
+        None => String::new(),
+    }
+}
+
+/// Classifies a transport failure so connection problems aren't mistaken for auth problems.
+fn describe_network_error(err: &reqwest::Error) -> String {
+    if err.is_timeout() {
+        return tr!("network_timed_out");
+    }
+    if err.is_connect() {
+        let mut source = err.source();
+        while let Some(cause) = source {
+            if let Some(io) = cause.downcast_ref::<std::io::Error>() {
+                match io.kind() {
+                    ErrorKind::ConnectionRefused => return tr!("network_refused"),
+                    ErrorKind::TimedOut => return tr!("network_connect_timed_out"),
+                    _ => {}
+                }
+            }
+            let text = cause.to_string().to_lowercase();
+            if text.contains("dns") || text.contains("lookup address") {
+                return tr!("network_dns");
+            }
+            if text.contains("certificate") || text.contains("tls") || text.contains("ssl") {
+                return tr!("network_tls");
+            }
+            source = cause.source();
+        }
+        return tr!("network_connect");
+    }
+    if err.is_decode() {
+        return tr!("network_decode");
+    }
+    if err.is_body() {
+        return tr!("network_body");
+    }
+    if err.is_redirect() {
+        return tr!("network_redirect");
+    }
+    if err.is_builder() {
+        return tr!("network_builder");
+    }
+    tr!("network_failed")
+}
+
+/// Result type used throughout the library.
+pub type Result<T> = std::result::Result<T, Error>;
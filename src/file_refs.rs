@@ -0,0 +1,82 @@
+use log::{debug, warn};
+use mistral_chat_cli_rs::tr;
+use std::fs;
+use std::path::Path;
+
+/// Punctuation that ends a sentence rather than a path, as in "look at @src/main.rs."
+const TRAILING_PUNCTUATION: &[char] = &['.', ',', ';', ':', '!', '?', ')', ']', '"', '\''];
+
+/// Expands `@path` references in `prompt` to files appended after it.
+///
+/// A reference is an `@` at the start of a word naming a readable text file; it is replaced by
+/// the path and the file's contents follow the prompt in a fenced block, once per file. Anything
+/// else starting with `@`, such as a missing path or an email address, is left as written.
+pub fn expand(prompt: &str) -> String {
+    let mut text = String::with_capacity(prompt.len());
+    let mut attached: Vec<(String, String)> = Vec::new();
+    let mut rest = prompt;
+    while let Some(at) = rest.find('@') {
+        let starts_word = rest[..at]
+            .chars()
+            .next_back()
+            .or_else(|| text.chars().next_back())
+            .is_none_or(|c| c.is_whitespace() || matches!(c, '(' | '[' | '"' | '\''));
+        text.push_str(&rest[..at]);
+        rest = &rest[at + 1..];
+        let end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+        let word = &rest[..end];
+        match starts_word.then(|| resolve(word)).flatten() {
+            Some((path, content)) => {
+                text.push_str(&path);
+                rest = &rest[path.len()..];
+                if !attached.iter().any(|(known, _)| *known == path) {
+                    attached.push((path, content));
+                }
+            }
+            None => text.push('@'),
+        }
+    }
+    text.push_str(rest);
+
+    for (path, content) in attached {
+        text.push_str("\n\n");
+        text.push_str(&fenced(&path, &content));
+    }
+    text
+}
+
+/// Formats a file for a prompt: its path, then its contents in a block fenced so that nothing in
+/// the file can close it early, tagged with the file's extension.
+pub fn fenced(path: &str, content: &str) -> String {
+    let longest = content.split(|c| c != '`').map(str::len).max().unwrap_or(0);
+    let fence = "`".repeat(longest.max(2) + 1);
+    let language = Path::new(path)
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .unwrap_or_default();
+    let newline = if content.ends_with('\n') { "" } else { "\n" };
+    format!(
+        "{}:\n{}{}\n{}{}{}",
+        path, fence, language, content, newline, fence
+    )
+}
+
+/// Finds the file `word` refers to, trying it without trailing punctuation as well, and reads it.
+fn resolve(word: &str) -> Option<(String, String)> {
+    let mut candidate = word;
+    loop {
+        if !candidate.is_empty() && Path::new(candidate).is_file() {
+            return match fs::read_to_string(candidate) {
+                Ok(content) => {
+                    debug!("Attaching {} to the prompt", candidate);
+                    Some((candidate.to_string(), content))
+                }
+                Err(e) => {
+                    warn!("{}", tr!("file_ref_unreadable", candidate, e));
+                    None
+                }
+            };
+        }
+        candidate = candidate.strip_suffix(TRAILING_PUNCTUATION)?;
+    }
+}
@@ -0,0 +1,46 @@
+use log::{debug, warn};
+use mistral_chat_cli_rs::tr;
+use std::process::Command;
+
+/// Runs a git command, returning its stdout on success.
+fn git(args: &[&str]) -> Option<String> {
+    let output = Command::new("git").args(args).output().ok()?;
+    if !output.status.success() {
+        debug!(
+            "git {} failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+        return None;
+    }
+    Some(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Summarizes the current repository for a prompt, or `None` when not inside one.
+///
+/// Includes the branch and changed files, plus the staged diff when `staged` is set.
+pub fn context(staged: bool) -> Option<String> {
+    let Some(branch) = git(&["rev-parse", "--abbrev-ref", "HEAD"]) else {
+        warn!("{}", tr!("not_git_repo"));
+        return None;
+    };
+
+    let mut context = format!("Git branch: {}\n", branch.trim());
+    let status = git(&["status", "--porcelain"]).unwrap_or_default();
+    if status.trim().is_empty() {
+        context.push_str("Changed files: none\n");
+    } else {
+        context.push_str("Changed files:\n");
+        context.push_str(&status);
+    }
+
+    if staged {
+        let diff = git(&["diff", "--staged"]).unwrap_or_default();
+        if diff.trim().is_empty() {
+            context.push_str("\nStaged diff: none\n");
+        } else {
+            context.push_str(&format!("\nStaged diff:\n```diff\n{}```\n", diff));
+        }
+    }
+    Some(context)
+}
@@ -0,0 +1,220 @@
+use crate::markdown;
+use crate::RequestMessage;
+
+/// Elements whose contents are never readable text, or are only site navigation.
+const SKIPPED: [&str; 7] = [
+    "script", "style", "head", "nav", "noscript", "svg", "template",
+];
+
+/// Elements that end the current line.
+const LINE_BREAKS: [&str; 4] = ["br", "li", "tr", "dt"];
+
+/// Elements that separate paragraphs in the extracted text.
+const PARAGRAPHS: [&str; 17] = [
+    "p",
+    "div",
+    "ul",
+    "ol",
+    "table",
+    "section",
+    "article",
+    "header",
+    "footer",
+    "h1",
+    "h2",
+    "h3",
+    "h4",
+    "h5",
+    "h6",
+    "pre",
+    "blockquote",
+];
+
+/// Extracts the readable text of an HTML page.
+///
+/// Scripts, styles, navigation and the document head are dropped, block elements become line breaks,
+/// runs of whitespace are collapsed and common character entities are decoded.
+pub fn to_text(html: &str) -> String {
+    let mut text = String::new();
+    let mut rest = html;
+    // The element whose contents are being skipped, if any.
+    let mut skipping: Option<String> = None;
+
+    while let Some(start) = rest.find('<') {
+        if skipping.is_none() {
+            push_text(&mut text, &rest[..start]);
+        }
+        rest = &rest[start..];
+        if rest.starts_with("<!--") {
+            rest = rest.find("-->").map_or("", |end| &rest[end + 3..]);
+            continue;
+        }
+        let Some(end) = rest.find('>') else {
+            rest = "";
+            break;
+        };
+        let tag = &rest[1..end];
+        rest = &rest[end + 1..];
+
+        let closing = tag.starts_with('/');
+        let name: String = tag
+            .trim_start_matches('/')
+            .chars()
+            .take_while(|c| c.is_ascii_alphanumeric())
+            .collect::<String>()
+            .to_ascii_lowercase();
+        match &skipping {
+            Some(skipped) if closing && *skipped == name => skipping = None,
+            Some(_) => {}
+            None if !closing && !tag.ends_with('/') && SKIPPED.contains(&name.as_str()) => {
+                skipping = Some(name);
+            }
+            None if LINE_BREAKS.contains(&name.as_str()) => end_line(&mut text, "\n"),
+            None if PARAGRAPHS.contains(&name.as_str()) => end_line(&mut text, "\n\n"),
+            None => {}
+        }
+    }
+    if skipping.is_none() {
+        push_text(&mut text, rest);
+    }
+
+    text.trim().to_string()
+}
+
+/// Ends the text with `ending` (a line or paragraph break) unless it already does.
+fn end_line(text: &mut String, ending: &str) {
+    let trimmed = text.trim_end_matches(' ').len();
+    text.truncate(trimmed);
+    if text.is_empty() || text.ends_with(ending) {
+        return;
+    }
+    if ending == "\n\n" && text.ends_with('\n') {
+        text.push('\n');
+    } else {
+        text.push_str(ending);
+    }
+}
+
+/// Appends a run of text between tags, collapsing whitespace and decoding entities.
+fn push_text(text: &mut String, raw: &str) {
+    for (i, word) in raw.split_whitespace().enumerate() {
+        let separate = i > 0 || raw.starts_with(char::is_whitespace);
+        if separate && !text.is_empty() && !text.ends_with(['\n', ' ']) {
+            text.push(' ');
+        }
+        text.push_str(&decode_entities(word));
+    }
+    if raw.ends_with(char::is_whitespace) && !text.is_empty() && !text.ends_with(['\n', ' ']) {
+        text.push(' ');
+    }
+}
+
+/// Decodes the named and numeric character references common in page text.
+fn decode_entities(word: &str) -> String {
+    if !word.contains('&') {
+        return word.to_string();
+    }
+    let mut decoded = String::new();
+    let mut rest = word;
+    while let Some(start) = rest.find('&') {
+        decoded.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let Some(end) = rest.find(';').filter(|end| *end <= 10) else {
+            decoded.push('&');
+            rest = &rest[1..];
+            continue;
+        };
+        let entity = &rest[1..end];
+        let ch = match entity {
+            "amp" => Some('&'),
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "quot" => Some('"'),
+            "apos" | "#39" => Some('\''),
+            "nbsp" => Some(' '),
+            _ => entity
+                .strip_prefix("#x")
+                .or_else(|| entity.strip_prefix("#X"))
+                .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+                .or_else(|| entity.strip_prefix('#').and_then(|dec| dec.parse().ok()))
+                .and_then(char::from_u32),
+        };
+        match ch {
+            Some(ch) => {
+                decoded.push(ch);
+                rest = &rest[end + 1..];
+            }
+            None => {
+                decoded.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    decoded.push_str(rest);
+    decoded
+}
+
+/// Styling for exported conversations, inlined so the page needs nothing else to display.
+const TRANSCRIPT_STYLE: &str = "
+body { max-width: 50rem; margin: 2rem auto; padding: 0 1rem; color: #1f2328;
+  font: 16px/1.5 system-ui, sans-serif; }
+h1 { font-size: 1.4rem; }
+section { margin: 1.5rem 0; padding: 0.5rem 1rem; border-left: 4px solid #d0d7de; }
+section.user { border-color: #0969da; }
+section.assistant { border-color: #1a7f37; }
+section.system { border-color: #9a6700; }
+section > h2 { margin: 0.5rem 0; font-size: 0.8rem; text-transform: uppercase;
+  letter-spacing: 0.05em; color: #59636e; }
+pre { padding: 0.75rem; overflow-x: auto; background: #f6f8fa; border-radius: 6px; }
+code { font: 0.9em ui-monospace, monospace; }
+:not(pre) > code { padding: 0.1em 0.3em; background: #eff1f3; border-radius: 4px; }
+blockquote { margin-left: 0; padding-left: 1rem; color: #59636e; border-left: 3px solid #d0d7de; }
+.keyword { color: #cf222e; }
+.string { color: #0a3069; }
+.number { color: #0550ae; }
+.comment { color: #6e7781; font-style: italic; }
+";
+
+/// Renders a conversation as a self-contained HTML page titled `title`, one section per message
+/// with its Markdown rendered, under the same `You`, `Assistant` and `System` headings as a
+/// Markdown transcript.
+pub fn transcript(messages: &[RequestMessage], title: &str) -> String {
+    let mut html = format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n\
+         <title>{0}</title>\n<style>{1}</style>\n</head>\n<body>\n<h1>{0}</h1>\n",
+        escape(title),
+        TRANSCRIPT_STYLE
+    );
+    for message in messages {
+        let heading = match message.role.as_str() {
+            "user" => "You",
+            "assistant" => "Assistant",
+            "system" => "System",
+            other => other,
+        };
+        html.push_str(&format!(
+            "<section class=\"{}\">\n<h2>{}</h2>\n{}</section>\n",
+            escape(&message.role),
+            escape(heading),
+            markdown::to_html(&message.content)
+        ));
+    }
+    html.push_str("</body>\n</html>\n");
+    html
+}
+
+/// Escapes the characters that are markup in HTML text and attribute values.
+pub fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for ch in text.chars() {
+        match ch {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(ch),
+        }
+    }
+    escaped
+}
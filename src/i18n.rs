@@ -0,0 +1,860 @@
+use std::fmt::Display;
+use std::sync::OnceLock;
+
+/// Languages with a bundled message catalog.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Lang {
+    En,
+    Fr,
+}
+
+static LANG: OnceLock<Lang> = OnceLock::new();
+
+/// Picks the language from `--lang`, then `LC_ALL`, `LC_MESSAGES` and `LANG`, falling back to
+/// English for anything without a catalog. Only the first call has an effect.
+pub fn init(flag: Option<&str>) {
+    let requested = flag.map(str::to_string).or_else(|| {
+        ["LC_ALL", "LC_MESSAGES", "LANG"]
+            .iter()
+            .filter_map(|name| std::env::var(name).ok())
+            .find(|value| !value.is_empty())
+    });
+    // Locale names look like `fr_FR.UTF-8`; only the language part matters here.
+    let lang = match requested
+        .as_deref()
+        .and_then(|value| value.split(['_', '-', '.', '@']).next())
+        .map(str::to_lowercase)
+        .as_deref()
+    {
+        Some("fr") => Lang::Fr,
+        _ => Lang::En,
+    };
+    let _ = LANG.set(lang);
+}
+
+/// Formats catalog message `key` in the current language: `tr!("no_preset", name)`.
+#[macro_export]
+macro_rules! tr {
+    ($key:expr $(, $arg:expr)* $(,)?) => {
+        $crate::i18n::lookup($key, &[$(&$arg as &dyn std::fmt::Display),*])
+    };
+}
+
+/// Returns message `key` with each `{}` replaced by the next of `args`.
+///
+/// Messages missing from a translation fall back to English; unknown keys are returned as-is.
+pub fn lookup(key: &str, args: &[&dyn Display]) -> String {
+    let lang = *LANG.get().unwrap_or(&Lang::En);
+    let Some((_, en, fr)) = MESSAGES.iter().find(|(name, _, _)| *name == key) else {
+        return key.to_string();
+    };
+    let template = match lang {
+        Lang::Fr if !fr.is_empty() => fr,
+        _ => en,
+    };
+
+    let mut message = String::new();
+    let mut args = args.iter();
+    let mut pieces = template.split("{}");
+    message.push_str(pieces.next().unwrap_or_default());
+    for piece in pieces {
+        if let Some(arg) = args.next() {
+            message.push_str(&arg.to_string());
+        }
+        message.push_str(piece);
+    }
+    message
+}
+
+/// User-facing messages as `(key, English, French)`.
+const MESSAGES: &[(&str, &str, &str)] = &[
+    // chat
+    (
+        "prompt_injection",
+        "Possible prompt injection in message {} ({}): {} ({})",
+        "Injection de prompt possible dans le message {} ({}) : {} ({})",
+    ),
+    (
+        "lint_refused",
+        "Refusing to send messages with {} suspicious pattern(s); pass --force to send anyway",
+        "Envoi refusé : {} motif(s) suspect(s) dans les messages ; ajoutez --force pour envoyer quand même",
+    ),
+    (
+        "messages_read_failed",
+        "Failed to read messages file {}",
+        "Impossible de lire le fichier de messages {}",
+    ),
+    (
+        "messages_parse_failed",
+        "Failed to parse messages file {}",
+        "Impossible d'analyser le fichier de messages {}",
+    ),
+    (
+        "messages_empty",
+        "Messages file {} contains no messages",
+        "Le fichier de messages {} ne contient aucun message",
+    ),
+    (
+        "transcript_read_failed",
+        "Failed to read transcript {}",
+        "Impossible de lire la transcription {}",
+    ),
+    (
+        "transcript_parse_failed",
+        "Failed to parse transcript {}",
+        "Impossible d'analyser la transcription {}",
+    ),
+    (
+        "image_read_failed",
+        "Failed to read the image from stdin",
+        "Impossible de lire l'image depuis l'entrée standard",
+    ),
+    (
+        "image_not_base64",
+        "stdin is not valid base64 (pipe it through `base64` first)",
+        "l'entrée standard n'est pas du base64 valide (passez-la d'abord par `base64`)",
+    ),
+    (
+        "image_unknown_format",
+        "stdin does not contain a PNG, JPEG, GIF or WebP image",
+        "l'entrée standard ne contient pas d'image PNG, JPEG, GIF ou WebP",
+    ),
+    (
+        "no_preset",
+        "No preset named {} in the configuration",
+        "Aucun préréglage nommé {} dans la configuration",
+    ),
+    (
+        "history_save_failed",
+        "Failed to save prompt history: {}",
+        "Impossible d'enregistrer l'historique des prompts : {}",
+    ),
+    (
+        "history_shortened",
+        "Shortened the conversation by {} earlier messages",
+        "Conversation raccourcie de {} messages antérieurs",
+    ),
+    (
+        "still_truncated",
+        "Response still truncated after {} continuations",
+        "Réponse toujours tronquée après {} continuations",
+    ),
+    (
+        "resuming",
+        "Connection lost mid-response; resuming ({}/{})",
+        "Connexion perdue en pleine réponse ; reprise ({}/{})",
+    ),
+    (
+        "sink_failed",
+        "Failed to write the response to a sink: {}",
+        "Impossible d'écrire la réponse vers une destination : {}",
+    ),
+    (
+        "explaining_error",
+        "Asking {} to explain the error (one extra request)",
+        "Demande à {} d'expliquer l'erreur (une requête supplémentaire)",
+    ),
+    (
+        "explain_failed",
+        "Could not get an explanation: {}",
+        "Impossible d'obtenir une explication : {}",
+    ),
+    (
+        "nothing_to_cache",
+        "--cache-prompt has no system message to mark",
+        "--cache-prompt n'a aucun message système à marquer",
+    ),
+    (
+        "pretty_needs_jsonl",
+        "--pretty only applies to --stream-format jsonl",
+        "--pretty ne s'applique qu'à --stream-format jsonl",
+    ),
+    (
+        "no_logprobs",
+        "The API returned no log-probabilities for {}",
+        "L'API n'a renvoyé aucune log-probabilité pour {}",
+    ),
+    ("column_token", "Token", "Jeton"),
+    ("column_logprob", "Logprob", "Log-prob."),
+    ("column_probability", "Probability", "Probabilité"),
+    ("column_alternatives", "Top alternatives", "Meilleures alternatives"),
+    // code
+    (
+        "no_code_blocks",
+        "The response contains no fenced code blocks",
+        "La réponse ne contient aucun bloc de code délimité",
+    ),
+    (
+        "overlap_too_large",
+        "--overlap must be smaller than --chunk-size",
+        "--overlap doit être inférieur à --chunk-size",
+    ),
+    ("analyzing_chunk", "Analyzing chunk {}/{}", "Analyse du morceau {}/{}"),
+    ("chunk_heading", "Chunk {}/{}:", "Morceau {}/{} :"),
+    (
+        "summarizing_chunks",
+        "Summarizing {} chunk analyses",
+        "Synthèse de {} analyses de morceaux",
+    ),
+    (
+        "not_git_repo",
+        "Not inside a git repository; continuing without git context",
+        "Pas dans un dépôt git ; poursuite sans contexte git",
+    ),
+    // summarize, ocr, files
+    (
+        "no_readable_text",
+        "No readable text found at {}",
+        "Aucun texte lisible trouvé à {}",
+    ),
+    (
+        "page_truncated",
+        "Page text truncated to {} characters",
+        "Texte de la page tronqué à {} caractères",
+    ),
+    (
+        "ocr_written",
+        "OCR result written to {}",
+        "Résultat de l'OCR écrit dans {}",
+    ),
+    ("file_uploaded", "Uploaded {} as {}", "{} envoyé sous l'identifiant {}"),
+    ("no_files", "No files uploaded", "Aucun fichier envoyé"),
+    ("no_models", "No models found", "Aucun modèle d'IA trouvé"),
+    ("column_id", "ID", "ID"),
+    ("column_context", "Context", "Contexte"),
+    ("column_capabilities", "Capabilities", "Capacités"),
+    ("file_deleted", "Deleted {}", "{} supprimé"),
+    ("column_phase", "Phase", "Phase"),
+    ("column_ms", "ms", "ms"),
+    ("timing_dns", "DNS lookup", "Résolution DNS"),
+    ("timing_connect", "Connect + TLS", "Connexion + TLS"),
+    ("timing_first_byte", "Time to first byte", "Premier octet"),
+    ("timing_first_token", "Time to first token", "Premier jeton"),
+    ("timing_total", "Total", "Total"),
+    ("timing_reused", "reused connection", "connexion réutilisée"),
+    (
+        "input_too_large",
+        "{} is {} bytes, over the input limit of {} bytes (--max-input-bytes or max_input_bytes)",
+        "{} fait {} octets, au-delà de la limite de {} octets (--max-input-bytes ou max_input_bytes)",
+    ),
+    (
+        "file_ref_unreadable",
+        "Not attaching @{}: {}",
+        "@{} n'est pas joint : {}",
+    ),
+    (
+        "rewrite_no_code",
+        "The response contained no code block; the file was left unchanged",
+        "La réponse ne contenait aucun bloc de code ; le fichier n'a pas été modifié",
+    ),
+    (
+        "rewrite_written",
+        "Rewrote {} (original saved as {})",
+        "{} réécrit (original enregistré sous {})",
+    ),
+    ("input_prompt", "The prompt", "Le prompt"),
+    ("input_code", "The code", "Le code"),
+    ("input_stdin", "Standard input", "L'entrée standard"),
+    ("value_unset", "not set", "non défini"),
+    ("source_default", "built-in default", "valeur par défaut"),
+    ("source_file", "from {}", "depuis {}"),
+    ("source_environment", "from ${}", "depuis ${}"),
+    ("source_flag", "from {}", "depuis {}"),
+    ("editor_failed", "Could not run the editor", "Impossible de lancer l'éditeur"),
+    (
+        "example_invalid",
+        "--example \"{}\" must be USER::ASSISTANT with text on both sides",
+        "--example « {} » doit être de la forme UTILISATEUR::ASSISTANT, avec du texte des deux côtés",
+    ),
+    ("editor_empty", "Aborting: the prompt is empty", "Abandon : le prompt est vide"),
+    (
+        "replay_invalid",
+        "{} is not a JSON request body",
+        "{} n'est pas un corps de requête JSON",
+    ),
+    ("replay_status", "The API answered {}", "L'API a répondu {}"),
+    (
+        "redacted",
+        "Masked {} match(es) of the redact patterns in the response",
+        "{} correspondance(s) des motifs de masquage masquée(s) dans la réponse",
+    ),
+    ("served_by", "Response served by {}", "Réponse fournie par {}"),
+    (
+        "context_too_long",
+        "The prompt does not fit the model's context window; pass --larger-context-models to retry with a bigger model, or --history-limit to shorten the conversation",
+        "Le prompt dépasse la fenêtre de contexte du modèle ; passez --larger-context-models pour réessayer avec un modèle plus grand, ou --history-limit pour raccourcir la conversation",
+    ),
+    (
+        "context_too_long_all",
+        "The prompt does not fit the context window of the larger context models tried either; shorten the conversation, e.g. with --history-limit",
+        "Le prompt dépasse aussi la fenêtre de contexte des modèles à plus grand contexte essayés ; raccourcissez la conversation, par exemple avec --history-limit",
+    ),
+    (
+        "export_failed",
+        "Failed to write the HTML export to {}",
+        "Impossible d'écrire l'export HTML dans {}",
+    ),
+    (
+        "meta_invalid",
+        "expected KEY=VALUE with a non-empty key",
+        "KEY=VALEUR attendu, avec une clé non vide",
+    ),
+    (
+        "deadline_invalid",
+        "expected seconds, or a count with an s, m or h suffix such as 90s or 2m",
+        "nombre de secondes attendu, ou une durée suffixée par s, m ou h comme 90s ou 2m",
+    ),
+    ("export_title", "Conversation with {}", "Conversation avec {}"),
+    ("no_batch_jobs", "No batch jobs found", "Aucune tâche de traitement par lots"),
+    ("column_status", "Status", "Statut"),
+    ("column_created", "Created", "Créée"),
+    ("column_model", "Model", "Modèle"),
+    ("column_progress", "Progress", "Avancement"),
+    ("batch_failed_count", "{} failed", "{} en échec"),
+    (
+        "batch_succeeded",
+        "Succeeded: {}, failed: {}",
+        "Réussies : {}, en échec : {}",
+    ),
+    ("batch_output_file", "Output file: {}", "Fichier de résultats : {}"),
+    ("batch_error_file", "Error file: {}", "Fichier d'erreurs : {}"),
+    (
+        "batch_cancelled",
+        "Cancellation requested for {} (status {})",
+        "Annulation demandée pour {} (statut {})",
+    ),
+    (
+        "batch_invalid_line",
+        "Line {} of the batch file is not valid JSON",
+        "La ligne {} du fichier de lot n'est pas du JSON valide",
+    ),
+    (
+        "batch_no_body",
+        "Line {} of the batch file has no \"body\" object",
+        "La ligne {} du fichier de lot n'a pas d'objet « body »",
+    ),
+    (
+        "batch_deduplicated",
+        "{} request(s), {} to send: {} duplicate(s) will reuse an identical request's answer",
+        "{} requête(s), {} à envoyer : {} doublon(s) reprendront la réponse d'une requête identique",
+    ),
+    (
+        "batch_run_failed",
+        "{} of {} request(s) failed",
+        "{} requête(s) sur {} en échec",
+    ),
+    // config, templates, system prompts, history
+    (
+        "config_read_failed",
+        "Failed to read configuration file",
+        "Impossible de lire le fichier de configuration",
+    ),
+    (
+        "config_generate_failed",
+        "Failed to generate config file",
+        "Impossible de générer le fichier de configuration",
+    ),
+    (
+        "config_generated",
+        "Sample config file generated at {}",
+        "Fichier de configuration d'exemple généré dans {}",
+    ),
+    (
+        "config_loaded",
+        "Configuration loaded from {}",
+        "Configuration chargée depuis {}",
+    ),
+    ("key_set", "Set {} in {}", "{} défini dans {}"),
+    ("key_removed", "Removed {} from {}", "{} retiré de {}"),
+    ("write_failed", "Failed to write {}", "Impossible d'écrire {}"),
+    ("passphrase_prompt", "Passphrase: ", "Phrase secrète : "),
+    (
+        "passphrase_repeat",
+        "Repeat passphrase: ",
+        "Répétez la phrase secrète : ",
+    ),
+    (
+        "passphrase_mismatch",
+        "The passphrases do not match",
+        "Les phrases secrètes ne correspondent pas",
+    ),
+    (
+        "passphrase_empty",
+        "The passphrase must not be empty",
+        "La phrase secrète ne doit pas être vide",
+    ),
+    ("cancelled", "Cancelled", "Annulé"),
+    ("bundle_exported", "Exported {} to {}", "{} exporté vers {}"),
+    ("bundle_imported", "Imported {} into {}", "{} importé dans {}"),
+    (
+        "bundle_not_config",
+        "The bundle does not contain a valid configuration file",
+        "Le paquet ne contient pas de fichier de configuration valide",
+    ),
+    (
+        "config_exists",
+        "{} already exists; pass --force to replace it",
+        "{} existe déjà ; ajoutez --force pour le remplacer",
+    ),
+    (
+        "setup_offer",
+        "No configuration file at {}. Create one now?",
+        "Aucun fichier de configuration à {}. En créer un maintenant ?",
+    ),
+    ("setup_mistral_key", "Mistral API key: ", "Clé d'API Mistral : "),
+    (
+        "setup_codestral_key",
+        "Codestral API key (empty to skip): ",
+        "Clé d'API Codestral (vide pour ignorer) : ",
+    ),
+    (
+        "setup_model",
+        "Default model (empty to pick from each prompt)",
+        "Modèle par défaut (vide pour le choisir selon le prompt)",
+    ),
+    ("setup_key_required", "A Mistral API key is required", "Une clé d'API Mistral est requise"),
+    ("setup_written", "Configuration written to {}", "Configuration écrite dans {}"),
+    (
+        "config_missing",
+        "No configuration file at {}. Create one with `config generate` and add your API keys, pass --config <PATH>, or give --mistral-key and --codestral-key",
+        "Aucun fichier de configuration à {}. Créez-en un avec `config generate` et ajoutez vos clés d'API, indiquez --config <CHEMIN>, ou passez --mistral-key et --codestral-key",
+    ),
+    ("no_templates", "No templates found", "Aucun modèle trouvé"),
+    (
+        "no_system_prompts",
+        "No system prompts found",
+        "Aucun prompt système trouvé",
+    ),
+    ("no_presets", "No presets defined", "Aucun préréglage défini"),
+    ("config_current", "Current Configuration:", "Configuration actuelle :"),
+    ("setting.mistral_api_key", "Mistral API Key: {}", "Clé d'API Mistral : {}"),
+    ("setting.codestral_api_key", "Codestral API Key: {}", "Clé d'API Codestral : {}"),
+    ("setting.debug", "Debug Mode: {}", "Mode débogage : {}"),
+    (
+        "setting.danger_accept_invalid_certs",
+        "Accept Invalid Certificates: {}",
+        "Accepter les certificats invalides : {}",
+    ),
+    ("setting.ca_cert", "CA Certificate: {}", "Certificat d'autorité : {}"),
+    ("setting.user_agent", "User Agent: {}", "User-Agent : {}"),
+    ("setting.auth_command", "Auth Command: {}", "Commande d'authentification : {}"),
+    ("setting.preprocess_command", "Preprocess Command: {}", "Commande de prétraitement : {}"),
+    ("setting.request_template", "Request Template: {}", "Modèle de requête : {}"),
+    ("setting.stream_content_path", "Stream Content Path: {}", "Chemin du contenu diffusé : {}"),
+    ("setting.code_system_prompt", "Code System Prompt: {}", "Prompt système pour le code : {}"),
+    ("setting.tts_command", "TTS Command: {}", "Commande de synthèse vocale : {}"),
+    ("setting.pager", "Pager: {}", "Pagineur : {}"),
+    ("setting.model", "Model: {}", "Modèle : {}"),
+    ("setting.reasoning_effort", "Reasoning Effort: {}", "Effort de raisonnement : {}"),
+    ("setting.max_retries", "Max Retries: {}", "Nombre maximal de tentatives : {}"),
+    ("setting.retry_delay_ms", "Retry Delay: {} ms", "Délai entre les tentatives : {} ms"),
+    (
+        "setting.max_retries_on_429",
+        "Max Retries on 429: {}",
+        "Nombre maximal de tentatives après un 429 : {}",
+    ),
+    ("setting.retry_delay_on_429_ms", "Retry Delay on 429: {} ms", "Délai après un 429 : {} ms"),
+    ("setting.deadline_secs", "Deadline: {} s", "Échéance : {} s"),
+    ("setting.requests_per_minute", "Requests per Minute: {}", "Requêtes par minute : {}"),
+    ("setting.http2_prior_knowledge", "HTTP/2 Prior Knowledge: {}", "HTTP/2 sans négociation : {}"),
+    ("setting.tcp_keepalive_secs", "TCP Keepalive: {} s", "Keepalive TCP : {} s"),
+    ("setting.connect_timeout_ms", "Connect Timeout: {} ms", "Délai de connexion : {} ms"),
+    ("setting.no_proxy", "No Proxy: {}", "Sans proxy : {}"),
+    ("setting.max_input_bytes", "Max Input Bytes: {}", "Taille maximale de l'entrée (octets) : {}"),
+    (
+        "setting.max_response_bytes",
+        "Max Response Bytes: {}",
+        "Taille maximale de la réponse (octets) : {}",
+    ),
+    ("setting.redact_patterns", "Redact Patterns: {}", "Motifs masqués : {}"),
+    ("setting.fallback_models", "Fallback Models: {}", "Modèles de secours : {}"),
+    (
+        "setting.larger_context_models",
+        "Larger Context Models: {}",
+        "Modèles à contexte plus large : {}",
+    ),
+    ("setting.allowed_models", "Allowed Models: {}", "Modèles autorisés : {}"),
+    ("setting.metadata", "Metadata: {}", "Métadonnées : {}"),
+    ("setting.chat.model", "Model (chat): {}", "Modèle (chat) : {}"),
+    (
+        "setting.chat.reasoning_effort",
+        "Reasoning Effort (chat): {}",
+        "Effort de raisonnement (chat) : {}",
+    ),
+    ("setting.code.model", "Model (code): {}", "Modèle (code) : {}"),
+    (
+        "setting.code.reasoning_effort",
+        "Reasoning Effort (code): {}",
+        "Effort de raisonnement (code) : {}",
+    ),
+    (
+        "config_dir_unknown",
+        "Could not determine the user config directory",
+        "Impossible de déterminer le répertoire de configuration de l'utilisateur",
+    ),
+    (
+        "data_dir_unknown",
+        "Could not determine the user data directory",
+        "Impossible de déterminer le répertoire de données de l'utilisateur",
+    ),
+    (
+        "cache_dir_unknown",
+        "Could not determine the user cache directory",
+        "Impossible de déterminer le répertoire de cache de l'utilisateur",
+    ),
+    (
+        "model_list_failed",
+        "Could not load the model list, keeping the default model: {}",
+        "Impossible de charger la liste des modèles, le modèle par défaut est conservé : {}",
+    ),
+    ("pick_model", "Model", "Modèle"),
+    (
+        "no_context_window",
+        "The API reports no context window for {}; the conversation is sent as is",
+        "L'API n'indique aucune fenêtre de contexte pour {} ; la conversation est envoyée telle quelle",
+    ),
+    (
+        "no_context_window_for_max_tokens",
+        "The API reports no context window for {}; the response length is left to the API",
+        "L'API n'indique aucune fenêtre de contexte pour {} ; la longueur de la réponse est laissée à l'API",
+    ),
+    (
+        "context_nearly_full",
+        "The prompt (~{} tokens) nearly fills the {}-token context window; allowing {} response tokens",
+        "Le prompt (~{} jetons) remplit presque la fenêtre de contexte de {} jetons ; {} jetons de réponse autorisés",
+    ),
+    (
+        "context_window_failed",
+        "Could not look up the context window: {}",
+        "Impossible de déterminer la fenêtre de contexte : {}",
+    ),
+    ("read_failed", "Failed to read {}", "Impossible de lire {}"),
+    (
+        "watching",
+        "Watching {} for changes (Ctrl-C to stop)",
+        "Surveillance de {} (Ctrl-C pour arrêter)",
+    ),
+    (
+        "watch_run_failed",
+        "Analysis failed, waiting for the next change: {}",
+        "Échec de l'analyse, en attente de la prochaine modification : {}",
+    ),
+    ("parse_failed", "Failed to parse {}", "Impossible d'analyser {}"),
+    (
+        "template_read_failed",
+        "Failed to read template {}",
+        "Impossible de lire le modèle {}",
+    ),
+    (
+        "no_template",
+        "No template named {} in {}",
+        "Aucun modèle nommé {} dans {}",
+    ),
+    (
+        "template_needs_prompt",
+        "Template {} expects a prompt for {}",
+        "Le modèle {} attend un prompt pour {}",
+    ),
+    (
+        "system_prompt_read_failed",
+        "Failed to read system prompt {}",
+        "Impossible de lire le prompt système {}",
+    ),
+    (
+        "no_system_prompt",
+        "No system prompt named {} in {}",
+        "Aucun prompt système nommé {} dans {}",
+    ),
+    (
+        "history_corrupt",
+        "Corrupt prompt history entry",
+        "Entrée d'historique des prompts corrompue",
+    ),
+    (
+        "no_prompt_id",
+        "No prompt with ID {} in history",
+        "Aucun prompt avec l'ID {} dans l'historique",
+    ),
+    (
+        "no_history_matches",
+        "No prompts in the history match",
+        "Aucun prompt de l'historique ne correspond",
+    ),
+    (
+        "invalid_time",
+        "Invalid time {}: use RFC 3339 (2026-10-01T09:30:00Z), a date (2026-10-01) or an age (30m, 12h, 7d, 2w)",
+        "Heure invalide {} : utilisez RFC 3339 (2026-10-01T09:30:00Z), une date (2026-10-01) ou une durée (30m, 12h, 7d, 2w)",
+    ),
+    (
+        "no_prompt_matches",
+        "No prompts matching \"{}\"",
+        "Aucun prompt ne correspond à « {} »",
+    ),
+    ("session_usage", "Session usage: {}", "Consommation de la session : {}"),
+    ("tui_ready", "ready", "prêt"),
+    ("tui_shortening", "shortening history...", "raccourcissement de l'historique..."),
+    ("tui_waiting", "waiting for response...", "en attente de la réponse..."),
+    ("tui_streaming", "streaming", "réception en cours"),
+    ("tui_finished", "finished: {}", "terminé : {}"),
+    ("tui_error", "error: {}", "erreur : {}"),
+    (
+        "tui_status",
+        " {} | tokens: {} | {} | PgUp/PgDn scroll, /usage, Esc quit",
+        " {} | jetons : {} | {} | PgUp/PgDn défiler, /usage, Échap quitter",
+    ),
+    (
+        "usage_summary",
+        "prompt {}, completion {}, total {} tokens",
+        "prompt {}, complétion {}, total {} jetons",
+    ),
+    ("usage_cost", " (est. ${})", " (env. {} $)"),
+    // compare, ping
+    (
+        "compare_tokens",
+        "{} prompt + {} completion tokens",
+        "{} jetons de prompt + {} de complétion",
+    ),
+    ("compare_tokens_unknown", "? tokens", "? jetons"),
+    ("ping_up", "up", "actif"),
+    ("ping_down", "down", "inactif"),
+    // bench
+    ("bench_failed", "failed: {}", "échec : {}"),
+    (
+        "bench_summary",
+        "{} of {} requests succeeded with {} (concurrency {})",
+        "{} requête(s) sur {} réussie(s) avec {} (concurrence {})",
+    ),
+    (
+        "bench_sample",
+        "ttft {} ms  total {} ms  {} tokens  {}",
+        "1er jeton {} ms  total {} ms  {} jetons  {}",
+    ),
+    ("bench_rate", "{} tok/s", "{} jetons/s"),
+    ("bench_rate_unknown", "? tok/s", "? jetons/s"),
+    ("bench_min", "min", "min"),
+    ("bench_median", "median", "médiane"),
+    ("bench_p95", "p95", "p95"),
+    ("bench_max", "max", "max"),
+    ("bench_ttft_ms", "ttft ms", "1er jeton ms"),
+    ("bench_total_ms", "total ms", "total ms"),
+    ("bench_tok_s", "tok/s", "jetons/s"),
+    // serve
+    (
+        "socket_in_use",
+        "{} is already in use by a running server",
+        "{} est déjà utilisé par un serveur en cours d'exécution",
+    ),
+    (
+        "socket_listen_failed",
+        "Failed to listen on {}",
+        "Impossible d'écouter sur {}",
+    ),
+    (
+        "socket_remove_failed",
+        "Failed to remove socket {}",
+        "Impossible de supprimer le socket {}",
+    ),
+    ("socket_listening", "Listening on {}", "En écoute sur {}"),
+    ("serve_request_failed", "Request failed: {}", "Échec de la requête : {}"),
+    ("serve_invalid_request", "invalid request: {}", "requête invalide : {}"),
+    // doctor
+    ("doctor_hint", "hint: {}", "conseil : {}"),
+    ("doctor_config_file", "Config file", "Fichier de configuration"),
+    ("doctor_skipped", "skipped (--no-config)", "ignoré (--no-config)"),
+    (
+        "doctor_config_hint",
+        "run `config generate` or pass --config, --no-config or the key flags",
+        "lancez `config generate` ou passez --config, --no-config ou les options de clé",
+    ),
+    ("doctor_api_keys", "API keys", "Clés d'API"),
+    (
+        "doctor_auth_command",
+        "tokens come from auth_command `{}`",
+        "jetons fournis par auth_command `{}`",
+    ),
+    ("doctor_mistral_key", "Mistral API key", "Clé d'API Mistral"),
+    ("doctor_codestral_key", "Codestral API key", "Clé d'API Codestral"),
+    (
+        "doctor_key_hint",
+        "set it in the config file, {} or its environment variable",
+        "définissez-la dans le fichier de configuration, avec {} ou sa variable d'environnement",
+    ),
+    ("doctor_missing", "missing", "absente"),
+    (
+        "doctor_placeholder",
+        "still the placeholder from `config generate`",
+        "encore la valeur d'exemple de `config generate`",
+    ),
+    ("doctor_no_addresses", "no addresses", "aucune adresse"),
+    (
+        "doctor_dns_hint",
+        "check your DNS settings",
+        "vérifiez vos paramètres DNS",
+    ),
+    (
+        "doctor_proxy_hint",
+        "requests go through HTTPS_PROXY, which resolves hosts itself",
+        "les requêtes passent par HTTPS_PROXY, qui résout lui-même les noms",
+    ),
+    (
+        "doctor_network_dns_hint",
+        "check your network connection and DNS settings",
+        "vérifiez votre connexion réseau et vos paramètres DNS",
+    ),
+    ("doctor_http_client", "HTTP client", "Client HTTP"),
+    (
+        "doctor_ca_hint",
+        "check ca_cert in the config file",
+        "vérifiez ca_cert dans le fichier de configuration",
+    ),
+    ("doctor_endpoint", "{} endpoint", "Point d'accès {}"),
+    ("doctor_authentication", "{} authentication", "Authentification {}"),
+    ("doctor_key_accepted", "key accepted", "clé acceptée"),
+    ("doctor_key_rejected", "key rejected ({})", "clé refusée ({})"),
+    ("check_api_key", "Hint: Check your {} API key.", "Astuce : vérifiez votre clé d'API {}."),
+    (
+        "doctor_key_rejected_hint",
+        "check that the key belongs to this endpoint and is still active",
+        "vérifiez que la clé correspond à ce point d'accès et qu'elle est toujours active",
+    ),
+    ("doctor_unexpected_status", "unexpected status {}", "statut inattendu {}"),
+    (
+        "doctor_degraded_hint",
+        "the service may be degraded; try again later",
+        "le service est peut-être dégradé ; réessayez plus tard",
+    ),
+    (
+        "doctor_auth_command_hint",
+        "check auth_command in the config file",
+        "vérifiez auth_command dans le fichier de configuration",
+    ),
+    (
+        "doctor_network_hint",
+        "check your network, proxy settings (HTTPS_PROXY) and firewall",
+        "vérifiez votre réseau, vos paramètres de proxy (HTTPS_PROXY) et votre pare-feu",
+    ),
+    (
+        "doctor_failed",
+        "{} critical check(s) failed",
+        "{} vérification(s) critique(s) en échec",
+    ),
+    (
+        "doctor_passed",
+        "All checks passed",
+        "Toutes les vérifications sont réussies",
+    ),
+    // API client
+    (
+        "tls_verification_disabled",
+        "TLS certificate verification is DISABLED; traffic to the API can be intercepted",
+        "La vérification des certificats TLS est DÉSACTIVÉE ; le trafic vers l'API peut être intercepté",
+    ),
+    (
+        "rate_limited_retrying",
+        "Rate limited; retrying in {}s ({}/{})",
+        "Limite de débit atteinte ; nouvel essai dans {} s ({}/{})",
+    ),
+    ("request_id", "Request ID: {}", "ID de requête : {}"),
+    ("request_failed", "Request failed: {}", "Échec de la requête : {}"),
+    ("retry_attempt", "Retry attempt {}: {}", "Nouvel essai {} : {}"),
+    (
+        "record_chunk_failed",
+        "Failed to record response chunk: {}",
+        "Impossible d'enregistrer un fragment de la réponse : {}",
+    ),
+    (
+        "not_reasoning_model",
+        "{} is not a reasoning model; ignoring the reasoning effort",
+        "{} n'est pas un modèle de raisonnement ; l'effort de raisonnement est ignoré",
+    ),
+    (
+        "first_token_timeout",
+        "No response from the API within the first-token timeout",
+        "Aucune réponse de l'API dans le délai du premier jeton",
+    ),
+    ("larger_context_retry", "{}: {}; retrying with {}", "{} : {} ; nouvel essai avec {}"),
+    ("model_fallback", "{}: {}; falling back to {}", "{} : {} ; repli sur {}"),
+    (
+        "stream_deadline",
+        "The request passed its deadline; stopping with what was received",
+        "La requête a dépassé son échéance ; arrêt avec ce qui a été reçu",
+    ),
+    (
+        "stream_idle",
+        "No data from the API for {}; stopping with what was received",
+        "Aucune donnée de l'API depuis {} ; arrêt avec ce qui a été reçu",
+    ),
+    ("refused_prefix", "Request refused: ", "Requête refusée : "),
+    ("reasoning_prefix", "Reasoning: ", "Raisonnement : "),
+    ("streaming_failed", "Streaming failed: {}", "Échec du streaming : {}"),
+    ("references", "References:", "Références :"),
+    (
+        "stream_no_done",
+        "The stream ended without [DONE]; the response may be incomplete",
+        "Le flux s'est terminé sans [DONE] ; la réponse est peut-être incomplète",
+    ),
+    ("empty_response", "empty response from server", "réponse vide du serveur"),
+    ("connection_failed", "{}-API connection failed: {}", "Échec de la connexion à l'API {} : {}"),
+    ("connection_succeeded", "{}-API connection successful", "Connexion à l'API {} réussie"),
+    ("connection_request_id", "{} request ID: {}", "ID de requête {} : {}"),
+    (
+        "page_too_large",
+        "{} is larger than {} bytes; reading only the start",
+        "{} dépasse {} octets ; seul le début est lu",
+    ),
+    // errors
+    ("error_config", "configuration error", "erreur de configuration"),
+    ("error_network", "network error: {}: {}", "erreur réseau : {} : {}"),
+    ("error_api", "API error ({}): {}{}", "erreur de l'API ({}) : {}{}"),
+    ("error_parse", "parse error: {}", "erreur d'analyse : {}"),
+    ("error_auth", "authentication failed ({}): {}{}", "échec de l'authentification ({}) : {}{}"),
+    ("error_input", "invalid input: {}", "entrée invalide : {}"),
+    (
+        "error_response_too_large",
+        "the response is larger than {} bytes; raise `max_response_bytes` to accept it",
+        "la réponse dépasse {} octets ; augmentez `max_response_bytes` pour l'accepter",
+    ),
+    (
+        "error_deadline",
+        "gave up on the request to keep to its {} deadline, retries included",
+        "requête abandonnée pour respecter son échéance de {}, nouveaux essais compris",
+    ),
+    ("error_context_length", "{} (also too long for {})", "{} (également trop long pour {})"),
+    ("error_io", "I/O error", "erreur d'entrée/sortie"),
+    ("error_request_id", " (request ID: {})", " (ID de requête : {})"),
+    ("network_timed_out", "the request timed out", "la requête a expiré"),
+    (
+        "network_refused",
+        "connection refused (is a firewall or proxy blocking the API?)",
+        "connexion refusée (un pare-feu ou un proxy bloque-t-il l'API ?)",
+    ),
+    ("network_connect_timed_out", "the connection timed out", "la connexion a expiré"),
+    (
+        "network_dns",
+        "DNS lookup failed (check your network connection and DNS settings)",
+        "échec de la résolution DNS (vérifiez votre connexion réseau et vos paramètres DNS)",
+    ),
+    (
+        "network_tls",
+        "TLS handshake failed (a private CA or intercepting proxy may be in the way)",
+        "échec de la négociation TLS (une autorité de certification privée ou un proxy d'interception est peut-être en cause)",
+    ),
+    (
+        "network_connect",
+        "could not connect to the server (check your network or firewall)",
+        "connexion au serveur impossible (vérifiez votre réseau ou votre pare-feu)",
+    ),
+    (
+        "network_decode",
+        "the response body could not be decoded",
+        "le corps de la réponse n'a pas pu être décodé",
+    ),
+    (
+        "network_body",
+        "the response body was interrupted",
+        "le corps de la réponse a été interrompu",
+    ),
+    ("network_redirect", "too many redirects", "trop de redirections"),
+    ("network_builder", "the request could not be built", "la requête n'a pas pu être construite"),
+    ("network_failed", "the request failed", "la requête a échoué"),
+];
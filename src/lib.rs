@@ -0,0 +1,23 @@
+//! Client library for the Mistral and Codestral chat APIs.
+
+pub mod bundle;
+pub mod chunk;
+pub mod client;
+pub mod config;
+pub mod error;
+pub mod html;
+pub mod i18n;
+pub mod lint;
+pub mod markdown;
+pub mod redact;
+pub mod sink;
+pub mod stream;
+pub mod timing;
+pub mod truncate;
+pub mod wrap;
+
+pub use client::{ChatClient, RequestMessage};
+pub use config::{Config, Preset};
+pub use error::{Error, Result};
+pub use stream::{ChatStream, Citation, StreamEvent};
+pub use tokio_util::sync::CancellationToken;
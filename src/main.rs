@@ -1,19 +1,77 @@
-use anyhow::{Context, Result};
-use clap::{Parser, Subcommand};
-use config::{Config as ConfigFile, Environment, File};
-use futures_util::StreamExt;
-use log::{debug, error, info};
-use reqwest::Client;
-use serde::{Deserialize, Serialize};
+use anyhow::{bail, Context, Result};
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use clap::{CommandFactory, Parser, Subcommand};
+use log::{debug, info, warn};
+use mistral_chat_cli_rs::client::{
+    BatchJob, Cassette, CassetteMode, ChatParams, LineEnding, StreamOptions, StreamOutcome,
+    TokenLogprob, CODESTRAL_MODEL, DEFAULT_MAX_RESPONSE_BYTES, DEFAULT_RETRIES,
+    DEFAULT_RETRIES_ON_429, DEFAULT_RETRY_DELAY, DEFAULT_RETRY_DELAY_ON_429, MISTRAL_MODEL,
+    USER_AGENT, VISION_MODEL,
+};
+use mistral_chat_cli_rs::config::{Defaults, Source};
+use mistral_chat_cli_rs::redact::{OutputPatterns, DEFAULT_OUTPUT_PATTERNS};
+use mistral_chat_cli_rs::timing::RequestTimings;
+use mistral_chat_cli_rs::truncate::{estimate_tokens, HistoryLimit, TruncateStrategy};
+use mistral_chat_cli_rs::wrap::Wrapper;
+use mistral_chat_cli_rs::{
+    bundle, chunk, html, i18n, lint, markdown, redact, sink, tr, CancellationToken, ChatClient,
+    Config, RequestMessage,
+};
 use std::fs;
-use std::time::Duration;
-use tokio::io::AsyncWriteExt;
+use std::io::{IsTerminal, Read, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
-// Constants for API endpoints and model names.
-const MISTRAL_URL: &str = "https://api.mistral.ai/v1/chat/completions";
-const CODESTRAL_URL: &str = "https://codestral.mistral.ai/v1/chat/completions";
-const MISTRAL_MODEL: &str = "mistral-large-latest";
-const CODESTRAL_MODEL: &str = "codestral-latest";
+mod batch;
+mod bench;
+mod capabilities;
+mod compare;
+mod doctor;
+mod file_refs;
+mod git;
+mod models;
+mod prompts;
+#[cfg(unix)]
+mod serve;
+mod setup;
+mod system_prompts;
+mod templates;
+mod time;
+mod tui;
+mod watch;
+
+/// Follow-up sent when `--auto-continue` resumes a truncated response.
+const CONTINUE_PROMPT: &str =
+    "Continue exactly where you left off, without repeating or summarizing anything.";
+
+const SUMMARIZE_PROMPT: &str =
+    "Summarize the main points of this web page in a few short paragraphs.";
+
+/// Text-to-speech command for `--speak` when `tts_command` is not configured.
+const DEFAULT_TTS_COMMAND: &str = if cfg!(target_os = "macos") {
+    "say"
+} else {
+    "espeak"
+};
+
+/// Default prompt for `bench`, asking for a few hundred tokens of output.
+const BENCH_PROMPT: &str = "Write a short story of about 300 words about a lighthouse keeper.";
+
+const EXPLAIN_ERROR_PROMPT: &str = "A request to the Mistral API from a command-line chat client \
+    failed with the error below. Explain in plain language what it means and suggest how to fix it, \
+    in a few short sentences.";
+
+/// System message for `rewrite`, asking for the whole file back in a single code block.
+const REWRITE_PROMPT: &str = "You rewrite source files as instructed. Reply with the complete \
+    rewritten file in a single fenced code block and nothing else: no explanations, no omissions \
+    such as \"rest unchanged\".";
+
+/// Fewest tokens `--max-tokens-remaining` lets the response use, however full the window is.
+const MIN_COMPLETION_TOKENS: u32 = 256;
+
+/// Largest web page downloaded by `summarize`.
+const MAX_PAGE_BYTES: usize = 5 * 1024 * 1024;
 
 /// Command-line argument parser for the CLI.
 #[derive(Parser)]
@@ -23,26 +81,573 @@ struct Cli {
     #[arg(long)]
     debug: bool,
 
+    /// Print no warnings or errors, only output; failures are reported by the exit status alone.
+    #[arg(long, conflicts_with = "debug")]
+    quiet_errors: bool,
+
     /// Configuration file to use.
     #[arg(long, default_value = "config.toml")]
     config: String,
 
+    /// Skip the configuration file and rely only on flags and environment variables.
+    #[arg(long)]
+    no_config: bool,
+
+    /// Mistral API key, overriding the configuration file.
+    #[arg(long, env = "APP_MISTRAL_API_KEY", hide_env_values = true)]
+    mistral_key: Option<String>,
+
+    /// Codestral API key, overriding the configuration file.
+    #[arg(long, env = "APP_CODESTRAL_API_KEY", hide_env_values = true)]
+    codestral_key: Option<String>,
+
+    /// Disable TLS certificate verification (e.g. behind an intercepting proxy). Dangerous.
+    #[arg(long)]
+    insecure: bool,
+
+    /// Connect to the APIs directly, ignoring `HTTPS_PROXY`, `ALL_PROXY` and the like.
+    #[arg(long)]
+    no_proxy: bool,
+
+    /// Retries after a transport error such as a refused or reset connection.
+    #[arg(long, value_name = "N")]
+    max_retries: Option<u32>,
+
+    /// Milliseconds between transport-error retries.
+    #[arg(long, value_name = "MS")]
+    retry_delay: Option<u64>,
+
+    /// Retries after a 429 Too Many Requests response, honouring `Retry-After`.
+    #[arg(long, value_name = "N")]
+    max_retries_on_429: Option<u32>,
+
+    /// Milliseconds before the first 429 retry, doubled for each later one.
+    #[arg(long, value_name = "MS")]
+    retry_delay_on_429: Option<u64>,
+
+    /// Give up on a request after this long in all, however many retries remain and with the
+    /// response body included: seconds, or a count with an s, m or h suffix such as `90s` or `2m`.
+    #[arg(long, value_name = "DURATION", value_parser = deadline_secs)]
+    deadline: Option<u64>,
+
+    /// Log the server's ID for every request to stderr, and add it to `--stream-format jsonl`
+    /// output, to match requests with the server's logs.
+    #[arg(long)]
+    echo_request_id: bool,
+
+    /// Refuse, before sending anything, a prompt, file or stdin input larger than this many bytes.
+    #[arg(long, value_name = "BYTES")]
+    max_input_bytes: Option<u64>,
+
+    /// Fail a non-streaming request whose response is larger than this many bytes, instead of
+    /// reading it all into memory.
+    #[arg(long, value_name = "BYTES")]
+    max_response_bytes: Option<u64>,
+
+    /// Tag every chat request with `KEY=VALUE` in its `metadata` object, for usage attribution.
+    /// Repeat for several; adds to and overrides `[metadata]` in the config.
+    #[arg(long, value_name = "KEY=VALUE", value_parser = meta_pair)]
+    meta: Vec<(String, String)>,
+
+    /// Record request bodies and raw responses into this directory.
+    #[arg(long, value_name = "DIR", conflicts_with = "replay")]
+    record: Option<PathBuf>,
+
+    /// Serve responses recorded with `--record` from this directory without network access.
+    #[arg(long, value_name = "DIR")]
+    replay: Option<PathBuf>,
+
+    /// Language for messages, e.g. `fr`; defaults to the locale from LC_ALL, LC_MESSAGES or LANG.
+    #[arg(long, value_name = "LANG")]
+    lang: Option<String>,
+
     /// Subcommand to execute (e.g., chat, test, code, config).
     #[command(subcommand)]
     command: Commands,
 }
 
 /// Enum representing the available subcommands.
+// Parsed once at startup, so the size of the `Chat` variant does not matter.
+#[allow(clippy::large_enum_variant)]
 #[derive(Subcommand)]
 enum Commands {
     /// Send a chat prompt to the API.
-    Chat { prompt: String },
+    #[command(group(clap::ArgGroup::new("history_budget").multiple(true)))]
+    Chat {
+        /// Prompt to send; appended as a final user message when `--messages-file` is given.
+        #[arg(required_unless_present_any = [
+            "messages_file", "from_markdown", "tui", "history_search", "resend", "template",
+            "editor"
+        ])]
+        prompt: Option<String>,
+
+        /// Write the prompt in `$VISUAL` or `$EDITOR`, as `git commit` does; saving an empty
+        /// buffer aborts without sending.
+        #[arg(long, conflicts_with_all = ["prompt", "resend", "tui", "image_stdin"])]
+        editor: bool,
+
+        /// Model to use instead of picking Mistral or Codestral from the prompt.
+        #[arg(long)]
+        model: Option<String>,
+
+        /// Reasoning effort for reasoning models such as Magistral.
+        #[arg(long, value_parser = ["low", "medium", "high"])]
+        reasoning: Option<String>,
+
+        /// Service tier: `flex` trades latency for a lower price on bulk, low-priority work.
+        #[arg(long, value_parser = ["standard", "flex"])]
+        tier: Option<String>,
+
+        /// Print the model's reasoning trace to stderr, separately from the answer.
+        #[arg(long, conflicts_with = "tui")]
+        show_reasoning: bool,
+
+        /// Apply the model and system message of a preset from the config file.
+        #[arg(long, value_name = "NAME")]
+        preset: Option<String>,
+
+        /// Use a named system prompt from the system prompt library as the system message.
+        #[arg(long, value_name = "NAME")]
+        system_preset: Option<String>,
+
+        /// Build the prompt from a template file, substituting `{{prompt}}` with the prompt.
+        #[arg(long, value_name = "NAME")]
+        template: Option<String>,
+
+        /// Fuzzy-search previously sent prompts and print the matches with their IDs.
+        #[arg(long, value_name = "QUERY", exclusive = true)]
+        history_search: Option<String>,
+
+        /// Send the prompt with this ID from the prompt history again.
+        #[arg(long, value_name = "ID", conflicts_with = "prompt")]
+        resend: Option<usize>,
+
+        /// JSON file containing the full messages array to send.
+        #[arg(long, value_name = "PATH")]
+        messages_file: Option<String>,
+
+        /// Markdown transcript of `## You` and `## Assistant` sections to continue.
+        #[arg(long, value_name = "PATH", conflicts_with = "messages_file")]
+        from_markdown: Option<String>,
+
+        /// Send `@path` in the prompt as written instead of attaching that file.
+        #[arg(long)]
+        no_file_refs: bool,
+
+        /// Attach a base64-encoded image read from stdin to the prompt.
+        #[arg(long, requires = "prompt", conflicts_with = "tui")]
+        image_stdin: bool,
+
+        /// A few-shot example turn as `USER::ASSISTANT`, sent before the prompt. Repeat for
+        /// several, in order.
+        #[arg(long, value_name = "USER::ASSISTANT")]
+        example: Vec<String>,
+
+        /// Make the answer begin with this text: it is sent as an assistant prefix for the model to
+        /// continue and printed ahead of the continuation.
+        #[arg(
+            long,
+            value_name = "TEXT",
+            conflicts_with_all = ["tui", "logprobs", "resume", "auto_continue"]
+        )]
+        start_with: Option<String>,
+
+        /// Wrap streamed output to the terminal width on word boundaries.
+        #[arg(long)]
+        wrap: bool,
+
+        /// Print the messages being sent before the response.
+        #[arg(long)]
+        echo_prompt: bool,
+
+        /// Warn about prompt-injection patterns in the messages and refuse to send them.
+        #[arg(long)]
+        lint_prompt: bool,
+
+        /// Send the messages even when `--lint-prompt` finds suspicious patterns.
+        #[arg(long, requires = "lint_prompt")]
+        force: bool,
+
+        /// Print raw text, or one JSON object per delta followed by a final `done` object.
+        #[arg(
+            long,
+            value_name = "FORMAT",
+            default_value = "text",
+            value_parser = ["text", "jsonl"],
+            conflicts_with_all = ["wrap", "echo_prompt", "typewriter", "auto_continue", "resume", "tui"]
+        )]
+        stream_format: String,
+
+        /// With `--stream-format jsonl`, indent each JSON object for reading instead of piping.
+        #[arg(long)]
+        pretty: bool,
+
+        /// Line ending for the printed response: `lf` or `crlf`. Defaults to the platform's.
+        #[arg(long, value_parser = ["lf", "crlf"])]
+        eol: Option<String>,
+
+        /// Send the response to this destination: `stdout`, `file:PATH`, `clipboard` or
+        /// `command:CMD` (run at the end with the response on stdin). Repeat for several;
+        /// stdout is only written when listed or when no sink is given.
+        #[arg(long, value_name = "SINK", conflicts_with = "tui")]
+        sink: Vec<String>,
+
+        /// Give up if the API has not started responding this many seconds after the request.
+        #[arg(long, value_name = "SECS")]
+        timeout_first_token: Option<u64>,
+
+        /// Stop, keeping the output so far, if the stream stalls this many seconds between chunks.
+        #[arg(long, value_name = "SECS")]
+        idle_timeout: Option<u64>,
+
+        /// Print output one character at a time, pausing this many milliseconds between them.
+        /// Ignored when stdout is not a terminal.
+        #[arg(long, value_name = "MS")]
+        typewriter: Option<u64>,
+
+        /// On a terminal, batch the response and flush it at least every this many milliseconds
+        /// instead of after every chunk, for fast models and long outputs.
+        #[arg(long, value_name = "MS", conflicts_with_all = ["typewriter", "tui"])]
+        flush_interval_ms: Option<u64>,
+
+        /// Collect the response and show it in `$PAGER` (`less -R` by default) when it is taller than
+        /// the terminal. Ignored when stdout is not a terminal.
+        #[arg(long, conflicts_with_all = ["tui", "stream_format", "no_pager"])]
+        pager: bool,
+
+        /// Print the whole response as one line once it is complete, with no trailing newline
+        /// unless stdout is a terminal, e.g. for `$(...)` in scripts.
+        #[arg(
+            long,
+            conflicts_with_all = ["tui", "stream_format", "pager", "wrap", "typewriter"]
+        )]
+        oneline: bool,
+
+        /// Print the response as it streams even when `pager` is set in the config.
+        #[arg(long)]
+        no_pager: bool,
+
+        /// Open a full-screen chat interface instead of printing a single response.
+        #[arg(long, conflicts_with_all = ["wrap", "echo_prompt", "typewriter", "auto_continue"])]
+        tui: bool,
+
+        /// Shorten the conversation before sending once it exceeds about this many tokens.
+        #[arg(long, value_name = "TOKENS", group = "history_budget")]
+        history_limit: Option<usize>,
+
+        /// Look up the model's context window from the API and keep the conversation within it.
+        #[arg(long, group = "history_budget")]
+        context_window_from_api: bool,
+
+        /// How to shorten a conversation over `--history-limit`: drop or summarize the oldest turns.
+        #[arg(
+            long,
+            value_name = "STRATEGY",
+            default_value = "drop",
+            value_parser = ["drop", "summarize"],
+            requires = "history_budget"
+        )]
+        truncate_strategy: String,
+
+        /// Let the response use whatever the model's context window leaves after the prompt, by
+        /// setting `max_tokens` to the window less the estimated prompt tokens.
+        #[arg(long, conflicts_with_all = ["tui", "auto_continue"])]
+        max_tokens_remaining: bool,
+
+        /// Do not end the printed response with a newline.
+        #[arg(long, conflicts_with_all = ["tui", "stream_format"])]
+        no_trailing_newline: bool,
+
+        /// Automatically ask the model to continue when output is cut off by the token limit.
+        #[arg(long)]
+        auto_continue: bool,
+
+        /// Maximum number of automatic continuations.
+        #[arg(long, default_value_t = 3, requires = "auto_continue")]
+        max_continues: u32,
+
+        /// Mark the system message for prompt caching, so a long one resent every turn costs less.
+        #[arg(long)]
+        cache_prompt: bool,
+
+        /// If the connection drops mid-response, request the rest with what was received as an
+        /// assistant prefix, up to this many times. Output continues in place, sinks included.
+        #[arg(long, value_name = "N", default_value_t = 0, conflicts_with = "tui")]
+        resume: u32,
+
+        /// When the API rejects the request, ask the model to explain the error (one extra request).
+        #[arg(long)]
+        explain_error: bool,
+
+        /// Wait for the whole response, then print its tokens with their log-probabilities.
+        #[arg(
+            long,
+            conflicts_with_all = [
+                "tui", "stream_format", "auto_continue", "resume", "sink", "wrap", "typewriter",
+                "speak"
+            ]
+        )]
+        logprobs: bool,
+
+        /// Also list the N most likely alternatives at each token position.
+        #[arg(long, value_name = "N", requires = "logprobs")]
+        top_logprobs: Option<u8>,
+
+        /// Exit with status 3 when the response was cut off by the token limit and 4 when it was
+        /// stopped by the content filter, instead of 0.
+        #[arg(long, conflicts_with = "tui")]
+        strict_finish: bool,
+
+        /// Read the complete response aloud with `tts_command` from the config (`say` on macOS,
+        /// `espeak` elsewhere by default).
+        #[arg(long, conflicts_with = "tui")]
+        speak: bool,
+
+        /// Print how long DNS, connecting, the first byte, the first token and the whole request
+        /// took to stderr.
+        #[arg(long, conflicts_with_all = ["tui", "logprobs"])]
+        profile_timing: bool,
+
+        /// Mask emails, card numbers or the config's `redact_patterns` in the response before it
+        /// is printed, saved or sent to sinks.
+        #[arg(long, conflicts_with_all = ["tui", "logprobs"])]
+        redact: bool,
+
+        /// Comma-separated models to try in turn when the model is still rate limited after its
+        /// retries, or unavailable. Overrides `fallback_models` in the config.
+        #[arg(
+            long,
+            value_name = "MODELS",
+            value_delimiter = ',',
+            conflicts_with_all = ["tui", "logprobs"]
+        )]
+        fallback_models: Vec<String>,
+
+        /// Comma-separated models, in order of growing context window, to retry with when the
+        /// prompt is too long for the model. Overrides `larger_context_models` in the config.
+        #[arg(
+            long,
+            value_name = "MODELS",
+            value_delimiter = ',',
+            conflicts_with_all = ["tui", "logprobs"]
+        )]
+        larger_context_models: Vec<String>,
+
+        /// Also save the conversation, with the response, as a self-contained HTML page with the
+        /// Markdown rendered and code blocks highlighted.
+        #[arg(long, value_name = "PATH", conflicts_with_all = ["tui", "logprobs"])]
+        export_html: Option<String>,
+    },
 
     /// Test the API connection.
-    Test,
+    Test {
+        /// `text` logs each API's result; `json` prints them as one object keyed by API, for
+        /// health checks.
+        #[arg(long, value_name = "FORMAT", default_value = "text", value_parser = ["text", "json"])]
+        output: String,
+    },
+
+    /// Check that both endpoints are reachable, without spending tokens.
+    Ping,
+
+    /// Diagnose common setup problems: config file, keys, DNS, network and authentication.
+    Doctor,
+
+    /// Print the version, compiled-in features, subcommands and options as JSON, for tools that
+    /// wrap the CLI.
+    Capabilities,
+
+    /// Answer newline-delimited JSON requests on a Unix socket, keeping one client warm.
+    #[cfg(unix)]
+    Serve {
+        /// Path of the socket to listen on.
+        #[arg(long)]
+        socket: PathBuf,
+    },
+
+    /// Measure time to first token and throughput of a model over several requests.
+    Bench {
+        /// Model to benchmark.
+        #[arg(long, default_value = MISTRAL_MODEL)]
+        model: String,
+
+        /// Number of requests to send.
+        #[arg(long, default_value_t = 5, value_parser = clap::value_parser!(u64).range(1..))]
+        requests: u64,
+
+        /// Prompt sent with every request.
+        #[arg(long, default_value = BENCH_PROMPT)]
+        prompt: String,
+
+        /// Number of requests in flight at once.
+        #[arg(long, default_value_t = 1, value_parser = clap::value_parser!(u64).range(1..))]
+        concurrency: u64,
+    },
+
+    /// Send one prompt to several models at once and print their answers in the order given.
+    ///
+    /// Each answer is printed in full under a `## <model>` heading with its latency and token
+    /// usage, so runs can be diffed.
+    Compare {
+        /// Prompt sent to every model.
+        prompt: String,
+
+        /// Comma-separated models to compare.
+        #[arg(long, value_name = "MODELS", value_delimiter = ',', required = true)]
+        models: Vec<String>,
+    },
+
+    /// Send a request body saved by `--record` exactly as written and print the raw response.
+    ///
+    /// The status line and headers go to stderr and the body to stdout, as it arrives when the
+    /// request has `"stream": true`.
+    Replay {
+        /// Path to the saved request, e.g. `001-request.json` from a `--record` directory.
+        request: PathBuf,
+    },
 
     /// Analyze a code snippet using the API.
-    Code { code: String },
+    Code {
+        #[arg(required_unless_present = "file", conflicts_with = "file")]
+        code: Option<String>,
+
+        /// Analyze this file instead of code given on the command line.
+        #[arg(long, value_name = "PATH")]
+        file: Option<String>,
+
+        /// With `--file`, analyze the file again each time it is saved, clearing the screen first,
+        /// until Ctrl-C.
+        #[arg(long, requires = "file", conflicts_with = "code")]
+        watch: bool,
+
+        /// Print the messages being sent before the response.
+        #[arg(long)]
+        echo_prompt: bool,
+
+        /// Include the current git branch and changed files in the prompt.
+        #[arg(long)]
+        git_context: bool,
+
+        /// Also include `git diff --staged` (requires `--git-context`).
+        #[arg(long, requires = "git_context")]
+        staged: bool,
+
+        /// Split the input into chunks of at most this many characters and analyze each separately.
+        #[arg(long, value_name = "CHARS", value_parser = clap::value_parser!(u64).range(1..))]
+        chunk_size: Option<u64>,
+
+        /// Characters each chunk repeats from the end of the previous one.
+        #[arg(
+            long,
+            value_name = "CHARS",
+            default_value_t = 0,
+            requires = "chunk_size"
+        )]
+        overlap: u64,
+
+        /// Combine the per-chunk analyses into one final answer.
+        #[arg(long, requires = "chunk_size")]
+        summarize: bool,
+
+        /// Print only the fenced code blocks of the response, without prose or fences.
+        #[arg(long)]
+        extract_code: bool,
+
+        /// With `--extract-code`, print only the first code block.
+        #[arg(long, requires = "extract_code")]
+        first_block: bool,
+
+        /// Send the code without the code-review system prompt.
+        #[arg(long)]
+        no_system: bool,
+    },
+
+    /// Have Codestral rewrite a file as instructed and print only the new code.
+    Rewrite {
+        /// File to rewrite.
+        file: String,
+
+        /// What to change, e.g. "add type hints" or "translate to Rust".
+        instruction: String,
+
+        /// Overwrite the file with the result, keeping the original as `<FILE>.bak`.
+        #[arg(long)]
+        in_place: bool,
+    },
+
+    /// Fetch a web page and stream a summary of its text.
+    Summarize {
+        /// URL of the page to summarize.
+        url: String,
+
+        /// Send at most this many characters of the page text to the model.
+        #[arg(long, value_name = "CHARS", default_value_t = 20_000)]
+        max_chars: usize,
+
+        /// Model to summarize with.
+        #[arg(long, default_value = MISTRAL_MODEL)]
+        model: String,
+
+        /// Warn about prompt-injection patterns in the messages and refuse to send them.
+        #[arg(long)]
+        lint_prompt: bool,
+
+        /// Send the messages even when `--lint-prompt` finds suspicious patterns.
+        #[arg(long, requires = "lint_prompt")]
+        force: bool,
+    },
+
+    /// Extract text from a PDF or image using the OCR API.
+    Ocr {
+        /// Path to the PDF or image file.
+        path: String,
+
+        /// Write the extracted Markdown to this file instead of stdout.
+        #[arg(short, long)]
+        output: Option<String>,
+    },
+
+    /// List the models available to the Mistral API key as a table.
+    Models {
+        /// Column to sort by.
+        #[arg(long, default_value = "id", value_parser = ["id", "context"])]
+        sort: String,
+
+        /// Only show models whose ID contains this text (case-insensitive).
+        #[arg(long)]
+        filter: Option<String>,
+    },
+
+    /// List previously sent prompts, optionally filtered.
+    History {
+        /// Only show prompts sent at or after this time: RFC 3339, a date, or an age like `7d`.
+        #[arg(long, value_name = "TIME")]
+        since: Option<String>,
+
+        /// Only show prompts sent before this time; a date includes that whole day.
+        #[arg(long, value_name = "TIME")]
+        until: Option<String>,
+
+        /// Only show prompts sent to this model.
+        #[arg(long)]
+        model: Option<String>,
+
+        /// Only show prompts containing this text (case-insensitive).
+        #[arg(long, value_name = "TEXT")]
+        grep: Option<String>,
+    },
+
+    /// Upload, list and delete files stored with the files API.
+    Files {
+        #[command(subcommand)]
+        files_command: FilesCommands,
+    },
+
+    /// List, inspect and cancel batch inference jobs, or run a batch file locally.
+    Batch {
+        #[command(subcommand)]
+        batch_command: BatchCommands,
+    },
 
     /// Manage configuration files.
     Config {
@@ -51,38 +656,293 @@ enum Commands {
     },
 }
 
-/// Struct representing a request message sent to the API.
-#[derive(Serialize, Clone)]
-struct RequestMessage {
-    role: String,
-    content: String,
+/// Prints the messages being sent, separated from the response that follows.
+fn echo_prompt(messages: &[RequestMessage]) {
+    for message in messages {
+        println!("[{}]", message.role);
+        println!("{}", message.content);
+        println!();
+    }
+    println!("[assistant]");
 }
 
-/// Struct representing a response message received from the API.
-#[derive(Deserialize)]
-struct ResponseMessage {
-    content: String,
+/// Warns about suspicious patterns in `messages`, failing unless `force` is set.
+fn lint_prompt(messages: &[RequestMessage], force: bool) -> Result<()> {
+    let findings = lint::check(messages);
+    for finding in &findings {
+        warn!(
+            "{}",
+            tr!(
+                "prompt_injection",
+                finding.index + 1,
+                finding.role,
+                finding.rule,
+                format!("{:?}", finding.excerpt)
+            )
+        );
+    }
+    if !findings.is_empty() && !force {
+        bail!(tr!("lint_refused", findings.len()));
+    }
+    Ok(())
 }
 
-/// Struct representing a chat request sent to the API.
-#[derive(Serialize)]
-struct ChatRequest {
-    model: String,
+/// Streams a response, resuming it up to `resumes` times when the connection drops mid-stream.
+///
+/// Each retry sends the text received so far as an assistant prefix, so the model picks up
+/// where it was cut off and the output continues in place.
+async fn stream_resuming(
+    client: &ChatClient,
+    model: &str,
     messages: Vec<RequestMessage>,
-    stream: bool,
-    max_tokens: Option<u32>,
+    params: &ChatParams,
+    options: &StreamOptions,
+    resumes: u32,
+) -> mistral_chat_cli_rs::Result<StreamOutcome> {
+    let mut outcome = client
+        .chat_stream(model, messages.clone(), params, options)
+        .await?;
+    let mut attempt = 0;
+    while outcome.interrupted && attempt < resumes {
+        attempt += 1;
+        warn!("{}", tr!("resuming", attempt, resumes));
+        let mut retry = messages.clone();
+        retry.push(RequestMessage {
+            role: "assistant".to_string(),
+            content: outcome.content,
+            prefix: true,
+            ..Default::default()
+        });
+        let redactions = outcome.redactions;
+        let model = outcome.model;
+        outcome = client.chat_stream(&model, retry, params, options).await?;
+        outcome.redactions += redactions;
+    }
+    Ok(outcome)
+}
+
+/// Asks the model to explain a failed request in plain language, printing the answer to stderr,
+/// and returns the original error.
+///
+/// Only errors reported by the API are explained; the explanation request itself is never retried
+/// or explained, so a failing API cannot cause a loop.
+async fn explain(client: &ChatClient, error: mistral_chat_cli_rs::Error) -> anyhow::Error {
+    use mistral_chat_cli_rs::{Error, StreamEvent};
+
+    if !matches!(error, Error::Api { .. } | Error::Auth { .. } | Error::Input(_)) {
+        return error.into();
+    }
+    let error = anyhow::Error::from(error);
+    warn!("{}", tr!("explaining_error", MISTRAL_MODEL));
+    let messages = vec![RequestMessage {
+        role: "user".to_string(),
+        content: format!("{}\n\n{:#}", EXPLAIN_ERROR_PROMPT, error),
+        ..Default::default()
+    }];
+    let explanation = async {
+        let mut stream = client
+            .chat_events(MISTRAL_MODEL, messages, &ChatParams::default())
+            .await?;
+        let mut explanation = String::new();
+        while let Some(event) = stream.next().await {
+            if let StreamEvent::Content(content) = event? {
+                explanation.push_str(&content);
+            }
+        }
+        Ok::<_, mistral_chat_cli_rs::Error>(explanation)
+    };
+    match explanation.await {
+        Ok(explanation) => eprintln!("{}\n", explanation.trim()),
+        Err(e) => warn!("{}", tr!("explain_failed", format!("{:#}", anyhow::Error::from(e)))),
+    }
+    error
+}
+
+/// Exit status for `--strict-finish`, so scripts can tell a truncated or filtered response from a
+/// complete one.
+fn finish_exit_code(finish_reason: Option<&str>) -> i32 {
+    match finish_reason {
+        Some("length" | "model_length") => 3,
+        Some("content_filter") => 4,
+        _ => 0,
+    }
+}
+
+/// Lays out each token with its log-probability, probability and any top alternatives.
+fn logprobs_table(tokens: &[TokenLogprob]) -> comfy_table::Table {
+    let mut table = comfy_table::Table::new();
+    table
+        .load_style(comfy_table::presets::UTF8_FULL_CONDENSED)
+        .set_content_arrangement(comfy_table::ContentArrangement::Dynamic)
+        .set_header([
+            tr!("column_token"),
+            tr!("column_logprob"),
+            tr!("column_probability"),
+            tr!("column_alternatives"),
+        ]);
+    for token in tokens {
+        // Debug formatting makes whitespace and newline tokens visible.
+        let alternatives = token
+            .top_logprobs
+            .iter()
+            .map(|top| format!("{:?} {:.3}", top.token, top.logprob))
+            .collect::<Vec<_>>()
+            .join(", ");
+        table.add_row([
+            format!("{:?}", token.token),
+            format!("{:.4}", token.logprob),
+            format!("{:.1}%", token.logprob.exp() * 100.0),
+            alternatives,
+        ]);
+    }
+    table
+}
+
+/// Lays out batch jobs with their status, creation time, model and progress.
+fn batch_table(jobs: &[BatchJob]) -> comfy_table::Table {
+    let mut table = comfy_table::Table::new();
+    table
+        .load_style(comfy_table::presets::UTF8_FULL_CONDENSED)
+        .set_content_arrangement(comfy_table::ContentArrangement::Dynamic)
+        .set_header([
+            tr!("column_id"),
+            tr!("column_status"),
+            tr!("column_created"),
+            tr!("column_model"),
+            tr!("column_progress"),
+        ]);
+    for job in jobs {
+        table.add_row([
+            job.id.clone(),
+            job.status.clone(),
+            time::utc_time(job.created_at),
+            job.model.clone().unwrap_or_else(|| "-".to_string()),
+            batch_progress(job),
+        ]);
+    }
+    table
+}
+
+/// Describes how many of a job's requests are done, e.g. `40/100 (2 failed)`.
+fn batch_progress(job: &BatchJob) -> String {
+    let mut progress = format!("{}/{}", job.completed_requests, job.total_requests);
+    if job.failed_requests > 0 {
+        progress.push_str(&format!(" ({})", tr!("batch_failed_count", job.failed_requests)));
+    }
+    progress
+}
+
+/// Lays out the phases of a request with their durations in milliseconds.
+fn timing_table(timings: &RequestTimings) -> comfy_table::Table {
+    let mut table = comfy_table::Table::new();
+    table
+        .load_style(comfy_table::presets::UTF8_FULL_CONDENSED)
+        .set_header([tr!("column_phase"), tr!("column_ms")]);
+    // Without a connect time, the request went over a pooled connection. A new connection can
+    // still skip DNS, e.g. to an IP address or a proxy given as one.
+    let reused = timings.connect.is_none();
+    let phases = [
+        ("timing_dns", timings.dns),
+        ("timing_connect", timings.connect),
+        ("timing_first_byte", timings.first_byte),
+        ("timing_first_token", timings.first_token),
+        ("timing_total", timings.total),
+    ];
+    for (index, (phase, duration)) in phases.into_iter().enumerate() {
+        let duration = match duration {
+            Some(duration) => format!("{:.1}", duration.as_secs_f64() * 1000.0),
+            None if reused && index < 2 => tr!("timing_reused"),
+            None => "-".to_string(),
+        };
+        table.add_row([tr!(phase), duration]);
+    }
+    if let Some(column) = table.column_mut(1) {
+        column.set_cell_alignment(comfy_table::CellAlignment::Right);
+    }
+    table
 }
 
-/// Struct representing a chat response received from the API.
-#[derive(Deserialize)]
-struct ChatResponse {
-    choices: Vec<Choice>,
+/// Prints a code analysis, or with `extract` set only its code blocks (just the first if `true`).
+///
+/// Extracted code goes to stdout so it can be redirected straight into a file.
+fn print_analysis(analysis: &str, extract: Option<bool>) {
+    let Some(first_only) = extract else {
+        info!("{}", analysis);
+        return;
+    };
+    let blocks = markdown::code_blocks(analysis);
+    if blocks.is_empty() {
+        warn!("{}", tr!("no_code_blocks"));
+    }
+    let count = if first_only { 1 } else { blocks.len() };
+    for block in blocks.iter().take(count) {
+        print!("{}", block);
+    }
 }
 
-/// Struct representing a choice in the chat response.
-#[derive(Deserialize)]
-struct Choice {
-    message: ResponseMessage,
+/// Enum representing the files subcommands.
+#[derive(Subcommand)]
+enum FilesCommands {
+    /// Upload a file, e.g. batch input or fine-tuning data.
+    Upload {
+        /// Path to the file to upload.
+        path: String,
+
+        /// What the file will be used for.
+        #[arg(long, default_value = "fine-tune", value_parser = ["fine-tune", "batch", "ocr"])]
+        purpose: String,
+    },
+
+    /// List uploaded files.
+    List,
+
+    /// Delete an uploaded file.
+    Delete {
+        /// ID of the file, as shown by `files list`.
+        id: String,
+    },
+}
+
+/// Enum representing the batch subcommands.
+#[derive(Subcommand)]
+enum BatchCommands {
+    /// List batch jobs as a table.
+    List,
+
+    /// Show the status and progress of a batch job.
+    Status {
+        /// ID of the job, as shown by `batch list`.
+        id: String,
+    },
+
+    /// Cancel a queued or running batch job.
+    Cancel {
+        /// ID of the job, as shown by `batch list`.
+        id: String,
+    },
+
+    /// Send the requests in a batch file right away instead of as a job, each distinct request
+    /// only once.
+    ///
+    /// The file has one `{"custom_id": ..., "body": ...}` object per line, as for a batch job.
+    /// Lines whose bodies are identical share one request, and each line gets its own result
+    /// line, in file order.
+    Run {
+        /// The batch file.
+        input: PathBuf,
+
+        /// Model for bodies that name none, instead of the configured or built-in default.
+        #[arg(long)]
+        model: Option<String>,
+
+        /// Write the results to this file instead of stdout.
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+
+        /// Number of requests in flight at once.
+        #[arg(long, default_value_t = 4, value_parser = clap::value_parser!(u64).range(1..))]
+        concurrency: u64,
+    },
 }
 
 /// Enum representing the configuration subcommands.
@@ -98,358 +958,423 @@ enum ConfigCommands {
     /// View the current configuration.
     View,
 
+    /// Print every setting with its effective value and where that value came from.
+    Explain,
+
+    /// List the presets defined in the configuration file.
+    Presets,
+
+    /// List the prompt templates in the templates directory.
+    Templates,
+
+    /// List the system prompts in the system prompt library.
+    SystemPrompts,
+
     /// Load a configuration file from a specified path.
     Load {
         /// Path to the configuration file.
         #[arg(short, long)]
         file_path: String,
     },
-}
 
-/// Struct representing configuration for the CLI.
-#[derive(Debug, Deserialize, Serialize)]
-struct Config {
-    mistral_api_key: String,
-    codestral_api_key: String,
-    debug: bool,
-}
+    /// Set a single key in the configuration file.
+    Set {
+        /// Name of the config key, e.g. `debug`.
+        key: String,
 
-impl Config {
-    fn from_file(file_path: &str) -> Result<Self, config::ConfigError> {
-        let settings = ConfigFile::builder()
-            // Add configuration from a file.
-            .add_source(File::with_name(file_path))
-            // Add configuration from environment variables.
-            .add_source(Environment::with_prefix("APP"))
-            .build()?;
+        /// New value for the key.
+        value: String,
+    },
 
-        // Try to deserialize the configuration into the `Config` struct.
-        settings.try_deserialize()
-    }
+    /// Remove a single key from the configuration file.
+    Unset {
+        /// Name of the config key, e.g. `ca_cert`.
+        key: String,
+    },
 
-    fn generate_sample_config(file_path: &str) -> Result<()> {
-        let sample_config = Config {
-            mistral_api_key: "your_mistral_api_key".to_string(),
-            codestral_api_key: "your_codestral_api_key".to_string(),
-            debug: false,
-        };
+    /// Write the configuration file, keys included, to a passphrase-encrypted bundle.
+    Export {
+        /// Where to write the bundle.
+        path: PathBuf,
 
-        let config_content = toml::to_string(&sample_config)?;
-        fs::write(file_path, config_content)?;
-        Ok(())
-    }
+        /// Passphrase for the bundle; prompted for when not given.
+        #[arg(long, env = "APP_BUNDLE_PASSPHRASE", hide_env_values = true)]
+        passphrase: Option<String>,
+    },
 
-    // Mask API keys by showing only the first few characters.
-    fn mask_key(key: &str) -> String {
-        if key.len() > 5 {
-            format!("{}{}", &key[..5], "*".repeat(key.len() - 5))
-        } else {
-            key.to_string()
-        }
-    }
+    /// Decrypt a bundle written by `config export` and install it as the configuration file.
+    Import {
+        /// Bundle to read.
+        path: PathBuf,
 
-    fn view_config(config: &Config) {
-        println!("Current Configuration:");
-        println!(
-            "Mistral API Key: {}",
-            Config::mask_key(&config.mistral_api_key)
-        );
-        println!(
-            "Codestral API Key: {}",
-            Config::mask_key(&config.codestral_api_key)
-        );
-        println!("Debug Mode: {}", config.debug);
-    }
-}
+        /// Passphrase for the bundle; prompted for when not given.
+        #[arg(long, env = "APP_BUNDLE_PASSPHRASE", hide_env_values = true)]
+        passphrase: Option<String>,
 
-/// A client for interacting with the Mistral and Codestral APIs.
-struct ChatClient {
-    client: Client,
-    mistral_api_key: String,
-    codestral_api_key: String,
-    debug: bool,
+        /// Replace an existing configuration file.
+        #[arg(long)]
+        force: bool,
+    },
 }
 
-impl ChatClient {
-    /// Creates a new `ChatClient` with the given API keys and debug mode.
-    fn new(mistral_api_key: String, codestral_api_key: String, debug: bool) -> Self {
-        ChatClient {
-            client: Client::new(),
-            mistral_api_key,
-            codestral_api_key,
-            debug,
-        }
-    }
+/// Reads a passphrase from the terminal without echoing it, or a line from piped stdin.
+fn read_passphrase(prompt: &str) -> Result<String> {
+    use crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
+    use crossterm::terminal;
 
-    /// Helper for sending a request with retry logic.
-    async fn send_with_retry<F, Fut>(&self, request_func: F) -> Result<reqwest::Response>
-    where
-        F: Fn() -> Fut,
-        Fut: std::future::Future<Output = reqwest::Result<reqwest::Response>>,
-    {
-        let max_attempts = 3;
-        for attempt in 1..=max_attempts {
-            match request_func().await {
-                Ok(resp) => return Ok(resp),
-                Err(err) if attempt < max_attempts => {
-                    error!("Retry attempt {}: {}", attempt, err);
-                    tokio::time::sleep(Duration::from_secs(2)).await;
-                }
-                Err(err) => {
-                    return Err(err)
-                        .context("Failed to send request after multiple attempts")
-                }
-            }
-        }
-        unreachable!();
+    eprint!("{}", prompt);
+    if !std::io::stdin().is_terminal() {
+        let mut line = String::new();
+        std::io::stdin().read_line(&mut line)?;
+        eprintln!();
+        return Ok(line.trim_end_matches(['\r', '\n']).to_string());
     }
 
-    /// Streams chat completions from the API and prints them to stdout.
-    async fn chat_stream(&self, model: &str, messages: Vec<RequestMessage>) -> Result<()> {
-        if self.debug {
-            debug!("Sending streaming request to {} API", model);
-            debug!(
-                "Using URL: {}",
-                if model.contains("codestral") {
-                    CODESTRAL_URL
-                } else {
-                    MISTRAL_URL
-                }
-            );
-        }
-
-        let request = ChatRequest {
-            model: model.to_string(),
-            messages,
-            stream: true,
-            max_tokens: None,
+    terminal::enable_raw_mode()?;
+    let mut passphrase = String::new();
+    let result = loop {
+        let key = match event::read() {
+            Ok(Event::Key(key)) if key.kind != KeyEventKind::Release => key,
+            Ok(_) => continue,
+            Err(e) => break Err(e.into()),
         };
-
-        if self.debug {
-            debug!("Request body: {}", serde_json::to_string(&request)?);
+        match key.code {
+            KeyCode::Enter => break Ok(()),
+            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                break Err(anyhow::anyhow!(tr!("cancelled")))
+            }
+            KeyCode::Char(c) => passphrase.push(c),
+            KeyCode::Backspace => {
+                passphrase.pop();
+            }
+            _ => {}
         }
+    };
+    terminal::disable_raw_mode()?;
+    eprintln!();
+    result.map(|()| passphrase)
+}
 
-        let url = if model.contains("codestral") {
-            CODESTRAL_URL
-        } else {
-            MISTRAL_URL
-        };
-
-        let api_key = if model.contains("codestral") {
-            &self.codestral_api_key
-        } else {
-            &self.mistral_api_key
-        };
+/// Writes `content` to `path`, readable only by the current user where the platform allows.
+fn write_private(path: &Path, content: &[u8]) -> std::io::Result<()> {
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    std::io::Write::write_all(&mut options.open(path)?, content)
+}
 
-        let response = self
-            .send_with_retry(|| {
-                self.client
-                    .post(url)
-                    .header("Authorization", format!("Bearer {}", api_key))
-                    .json(&request)
-                    .send()
-            })
-            .await?;
+/// Resolves the effective configuration from flags, environment and the config file.
+///
+/// The file is skipped entirely with `--no-config` or when both keys are given as flags.
+fn load_config(cli: &Cli) -> mistral_chat_cli_rs::Result<Config> {
+    let mut config = match (&cli.mistral_key, &cli.codestral_key) {
+        (Some(_), Some(_)) => Config::default(),
+        _ if cli.no_config => Config::default(),
+        _ => Config::from_file(&cli.config)?,
+    };
 
-        if self.debug {
-            debug!("Response status: {}", response.status());
-        }
+    if let Some(key) = &cli.mistral_key {
+        config.mistral_api_key = key.clone();
+    }
+    if let Some(key) = &cli.codestral_key {
+        config.codestral_api_key = key.clone();
+    }
+    if cli.insecure {
+        config.danger_accept_invalid_certs = true;
+    }
+    if cli.no_proxy {
+        config.no_proxy = true;
+    }
+    config.max_retries = cli.max_retries.or(config.max_retries);
+    config.retry_delay_ms = cli.retry_delay.or(config.retry_delay_ms);
+    config.max_retries_on_429 = cli.max_retries_on_429.or(config.max_retries_on_429);
+    config.retry_delay_on_429_ms = cli.retry_delay_on_429.or(config.retry_delay_on_429_ms);
+    config.deadline_secs = cli.deadline.or(config.deadline_secs);
+    config.max_input_bytes = cli.max_input_bytes.or(config.max_input_bytes);
+    config.max_response_bytes = cli.max_response_bytes.or(config.max_response_bytes);
+    config.metadata.extend(cli.meta.iter().cloned());
+    Ok(config)
+}
 
-        let mut stream = response.bytes_stream();
-        let mut stdout = tokio::io::stdout();
+/// Prints the settings of `config`, each labelled by its `setting.<name>` catalog message.
+fn view_config(config: &Config) {
+    println!("{}", tr!("config_current"));
+    for (name, value) in config.settings() {
+        println!("{}", tr!(&format!("setting.{}", name), value));
+    }
+}
 
-        'outer: while let Some(chunk) = stream.next().await {
-            match chunk {
-                Ok(bytes) => {
-                    let text = String::from_utf8_lossy(&bytes);
-                    if self.debug {
-                        debug!("Received chunk: {}", text);
-                    }
-                    for line in text.lines() {
-                        if line.starts_with("data: ") {
-                            let data = &line[6..];
-                            if data == "[DONE]" {
-                                if self.debug {
-                                    debug!("Received [DONE]");
-                                }
-                                stdout.write_all(b"\n").await?;
-                                stdout.flush().await?;
-                                break 'outer;
-                            }
-                            match serde_json::from_str::<serde_json::Value>(data) {
-                                Ok(json) => {
-                                    if let Some(content) =
-                                        json["choices"][0]["delta"]["content"].as_str()
-                                    {
-                                        stdout.write_all(content.as_bytes()).await?;
-                                        stdout.flush().await?;
-                                    } else if self.debug {
-                                        debug!("No content in JSON: {}", json);
-                                    }
-                                }
-                                Err(e) => {
-                                    if self.debug {
-                                        debug!("JSON parse error: {} - Data: {}", e, data);
-                                    }
-                                }
-                            }
-                        }
-                    }
-                }
-                Err(e) => {
-                    error!("Streaming failed: {}", e);
-                    if self.debug {
-                        debug!("Chunk error: {}", e);
-                    }
+/// Prints each setting of `config` with the layer it came from: a flag, the environment, the
+/// config file or the built-in default, in the order `load_config` applies them.
+fn explain_config(cli: &Cli, config: &Config) -> Result<()> {
+    let keys_given = cli.mistral_key.is_some() && cli.codestral_key.is_some();
+    // `load_config` skips the file when both keys are given on the command line.
+    let file = (!cli.no_config && !keys_given).then_some(cli.config.as_str());
+    let flags = [
+        ("mistral_api_key", cli.mistral_key.is_some(), "--mistral-key"),
+        ("codestral_api_key", cli.codestral_key.is_some(), "--codestral-key"),
+        ("danger_accept_invalid_certs", cli.insecure, "--insecure"),
+        ("no_proxy", cli.no_proxy, "--no-proxy"),
+        ("max_retries", cli.max_retries.is_some(), "--max-retries"),
+        ("retry_delay_ms", cli.retry_delay.is_some(), "--retry-delay"),
+        ("max_retries_on_429", cli.max_retries_on_429.is_some(), "--max-retries-on-429"),
+        ("retry_delay_on_429_ms", cli.retry_delay_on_429.is_some(), "--retry-delay-on-429"),
+        ("deadline_secs", cli.deadline.is_some(), "--deadline"),
+        ("max_input_bytes", cli.max_input_bytes.is_some(), "--max-input-bytes"),
+        ("max_response_bytes", cli.max_response_bytes.is_some(), "--max-response-bytes"),
+    ];
+    let values = toml::Value::try_from(config)?;
+    for (key, mut source) in Config::sources(file)? {
+        if let Some((_, _, flag)) = flags.iter().find(|(name, given, _)| *name == key && *given) {
+            // `--mistral-key` and `--codestral-key` fall back to the same `APP_*` variables, so
+            // a matching value means the flag was never given.
+            let from_environment = match &source {
+                Source::Environment(variable) => {
+                    std::env::var(variable).ok().as_deref()
+                        == values.get(key).and_then(toml::Value::as_str)
                 }
+                _ => false,
+            };
+            if !from_environment {
+                source = Source::Flag(flag.to_string());
             }
         }
+        let value = match values.get(key) {
+            Some(toml::Value::String(value)) if key.ends_with("_api_key") => {
+                Config::mask_key(value)
+            }
+            Some(toml::Value::String(value)) => value.clone(),
+            Some(toml::Value::Array(items)) if !items.is_empty() => items
+                .iter()
+                .map(|item| item.as_str().map_or_else(|| item.to_string(), str::to_string))
+                .collect::<Vec<_>>()
+                .join(", "),
+            Some(toml::Value::Array(_)) | None => builtin_default(key),
+            Some(value) => value.to_string(),
+        };
+        let source = match source {
+            Source::Default => tr!("source_default"),
+            Source::File => tr!("source_file", cli.config),
+            Source::Environment(variable) => tr!("source_environment", variable),
+            Source::Flag(flag) => tr!("source_flag", flag),
+        };
+        println!("{}: {} ({})", key, value, source);
+    }
+    Ok(())
+}
 
-        Ok(())
+/// What an unset `key` amounts to, as `ChatClient` applies it.
+fn builtin_default(key: &str) -> String {
+    let millis = |delay: Duration| delay.as_millis().to_string();
+    match key {
+        "user_agent" => USER_AGENT.to_string(),
+        "max_retries" => DEFAULT_RETRIES.to_string(),
+        "retry_delay_ms" => millis(DEFAULT_RETRY_DELAY),
+        "max_retries_on_429" => DEFAULT_RETRIES_ON_429.to_string(),
+        "retry_delay_on_429_ms" => millis(DEFAULT_RETRY_DELAY_ON_429),
+        "tcp_keepalive_secs" => "15".to_string(),
+        "max_response_bytes" => DEFAULT_MAX_RESPONSE_BYTES.to_string(),
+        _ => tr!("value_unset"),
     }
+}
 
-    /// Tests API connectivity with a minimal request.
-    async fn test_connection(&self) -> Result<()> {
-        if self.debug {
-            debug!("Testing API connection...");
+/// Parses a `--meta` tag, which needs a key before its `=`.
+fn meta_pair(pair: &str) -> std::result::Result<(String, String), String> {
+    match pair.split_once('=') {
+        Some((key, value)) if !key.trim().is_empty() => {
+            Ok((key.trim().to_string(), value.to_string()))
         }
+        _ => Err(tr!("meta_invalid")),
+    }
+}
 
-        let messages = vec![RequestMessage {
-            role: "user".to_string(),
-            content: "Test".to_string(),
-        }];
-
-        // Test Mistral API.
-        let request = ChatRequest {
-            model: MISTRAL_MODEL.to_string(),
-            messages: messages.clone(),
-            stream: false,
-            max_tokens: Some(1),
-        };
-
-        if self.debug {
-            debug!("Mistral request body: {}", serde_json::to_string(&request)?);
-        }
+/// Parses `--deadline` into seconds.
+fn deadline_secs(text: &str) -> std::result::Result<u64, String> {
+    time::parse_seconds(text).ok_or_else(|| tr!("deadline_invalid"))
+}
 
-        let mistral_response = self
-            .send_with_retry(|| {
-                self.client
-                    .post(MISTRAL_URL)
-                    .header("Authorization", format!("Bearer {}", self.mistral_api_key))
-                    .json(&request)
-                    .send()
-            })
-            .await?;
+/// Builds a `ChatClient` from the effective configuration and global flags.
+fn chat_client(cli: &Cli) -> Result<ChatClient> {
+    let config = load_config(cli).with_context(|| tr!("config_read_failed"))?;
+    let debug = cli.debug || config.debug;
+    let client = ChatClient::from_config(&config, debug, cassette(cli)?)?;
+    Ok(if cli.echo_request_id {
+        client.with_request_ids()
+    } else {
+        client
+    })
+}
 
-        let status = mistral_response.status();
-        if self.debug {
-            debug!("MISTRAL status: {}", status);
+/// Works out `max_tokens` for `--max-tokens-remaining`: the model's context window less the
+/// estimated size of `messages`, at least `MIN_COMPLETION_TOKENS`. `None` leaves it to the API
+/// when the window is unknown.
+async fn remaining_tokens(
+    client: &ChatClient,
+    model: &str,
+    messages: &[RequestMessage],
+) -> Option<u32> {
+    let window = match models::context_length(client, model).await {
+        Ok(Some(window)) => window,
+        Ok(None) => {
+            warn!("{}", tr!("no_context_window_for_max_tokens", model));
+            return None;
         }
-        if status.is_success() {
-            info!("MISTRAL-API connection successful");
-        } else {
-            error!("MISTRAL-API connection failed: {}", status);
-            if self.debug {
-                let text = mistral_response.text().await?;
-                debug!("MISTRAL response body: {}", text);
-            }
-            if status == reqwest::StatusCode::UNAUTHORIZED {
-                error!("Hint: Check your Mistral API key.");
-            }
+        Err(e) => {
+            warn!("{}", tr!("context_window_failed", format!("{:#}", e)));
+            return None;
         }
+    };
+    // The estimate is rough, so a tenth more is set aside for the prompt.
+    let prompt = estimate_tokens(messages) as u64;
+    let prompt = prompt + prompt / 10;
+    let remaining = window.saturating_sub(prompt);
+    if remaining < u64::from(MIN_COMPLETION_TOKENS) {
+        warn!("{}", tr!("context_nearly_full", prompt, window, MIN_COMPLETION_TOKENS));
+        return Some(MIN_COMPLETION_TOKENS);
+    }
+    debug!("max_tokens: {} ({} window, ~{} prompt)", remaining, window, prompt);
+    Some(u32::try_from(remaining).unwrap_or(u32::MAX))
+}
 
-        // Test Codestral API.
-        let codestral_request = ChatRequest {
-            model: CODESTRAL_MODEL.to_string(),
-            messages,
-            stream: false,
-            max_tokens: None,
-        };
+/// Fails when `source` (a path, or the prompt or stdin) is over `--max-input-bytes`.
+fn check_input_size(source: &str, bytes: usize, limit: Option<u64>) -> Result<()> {
+    match limit {
+        Some(limit) if bytes as u64 > limit => bail!(tr!("input_too_large", source, bytes, limit)),
+        _ => Ok(()),
+    }
+}
 
-        if self.debug {
-            debug!(
-                "Codestral request body: {}",
-                serde_json::to_string(&codestral_request)?
-            );
-        }
+/// Opens `$VISUAL` or `$EDITOR` on an empty buffer and returns what was saved there, failing
+/// when nothing was.
+fn prompt_from_editor() -> Result<String> {
+    let text = dialoguer::Editor::new()
+        .extension(".md")
+        .edit("")
+        .with_context(|| tr!("editor_failed"))?;
+    match text {
+        Some(text) if !text.trim().is_empty() => Ok(text),
+        _ => bail!(tr!("editor_empty")),
+    }
+}
 
-        let codestral_response = self
-            .send_with_retry(|| {
-                self.client
-                    .post(CODESTRAL_URL)
-                    .header(
-                        "Authorization",
-                        format!("Bearer {}", self.codestral_api_key),
-                    )
-                    .json(&codestral_request)
-                    .send()
-            })
-            .await?;
+/// Reads a JSON array of messages to send verbatim.
+fn load_messages(path: &str, limit: Option<u64>) -> Result<Vec<RequestMessage>> {
+    let content = fs::read_to_string(path)
+        .with_context(|| tr!("messages_read_failed", path))?;
+    check_input_size(path, content.len(), limit)?;
+    let messages: Vec<RequestMessage> = serde_json::from_str(&content)
+        .with_context(|| tr!("messages_parse_failed", path))?;
+    if messages.is_empty() {
+        bail!(tr!("messages_empty", path));
+    }
+    Ok(messages)
+}
 
-        let status = codestral_response.status();
-        if self.debug {
-            debug!("CODESTRAL status: {}", status);
+/// Turns `--example USER::ASSISTANT` flags into alternating user and assistant messages.
+fn example_turns(examples: &[String]) -> Result<Vec<RequestMessage>> {
+    let mut messages = Vec::new();
+    for example in examples {
+        let Some((user, assistant)) = example.split_once("::") else {
+            bail!(tr!("example_invalid", example));
+        };
+        let (user, assistant) = (user.trim(), assistant.trim());
+        if user.is_empty() || assistant.is_empty() {
+            bail!(tr!("example_invalid", example));
         }
-        if status.is_success() {
-            info!("CODESTRAL-API connection successful");
-        } else {
-            error!("CODESTRAL-API connection failed: {}", status);
-            if self.debug {
-                let text = codestral_response.text().await?;
-                debug!("CODESTRAL response body: {}", text);
-            }
-            if status == reqwest::StatusCode::UNAUTHORIZED {
-                error!("Hint: Check your Codestral API key.");
-            }
+        for (role, content) in [("user", user), ("assistant", assistant)] {
+            messages.push(RequestMessage {
+                role: role.to_string(),
+                content: content.to_string(),
+                ..Default::default()
+            });
         }
-
-        Ok(())
     }
+    Ok(messages)
+}
 
-    /// Analyzes code using the Codestral API.
-    async fn analyze_code(&self, code: String) -> Result<String> {
-        if self.debug {
-            debug!("Sending code to Codestral API");
-        }
+/// Reads a Markdown transcript to continue.
+fn load_transcript(path: &str, limit: Option<u64>) -> Result<Vec<RequestMessage>> {
+    let content = fs::read_to_string(path)
+        .with_context(|| tr!("transcript_read_failed", path))?;
+    check_input_size(path, content.len(), limit)?;
+    markdown::parse_transcript(&content).with_context(|| tr!("transcript_parse_failed", path))
+}
 
-        let messages = vec![RequestMessage {
-            role: "user".to_string(),
-            content: code,
-        }];
+/// Reads a base64-encoded image from stdin and returns it as a `data:` URI.
+fn read_image_stdin(limit: Option<u64>) -> Result<String> {
+    let mut input = String::new();
+    std::io::stdin()
+        .read_to_string(&mut input)
+        .with_context(|| tr!("image_read_failed"))?;
+    check_input_size(&tr!("input_stdin"), input.len(), limit)?;
+    // `base64` wraps its output at 76 columns, so drop all whitespace before decoding.
+    let encoded: String = input.split_whitespace().collect();
+    let bytes = BASE64
+        .decode(&encoded)
+        .with_context(|| tr!("image_not_base64"))?;
 
-        let request = ChatRequest {
-            model: CODESTRAL_MODEL.to_string(),
-            messages,
-            stream: false,
-            max_tokens: None,
-        };
+    let mime = if bytes.starts_with(b"\x89PNG") {
+        "image/png"
+    } else if bytes.starts_with(&[0xFF, 0xD8, 0xFF]) {
+        "image/jpeg"
+    } else if bytes.starts_with(b"GIF8") {
+        "image/gif"
+    } else if bytes.len() >= 12 && &bytes[..4] == b"RIFF" && &bytes[8..12] == b"WEBP" {
+        "image/webp"
+    } else {
+        bail!(tr!("image_unknown_format"));
+    };
+    Ok(format!("data:{};base64,{}", mime, encoded))
+}
 
-        if self.debug {
-            debug!("Analyze code request: {}", serde_json::to_string(&request)?);
-        }
+/// Builds a cassette from the `--record`/`--replay` flags, if either is set.
+fn cassette(cli: &Cli) -> mistral_chat_cli_rs::Result<Option<Cassette>> {
+    match (&cli.record, &cli.replay) {
+        (Some(dir), _) => Ok(Some(Cassette::new(dir.clone(), CassetteMode::Record)?)),
+        (None, Some(dir)) => Ok(Some(Cassette::new(dir.clone(), CassetteMode::Replay)?)),
+        (None, None) => Ok(None),
+    }
+}
 
-        let response = self
-            .send_with_retry(|| {
-                self.client
-                    .post(CODESTRAL_URL)
-                    .header(
-                        "Authorization",
-                        format!("Bearer {}", self.codestral_api_key),
-                    )
-                    .json(&request)
-                    .send()
-            })
-            .await?
-            .json::<ChatResponse>()
-            .await?;
+/// Logger for the CLI: masks credentials in messages and, with `--quiet-errors`, drops warnings
+/// and errors.
+///
+/// Known keys are masked at every level; debug messages, which carry request and response
+/// bodies, are also masked by the shape of a credential.
+struct CliLogger {
+    inner: env_logger::Logger,
+    quiet_errors: bool,
+}
 
-        if let Some(choice) = response.choices.get(0) {
-            Ok(choice.message.content.clone())
-        } else {
-            Err(anyhow::anyhow!(
-                "Empty response received from Codestral API"
-            ))
+impl log::Log for CliLogger {
+    fn enabled(&self, metadata: &log::Metadata) -> bool {
+        (!self.quiet_errors || metadata.level() > log::Level::Warn) && self.inner.enabled(metadata)
+    }
+
+    fn log(&self, record: &log::Record) {
+        if !self.enabled(record.metadata()) {
+            return;
         }
+        let message = record.args().to_string();
+        let message = if record.level() >= log::Level::Debug {
+            redact::redact(&message)
+        } else {
+            redact::redact_known(&message)
+        };
+        self.inner.log(
+            &log::Record::builder()
+                .args(format_args!("{}", message))
+                .metadata(record.metadata().clone())
+                .module_path(record.module_path())
+                .file(record.file())
+                .line(record.line())
+                .build(),
+        );
+    }
+
+    fn flush(&self) {
+        self.inner.flush();
     }
 }
 
@@ -464,72 +1389,1093 @@ async fn main() -> Result<()> {
     } else {
         log::LevelFilter::Info
     });
-    builder.init();
+    let inner = builder.build();
+    log::set_max_level(inner.filter());
+    log::set_boxed_logger(Box::new(CliLogger {
+        inner,
+        quiet_errors: cli.quiet_errors,
+    }))?;
+    i18n::init(cli.lang.as_deref());
+
+    let quiet_errors = cli.quiet_errors;
+    match run(cli).await {
+        Err(_) if quiet_errors => std::process::exit(1),
+        result => result,
+    }
+}
 
+/// Whether `command` needs the configuration file, as opposed to diagnosing, creating or
+/// editing it, or not using it at all.
+fn reads_config(command: &Commands) -> bool {
+    match command {
+        Commands::Doctor | Commands::History { .. } | Commands::Capabilities => false,
+        Commands::Config { config_command } => {
+            matches!(
+                config_command,
+                ConfigCommands::View | ConfigCommands::Explain | ConfigCommands::Presets
+            )
+        }
+        _ => true,
+    }
+}
+
+/// Runs the parsed command.
+async fn run(cli: Cli) -> Result<()> {
+    let keys_given = cli.mistral_key.is_some() && cli.codestral_key.is_some();
+    if reads_config(&cli.command) && !cli.no_config && !keys_given {
+        setup::ensure_config(&cli.config)?;
+    }
     match &cli.command {
-        Commands::Chat { prompt } => {
-            let config = Config::from_file(&cli.config)
-                .expect("Failed to read configuration file");
-            let debug = cli.debug || config.debug;
-            let chat_client = ChatClient::new(
-                config.mistral_api_key,
-                config.codestral_api_key,
-                debug,
-            );
+        Commands::Chat {
+            prompt,
+            model: model_flag,
+            reasoning,
+            tier,
+            show_reasoning,
+            preset,
+            system_preset,
+            template,
+            history_search,
+            resend,
+            editor,
+            messages_file,
+            from_markdown,
+            no_file_refs,
+            image_stdin,
+            example: examples,
+            start_with,
+            wrap,
+            echo_prompt: echo,
+            lint_prompt: lint,
+            force,
+            stream_format,
+            pretty,
+            eol,
+            sink: sink_specs,
+            timeout_first_token,
+            idle_timeout,
+            typewriter,
+            flush_interval_ms,
+            pager,
+            oneline,
+            no_pager,
+            tui,
+            history_limit,
+            context_window_from_api,
+            truncate_strategy,
+            max_tokens_remaining,
+            no_trailing_newline,
+            auto_continue,
+            max_continues,
+            cache_prompt,
+            resume,
+            explain_error,
+            logprobs,
+            top_logprobs,
+            strict_finish,
+            speak,
+            profile_timing,
+            redact,
+            fallback_models,
+            larger_context_models,
+            export_html,
+        } => {
+            if *pretty && stream_format != "jsonl" {
+                bail!(tr!("pretty_needs_jsonl"));
+            }
+            if let Some(query) = history_search {
+                return prompts::print_search(query);
+            }
+            let prompt = match resend {
+                Some(id) => Some(prompts::get(*id)?),
+                None if *editor => Some(prompt_from_editor()?),
+                None => prompt.clone(),
+            };
+            let prompt = match template {
+                Some(name) => Some(templates::render(name, prompt.as_deref())?),
+                None => prompt,
+            };
+            let config = load_config(&cli).with_context(|| tr!("config_read_failed"))?;
+            let limit = config.max_input_bytes;
+            // The model flag is applied further down, since it also outranks a preset.
+            let defaults = config.chat_defaults().with_flags(Defaults {
+                reasoning_effort: reasoning.clone(),
+                ..Default::default()
+            });
+            let preset = match preset {
+                Some(name) => match config.presets.get(name) {
+                    Some(preset) => Some(preset.clone()),
+                    None => bail!(tr!("no_preset", name)),
+                },
+                None => None,
+            };
+            let system_prompt = system_preset
+                .as_deref()
+                .map(system_prompts::get)
+                .transpose()?;
+
+            let chat_client = chat_client(&cli)?;
+            let mut messages = match (messages_file, from_markdown) {
+                (Some(path), _) => load_messages(path, limit)?,
+                (None, Some(path)) => load_transcript(path, limit)?,
+                (None, None) => Vec::new(),
+            };
+            messages.extend(example_turns(examples)?);
+            // Recorded once the model is settled, so the history can be filtered by it.
+            let new_prompt = prompt.clone();
+            if let Some(mut prompt) = prompt {
+                if !no_file_refs {
+                    prompt = file_refs::expand(&prompt);
+                }
+                check_input_size(&tr!("input_prompt"), prompt.len(), limit)?;
+                let images = if *image_stdin {
+                    vec![read_image_stdin(limit)?]
+                } else {
+                    Vec::new()
+                };
+                messages.push(RequestMessage {
+                    role: "user".to_string(),
+                    content: prompt,
+                    images,
+                    ..Default::default()
+                });
+            }
+            let last_prompt = messages
+                .iter()
+                .rev()
+                .find(|message| message.role == "user")
+                .map(|message| message.content.to_lowercase())
+                .unwrap_or_default();
+            // The prompt itself may settle the model: images need vision, code Codestral.
+            let guessed = if messages.iter().any(|message| !message.images.is_empty()) {
+                Some(VISION_MODEL)
+            } else if last_prompt.contains("code") {
+                Some(CODESTRAL_MODEL)
+            } else {
+                None
+            };
+            let mut model = guessed.unwrap_or(MISTRAL_MODEL);
+            // Flag, then preset, then `[chat]` and global config, then the pick above.
+            if let Some(default_model) = &defaults.model {
+                model = default_model;
+            }
+            if let Some(preset_model) = preset.as_ref().and_then(|preset| preset.model.as_ref())
+            {
+                model = preset_model;
+            }
+            // `--system-preset` is more specific, so it replaces a preset's system message.
+            let system = system_prompt
+                .or_else(|| preset.as_ref().and_then(|preset| preset.system.clone()));
+            if let Some(system) = system {
+                messages.insert(
+                    0,
+                    RequestMessage {
+                        role: "system".to_string(),
+                        content: system,
+                        ..Default::default()
+                    },
+                );
+            }
+            if let Some(model_flag) = model_flag {
+                model = model_flag;
+            }
+            // On a terminal, a model nothing settled on, or a `--model` that is not a known ID,
+            // is picked interactively. A recording or replay skips the picker, which would add
+            // a request to the cassette.
+            let undecided = model_flag.is_none()
+                && defaults.model.is_none()
+                && preset.as_ref().is_none_or(|preset| preset.model.is_none())
+                && guessed.is_none();
+            let ambiguous = model_flag.as_deref().is_some_and(|id| !models::is_cached(id));
+            let interactive = std::io::stdin().is_terminal()
+                && std::io::stderr().is_terminal()
+                && cli.record.is_none()
+                && cli.replay.is_none();
+            let picked = if interactive && (undecided || ambiguous) {
+                models::resolve(&chat_client, model_flag.as_deref(), model).await?
+            } else {
+                None
+            };
+            if let Some(picked) = &picked {
+                model = picked;
+            }
+            if let Some(prompt) = &new_prompt {
+                if let Err(e) = prompts::record(prompt, model) {
+                    warn!("{}", tr!("history_save_failed", format!("{:#}", e)));
+                }
+            }
+            if *cache_prompt {
+                // The leading system messages are the prefix that repeats from turn to turn.
+                let system = messages.iter_mut().take_while(|m| m.role == "system");
+                let marked = system.map(|message| message.cache = true).count();
+                if marked == 0 {
+                    warn!("{}", tr!("nothing_to_cache"));
+                }
+            }
+            let mut params = ChatParams {
+                reasoning_effort: defaults.reasoning_effort.clone(),
+                service_tier: tier.clone(),
+                ..Default::default()
+            };
+            if *lint {
+                lint_prompt(&messages, *force)?;
+            }
+            let mut history_limit = *history_limit;
+            if *context_window_from_api {
+                match models::context_length(&chat_client, model).await {
+                    Ok(Some(window)) => {
+                        let window = usize::try_from(window).unwrap_or(usize::MAX);
+                        debug!("Context window of {}: {} tokens", model, window);
+                        // An explicit `--history-limit` may only tighten the budget.
+                        let limit = history_limit.map_or(window, |limit| limit.min(window));
+                        history_limit = Some(limit);
+                    }
+                    Ok(None) => warn!("{}", tr!("no_context_window", model)),
+                    Err(e) => warn!("{}", tr!("context_window_failed", format!("{:#}", e))),
+                }
+            }
+            let history_limit = history_limit.map(|tokens| HistoryLimit {
+                tokens,
+                strategy: match truncate_strategy.as_str() {
+                    "summarize" => TruncateStrategy::Summarize,
+                    _ => TruncateStrategy::Drop,
+                },
+            });
+            if *tui {
+                return tui::run(&chat_client, model, messages, &params, history_limit).await;
+            }
+            if let Some(limit) = &history_limit {
+                let removed = limit.apply(&chat_client, model, &mut messages).await?;
+                if removed > 0 {
+                    warn!("{}", tr!("history_shortened", removed));
+                }
+            }
+            // Added after shortening, so the prompt is the latest message that is always kept.
+            if let Some(start) = start_with {
+                messages.push(RequestMessage {
+                    role: "assistant".to_string(),
+                    content: start.clone(),
+                    prefix: true,
+                    ..Default::default()
+                });
+            }
+            if *max_tokens_remaining {
+                params.max_tokens = remaining_tokens(&chat_client, model, &messages).await;
+            }
+            if *echo {
+                echo_prompt(&messages);
+            }
+            if *logprobs {
+                let answer = match chat_client
+                    .chat_logprobs(model, messages, &params, *top_logprobs)
+                    .await
+                {
+                    Ok(answer) => answer,
+                    Err(e) if *explain_error => return Err(explain(&chat_client, e).await),
+                    Err(e) => return Err(e.into()),
+                };
+                println!("{}", answer.content);
+                if answer.tokens.is_empty() {
+                    warn!("{}", tr!("no_logprobs", model));
+                } else {
+                    println!("\n{}", logprobs_table(&answer.tokens));
+                }
+                if *strict_finish {
+                    std::process::exit(finish_exit_code(answer.finish_reason.as_deref()));
+                }
+                return Ok(());
+            }
+            // Ctrl-C stops the response where it is instead of killing the process mid-write.
+            let cancel = CancellationToken::new();
+            let on_interrupt = cancel.clone();
+            tokio::spawn(async move {
+                if tokio::signal::ctrl_c().await.is_ok() {
+                    on_interrupt.cancel();
+                }
+            });
+            let mut options = StreamOptions {
+                wrap: wrap.then(Wrapper::terminal_width),
+                // Continuations are stitched together, so the newline is printed once at the end.
+                trailing_newline: !no_trailing_newline && !auto_continue && *resume == 0,
+                typewriter: typewriter
+                    .filter(|_| std::io::stdout().is_terminal())
+                    .map(Duration::from_millis),
+                flush_interval: flush_interval_ms.map(Duration::from_millis),
+                print_prefix: start_with.is_some(),
+                show_reasoning: *show_reasoning,
+                cancel: Some(cancel),
+                jsonl: stream_format == "jsonl",
+                pretty: *pretty,
+                eol: match eol.as_deref() {
+                    Some("crlf") => LineEnding::Crlf,
+                    Some(_) => LineEnding::Lf,
+                    None => LineEnding::native(),
+                },
+                stdout: sink_specs.is_empty() || sink_specs.iter().any(|spec| spec == "stdout"),
+                sinks: sink_specs
+                    .iter()
+                    .filter(|spec| *spec != "stdout")
+                    .map(|spec| sink::from_spec(spec))
+                    .collect::<mistral_chat_cli_rs::Result<_>>()?,
+                first_token_timeout: timeout_first_token.map(Duration::from_secs),
+                idle_timeout: idle_timeout.map(Duration::from_secs),
+                redact: match (*redact, config.redact_patterns.is_empty()) {
+                    (false, _) => None,
+                    (true, true) => Some(OutputPatterns::new(&DEFAULT_OUTPUT_PATTERNS)?),
+                    (true, false) => Some(OutputPatterns::new(&config.redact_patterns)?),
+                },
+                fallback_models: if fallback_models.is_empty() {
+                    config.fallback_models.clone()
+                } else {
+                    fallback_models.clone()
+                },
+                larger_context_models: if larger_context_models.is_empty() {
+                    config.larger_context_models.clone()
+                } else {
+                    larger_context_models.clone()
+                },
+                // One budget for the answer, continuations and resumes included.
+                deadline: chat_client.request_deadline(),
+            };
+            // Paging and `--oneline` need the whole response, so they take over from printing
+            // as it streams.
+            if *oneline && options.stdout {
+                options.stdout = false;
+                options.sinks.push(Box::new(sink::OnelineSink::default()));
+            }
+            let page = (*pager || config.pager)
+                && !no_pager
+                && options.stdout
+                && !options.jsonl
+                && std::io::stdout().is_terminal();
+            if page {
+                options.stdout = false;
+                options.sinks.push(Box::new(sink::PagerSink::default()));
+            }
+            if *speak {
+                let command = config.tts_command.as_deref().unwrap_or(DEFAULT_TTS_COMMAND);
+                // Spoken once the whole response, continuations included, has arrived.
+                options.sinks.push(Box::new(sink::CommandSink::new(command)));
+            }
+            // Continuations stay with the model that answered, which may be a fallback.
+            let stream = |model: String, messages: Vec<RequestMessage>| {
+                let (chat_client, params, options) = (&chat_client, &params, &options);
+                async move {
+                    match stream_resuming(chat_client, &model, messages, params, options, *resume)
+                        .await
+                    {
+                        Ok(outcome) => Ok(outcome),
+                        Err(e) => {
+                            if e.is_context_length_exceeded() {
+                                // Only a request that climbed the list has tried larger models.
+                                let climbed = matches!(
+                                    e,
+                                    mistral_chat_cli_rs::Error::ContextLengthExceeded { .. }
+                                );
+                                let hint = if climbed {
+                                    tr!("context_too_long_all")
+                                } else {
+                                    tr!("context_too_long")
+                                };
+                                warn!("{}", hint);
+                            }
+                            if *explain_error {
+                                Err(explain(chat_client, e).await)
+                            } else {
+                                Err(anyhow::Error::from(e))
+                            }
+                        }
+                    }
+                }
+            };
+            // The messages as the user sees them: a `--start-with` prefix is part of the answer.
+            let conversation = export_html.is_some().then(|| {
+                let sent = messages.iter().filter(|message| !message.prefix);
+                sent.cloned().collect::<Vec<_>>()
+            });
+            let mut outcome = stream(model.to_string(), messages.clone()).await?;
+            let mut answer = String::new();
+            // Whether the stitched output ends a line; a continuation may add no text at all.
+            let ends_line_of = |text: &str| text.is_empty() || text.ends_with(['\r', '\n']);
+            let mut ends_line = ends_line_of(&outcome.content);
+            let mut redactions = outcome.redactions;
+            let mut continues = 0;
+            while *auto_continue && outcome.finish_reason.as_deref() == Some("length") {
+                if continues == *max_continues {
+                    warn!("{}", tr!("still_truncated", continues));
+                    break;
+                }
+                continues += 1;
+                let served_by = outcome.model;
+                debug!(
+                    "Output truncated, continuing ({}/{})",
+                    continues, max_continues
+                );
+                answer.push_str(&outcome.content);
+                messages.push(RequestMessage {
+                    role: "assistant".to_string(),
+                    content: outcome.content,
+                    ..Default::default()
+                });
+                messages.push(RequestMessage {
+                    role: "user".to_string(),
+                    content: CONTINUE_PROMPT.to_string(),
+                    ..Default::default()
+                });
+                outcome = stream(served_by, messages.clone()).await?;
+                redactions += outcome.redactions;
+                if !outcome.content.is_empty() {
+                    ends_line = ends_line_of(&outcome.content);
+                }
+            }
+            let newline_pending = !no_trailing_newline && (*auto_continue || *resume > 0);
+            if newline_pending && options.stdout && !ends_line {
+                print!("{}", options.eol.as_str());
+            }
+            for sink in &options.sinks {
+                if let Err(e) = sink.finish() {
+                    warn!("{}", tr!("sink_failed", e));
+                }
+            }
+            if let (Some(path), Some(mut conversation)) = (export_html, conversation) {
+                answer.push_str(&outcome.content);
+                conversation.push(RequestMessage {
+                    role: "assistant".to_string(),
+                    content: answer,
+                    ..Default::default()
+                });
+                let page = html::transcript(&conversation, &tr!("export_title", outcome.model));
+                fs::write(path, page).with_context(|| tr!("export_failed", path))?;
+            }
+            if *profile_timing {
+                eprintln!("{}", timing_table(&outcome.timings));
+            }
+            if redactions > 0 {
+                info!("{}", tr!("redacted", redactions));
+            }
+            if !options.fallback_models.is_empty() || !options.larger_context_models.is_empty() {
+                info!("{}", tr!("served_by", outcome.model));
+            }
+            if outcome.cancelled {
+                // The conventional exit status for a process stopped by SIGINT.
+                std::process::exit(130);
+            }
+            if outcome.timed_out {
+                // The exit status `timeout(1)` uses.
+                std::process::exit(124);
+            }
+            if *strict_finish {
+                std::process::exit(finish_exit_code(outcome.finish_reason.as_deref()));
+            }
+        }
+        Commands::Test { output } => {
+            let chat_client = chat_client(&cli)?;
+            let mut tests = chat_client.test_connection().await?;
+            for test in &tests {
+                if test.status == Some(reqwest::StatusCode::UNAUTHORIZED) {
+                    warn!("{}", tr!("check_api_key", test.title));
+                }
+            }
+            if output == "json" {
+                let report: serde_json::Map<String, serde_json::Value> = tests
+                    .iter()
+                    .map(|test| {
+                        let mut result = serde_json::json!({
+                            "ok": test.error.is_none(),
+                            "status": test.status.map(|status| status.as_u16()),
+                            "latency_ms": test.latency.as_millis() as u64,
+                        });
+                        if let Some(e) = &test.error {
+                            result["error"] = e.to_string().into();
+                        }
+                        (test.name.to_string(), result)
+                    })
+                    .collect();
+                println!("{}", serde_json::to_string_pretty(&report)?);
+                if tests.iter().any(|test| test.error.is_some()) {
+                    std::process::exit(1);
+                }
+            } else if let Some(e) = tests.iter_mut().find_map(|test| test.error.take()) {
+                // The first failure decides the error shown.
+                return Err(e.into());
+            }
+        }
+        Commands::Ping => {
+            let chat_client = chat_client(&cli)?;
+            let mut all_up = true;
+            for ping in chat_client.ping().await {
+                match ping.result {
+                    Ok((status, latency)) => println!(
+                        "{:<10} {:<5} {:>5} ms  {} ({})",
+                        ping.name,
+                        tr!("ping_up"),
+                        latency.as_millis(),
+                        ping.url,
+                        status
+                    ),
+                    Err(e) => {
+                        all_up = false;
+                        let e = anyhow::Error::from(e);
+                        let down = tr!("ping_down");
+                        println!("{:<10} {:<5} {} ({:#})", ping.name, down, ping.url, e);
+                    }
+                }
+            }
+            if !all_up {
+                std::process::exit(1);
+            }
+        }
+        Commands::Doctor => {
+            let config_file = (!cli.no_config).then_some(cli.config.as_str());
+            if !doctor::run(config_file, load_config(&cli), cli.debug).await {
+                std::process::exit(1);
+            }
+        }
+        Commands::Capabilities => {
+            let mut command = Cli::command();
+            command.build();
+            let description = capabilities::describe(&command);
+            println!("{}", serde_json::to_string_pretty(&description)?);
+        }
+        #[cfg(unix)]
+        Commands::Serve { socket } => {
+            serve::run(chat_client(&cli)?, socket).await?;
+        }
+        Commands::Bench {
+            model,
+            requests,
+            prompt,
+            concurrency,
+        } => {
+            let limit = load_config(&cli)
+                .with_context(|| tr!("config_read_failed"))?
+                .max_input_bytes;
+            check_input_size(&tr!("input_prompt"), prompt.len(), limit)?;
+            let chat_client = chat_client(&cli)?;
+            let requests = *requests as usize;
+            let succeeded =
+                bench::run(&chat_client, model, prompt, requests, *concurrency as usize).await;
+            if succeeded == 0 {
+                std::process::exit(1);
+            }
+        }
+        Commands::Compare { prompt, models } => {
+            let limit = load_config(&cli)
+                .with_context(|| tr!("config_read_failed"))?
+                .max_input_bytes;
+            check_input_size(&tr!("input_prompt"), prompt.len(), limit)?;
+            let chat_client = chat_client(&cli)?;
+            if compare::run(&chat_client, models, prompt).await < models.len() {
+                std::process::exit(1);
+            }
+        }
+        Commands::Replay { request } => {
+            use futures_util::StreamExt;
+            use tokio::io::AsyncWriteExt;
+
+            let body = fs::read(request).with_context(|| tr!("read_failed", request.display()))?;
+            let limit = load_config(&cli)
+                .with_context(|| tr!("config_read_failed"))?
+                .max_input_bytes;
+            check_input_size(&request.display().to_string(), body.len(), limit)?;
+            let streaming = serde_json::from_slice::<serde_json::Value>(&body)
+                .with_context(|| tr!("replay_invalid", request.display()))?
+                .get("stream")
+                .and_then(serde_json::Value::as_bool)
+                .unwrap_or(false);
+            let chat_client = chat_client(&cli)?;
+            let response = chat_client.send_raw(body).await?;
+            let status = response.status();
+            eprintln!("{:?} {}", response.version(), status);
+            for (name, value) in response.headers() {
+                eprintln!("{}: {}", name, String::from_utf8_lossy(value.as_bytes()));
+            }
+            eprintln!();
+            let mut stdout = tokio::io::stdout();
+            if streaming {
+                let mut body = response.bytes_stream();
+                while let Some(chunk) = body.next().await {
+                    stdout.write_all(&chunk?).await?;
+                    stdout.flush().await?;
+                }
+            } else {
+                stdout.write_all(&chat_client.read_body(response).await?).await?;
+                stdout.flush().await?;
+            }
+            if !status.is_success() {
+                bail!(tr!("replay_status", status));
+            }
+        }
+        Commands::Code {
+            code,
+            file,
+            watch,
+            echo_prompt: echo,
+            git_context,
+            staged,
+            chunk_size,
+            overlap,
+            summarize,
+            extract_code,
+            first_block,
+            no_system,
+        } => {
+            let extract = extract_code.then_some(*first_block);
+            let mut chat_client = chat_client(&cli)?;
+            if *no_system {
+                chat_client = chat_client.without_code_system_prompt();
+            }
+            let config = load_config(&cli).with_context(|| tr!("config_read_failed"))?;
+            let defaults = config.code_defaults();
+            let model = defaults.model.as_deref().unwrap_or(CODESTRAL_MODEL);
+            let params = ChatParams {
+                reasoning_effort: defaults.reasoning_effort,
+                ..Default::default()
+            };
+            if chunk_size.is_some_and(|chunk_size| *overlap >= chunk_size) {
+                bail!(tr!("overlap_too_large"));
+            }
+            let analyze = |code: String| {
+                let (chat_client, config, params) = (&chat_client, &config, &params);
+                async move {
+                    let content = match git_context.then(|| git::context(*staged)).flatten() {
+                        Some(context) => format!("{}\n{}", context, code),
+                        None => code,
+                    };
+                    check_input_size(&tr!("input_code"), content.len(), config.max_input_bytes)?;
+                    let Some(chunk_size) = chunk_size else {
+                        let messages = ChatClient::code_messages(content);
+                        if *echo {
+                            echo_prompt(&messages);
+                        }
+                        let analysis = chat_client.analyze_code(model, messages, params).await?;
+                        print_analysis(&analysis, extract);
+                        return Ok(());
+                    };
+
+                    let chunks = chunk::split(&content, *chunk_size as usize, *overlap as usize);
+                    let mut analyses = Vec::new();
+                    for (index, piece) in chunks.iter().enumerate() {
+                        info!("{}", tr!("analyzing_chunk", index + 1, chunks.len()));
+                        let messages = ChatClient::code_messages(format!(
+                            "This is part {} of {} of a larger input.\n{}",
+                            index + 1,
+                            chunks.len(),
+                            piece
+                        ));
+                        if *echo {
+                            echo_prompt(&messages);
+                        }
+                        let analysis = chat_client.analyze_code(model, messages, params).await?;
+                        if !*summarize {
+                            info!("{}", tr!("chunk_heading", index + 1, chunks.len()));
+                            print_analysis(&analysis, extract);
+                        }
+                        analyses.push(analysis);
+                    }
+                    if *summarize {
+                        info!("{}", tr!("summarizing_chunks", analyses.len()));
+                        let mut request = String::from(
+                            "The following are analyses of consecutive parts of one input. \
+                             Combine them into a single answer.\n",
+                        );
+                        for (index, analysis) in analyses.iter().enumerate() {
+                            request.push_str(&format!("\nPart {}:\n{}\n", index + 1, analysis));
+                        }
+                        let summary = chat_client
+                            .analyze_code(model, ChatClient::code_messages(request), params)
+                            .await?;
+                        print_analysis(&summary, extract);
+                    }
+                    Ok::<_, anyhow::Error>(())
+                }
+            };
+            let read =
+                |path: &str| fs::read_to_string(path).with_context(|| tr!("read_failed", path));
+            let Some(path) = file.as_deref().filter(|_| *watch) else {
+                let code = match file {
+                    Some(path) => read(path)?,
+                    None => code.clone().unwrap_or_default(),
+                };
+                return analyze(code).await;
+            };
+
+            // Ctrl-C ends the loop at any point, during a request as well as between changes.
+            let mut watcher =
+                watch::Watcher::new(Path::new(path)).with_context(|| tr!("read_failed", path))?;
+            loop {
+                if std::io::stdout().is_terminal() {
+                    // Flushed now: stdout is line-buffered, and the analysis goes to stderr.
+                    let mut stdout = std::io::stdout();
+                    stdout.write_all(b"\x1b[2J\x1b[H")?;
+                    stdout.flush()?;
+                }
+                let run = async {
+                    analyze(read(path)?).await?;
+                    info!("{}", tr!("watching", path));
+                    Ok::<_, anyhow::Error>(())
+                };
+                tokio::select! {
+                    result = run => if let Err(e) = result {
+                        warn!("{}", tr!("watch_run_failed", format!("{:#}", e)));
+                    },
+                    _ = tokio::signal::ctrl_c() => break,
+                }
+                tokio::select! {
+                    _ = watcher.next_change() => {}
+                    _ = tokio::signal::ctrl_c() => break,
+                }
+            }
+        }
+        Commands::Rewrite {
+            file,
+            instruction,
+            in_place,
+        } => {
+            let config = load_config(&cli).with_context(|| tr!("config_read_failed"))?;
+            let code = fs::read_to_string(file).with_context(|| tr!("read_failed", file))?;
+            check_input_size(file, code.len(), config.max_input_bytes)?;
+            let defaults = config.code_defaults();
+            let model = defaults.model.as_deref().unwrap_or(CODESTRAL_MODEL);
+            let params = ChatParams {
+                reasoning_effort: defaults.reasoning_effort,
+                ..Default::default()
+            };
+            let messages = vec![
+                RequestMessage {
+                    role: "system".to_string(),
+                    content: REWRITE_PROMPT.to_string(),
+                    ..Default::default()
+                },
+                RequestMessage {
+                    role: "user".to_string(),
+                    content: format!("{}\n\n{}", instruction, file_refs::fenced(file, &code)),
+                    ..Default::default()
+                },
+            ];
+            let chat_client = chat_client(&cli)?.without_code_system_prompt();
+            let answer = chat_client.analyze_code(model, messages, &params).await?;
+            let Some(rewritten) = markdown::code_blocks(&answer).into_iter().next() else {
+                bail!(tr!("rewrite_no_code"));
+            };
+            if *in_place {
+                let backup = format!("{}.bak", file);
+                fs::copy(file, &backup).with_context(|| tr!("write_failed", backup))?;
+                fs::write(file, &rewritten).with_context(|| tr!("write_failed", file))?;
+                info!("{}", tr!("rewrite_written", file, backup));
+            } else {
+                print!("{}", rewritten);
+            }
+        }
+        Commands::Summarize {
+            url,
+            max_chars,
+            model,
+            lint_prompt: lint,
+            force,
+        } => {
+            let chat_client = chat_client(&cli)?;
+            let text = chat_client.fetch_page(url, MAX_PAGE_BYTES).await?;
+            if text.trim().is_empty() {
+                bail!(tr!("no_readable_text", url));
+            }
+            let text = match text.char_indices().nth(*max_chars) {
+                Some((end, _)) => {
+                    warn!("{}", tr!("page_truncated", max_chars));
+                    &text[..end]
+                }
+                None => &text,
+            };
             let messages = vec![RequestMessage {
                 role: "user".to_string(),
-                content: prompt.clone(),
+                content: format!("{}\n\nURL: {}\n\n{}", SUMMARIZE_PROMPT, url, text),
+                ..Default::default()
             }];
-            let model = if prompt.to_lowercase().contains("code") {
-                CODESTRAL_MODEL
-            } else {
-                MISTRAL_MODEL
-            };
-            chat_client.chat_stream(model, messages).await?;
+            if *lint {
+                lint_prompt(&messages, *force)?;
+            }
+            chat_client
+                .chat_stream(
+                    model,
+                    messages,
+                    &ChatParams::default(),
+                    &StreamOptions::default(),
+                )
+                .await?;
+        }
+        Commands::Ocr { path, output } => {
+            let limit = load_config(&cli)
+                .with_context(|| tr!("config_read_failed"))?
+                .max_input_bytes;
+            let bytes = fs::metadata(path).with_context(|| tr!("read_failed", path))?.len();
+            check_input_size(path, bytes as usize, limit)?;
+            let chat_client = chat_client(&cli)?;
+            let text = chat_client.ocr(Path::new(path)).await?;
+            match output {
+                Some(file_path) => {
+                    fs::write(file_path, &text)?;
+                    println!("{}", tr!("ocr_written", file_path));
+                }
+                None => println!("{}", text),
+            }
+        }
+        Commands::Models { sort, filter } => {
+            let chat_client = chat_client(&cli)?;
+            let mut models = chat_client.list_models().await?;
+            if let Err(e) = models::store(&models) {
+                debug!("Could not cache the model list: {:#}", e);
+            }
+            if let Some(filter) = filter {
+                let filter = filter.to_lowercase();
+                models.retain(|model| model.id.to_lowercase().contains(&filter));
+            }
+            models.sort_by(|a, b| a.id.cmp(&b.id));
+            if sort == "context" {
+                // Largest first, with models that report no context length last; ties stay by ID.
+                models.sort_by_key(|model| std::cmp::Reverse(model.max_context_length));
+            }
+            if models.is_empty() {
+                println!("{}", tr!("no_models"));
+                return Ok(());
+            }
+            let mut table = comfy_table::Table::new();
+            table
+                .load_style(comfy_table::presets::UTF8_FULL_CONDENSED)
+                .set_content_arrangement(comfy_table::ContentArrangement::Dynamic)
+                .set_header([tr!("column_id"), tr!("column_context"), tr!("column_capabilities")]);
+            for model in &models {
+                table.add_row([
+                    model.id.clone(),
+                    model
+                        .max_context_length
+                        .map_or("-".to_string(), |tokens| tokens.to_string()),
+                    model.enabled_capabilities().join(", "),
+                ]);
+            }
+            println!("{}", table);
         }
-        Commands::Test => {
-            let config = Config::from_file(&cli.config)
-                .expect("Failed to read configuration file");
-            let debug = cli.debug || config.debug;
-            let chat_client = ChatClient::new(
-                config.mistral_api_key,
-                config.codestral_api_key,
-                debug,
-            );
-            chat_client.test_connection().await?;
+        Commands::Files { files_command } => {
+            let chat_client = chat_client(&cli)?;
+            match files_command {
+                FilesCommands::Upload { path, purpose } => {
+                    let limit = load_config(&cli)
+                        .with_context(|| tr!("config_read_failed"))?
+                        .max_input_bytes;
+                    let bytes = fs::metadata(path).with_context(|| tr!("read_failed", path))?.len();
+                    check_input_size(path, bytes as usize, limit)?;
+                    let file = chat_client.upload_file(Path::new(path), purpose).await?;
+                    println!("{}", tr!("file_uploaded", file.filename, file.id));
+                }
+                FilesCommands::List => {
+                    let files = chat_client.list_files().await?;
+                    if files.is_empty() {
+                        println!("{}", tr!("no_files"));
+                    }
+                    for file in files {
+                        println!(
+                            "{:<38} {:<10} {:>12}  {}",
+                            file.id, file.purpose, file.bytes, file.filename
+                        );
+                    }
+                }
+                FilesCommands::Delete { id } => {
+                    chat_client.delete_file(id).await?;
+                    println!("{}", tr!("file_deleted", id));
+                }
+            }
         }
-        Commands::Code { code } => {
-            let config = Config::from_file(&cli.config)
-                .expect("Failed to read configuration file");
-            let debug = cli.debug || config.debug;
-            let chat_client = ChatClient::new(
-                config.mistral_api_key,
-                config.codestral_api_key,
-                debug,
-            );
-            let analysis = chat_client.analyze_code(code.clone()).await?;
-            info!("{}", analysis);
+        Commands::History {
+            since,
+            until,
+            model,
+            grep,
+        } => {
+            let now = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |d| d.as_secs() as i64);
+            let parse = |text: &String| {
+                time::parse(text, now).with_context(|| tr!("invalid_time", text))
+            };
+            let filter = prompts::HistoryFilter {
+                since: since.as_ref().map(parse).transpose()?.map(|(start, _)| start),
+                until: until.as_ref().map(parse).transpose()?.map(|(_, end)| end),
+                model: model.as_deref(),
+                grep: grep.as_deref(),
+            };
+            prompts::print_history(&filter)?;
+        }
+        Commands::Batch { batch_command } => {
+            let chat_client = chat_client(&cli)?;
+            match batch_command {
+                BatchCommands::List => {
+                    let jobs = chat_client.list_batch_jobs().await?;
+                    if jobs.is_empty() {
+                        println!("{}", tr!("no_batch_jobs"));
+                        return Ok(());
+                    }
+                    println!("{}", batch_table(&jobs));
+                }
+                BatchCommands::Status { id } => {
+                    let job = chat_client.batch_job(id).await?;
+                    println!("{}: {}", tr!("column_id"), job.id);
+                    println!("{}: {}", tr!("column_status"), job.status);
+                    println!("{}: {}", tr!("column_created"), time::utc_time(job.created_at));
+                    if let Some(model) = &job.model {
+                        println!("{}: {}", tr!("column_model"), model);
+                    }
+                    println!("{}: {}", tr!("column_progress"), batch_progress(&job));
+                    println!(
+                        "{}",
+                        tr!("batch_succeeded", job.succeeded_requests, job.failed_requests)
+                    );
+                    if let Some(file) = &job.output_file {
+                        println!("{}", tr!("batch_output_file", file));
+                    }
+                    if let Some(file) = &job.error_file {
+                        println!("{}", tr!("batch_error_file", file));
+                    }
+                }
+                BatchCommands::Cancel { id } => {
+                    let job = chat_client.cancel_batch_job(id).await?;
+                    println!("{}", tr!("batch_cancelled", job.id, job.status));
+                }
+                BatchCommands::Run {
+                    input,
+                    model,
+                    output,
+                    concurrency,
+                } => {
+                    let text = fs::read_to_string(input)
+                        .with_context(|| tr!("read_failed", input.display()))?;
+                    let config = load_config(&cli).with_context(|| tr!("config_read_failed"))?;
+                    let limit = config.max_input_bytes;
+                    check_input_size(&input.display().to_string(), text.len(), limit)?;
+                    let model = model.clone().or(config.model).unwrap_or(MISTRAL_MODEL.to_string());
+                    let plan = batch::Plan::parse(&text, &model)?;
+                    eprintln!(
+                        "{}",
+                        tr!("batch_deduplicated", plan.len(), plan.unique(), plan.duplicates())
+                    );
+                    let results = batch::run(&chat_client, &plan, *concurrency as usize).await;
+                    let failed = results.iter().filter(|line| line.get("error").is_some()).count();
+                    let mut lines = String::new();
+                    for line in &results {
+                        lines.push_str(&line.to_string());
+                        lines.push('\n');
+                    }
+                    match output {
+                        Some(path) => fs::write(path, lines)
+                            .with_context(|| tr!("write_failed", path.display()))?,
+                        None => print!("{}", lines),
+                    }
+                    if failed > 0 {
+                        eprintln!("{}", tr!("batch_run_failed", failed, results.len()));
+                        std::process::exit(1);
+                    }
+                }
+            }
         }
         Commands::Config { config_command } => match config_command {
             ConfigCommands::Generate { path } => {
                 let file_path = path.as_deref().unwrap_or("config.toml");
                 Config::generate_sample_config(file_path)
-                    .expect("Failed to generate config file");
-                println!("Sample config file generated at {}", file_path);
+                    .with_context(|| tr!("config_generate_failed"))?;
+                println!("{}", tr!("config_generated", file_path));
             }
             ConfigCommands::View => {
-                let config = Config::from_file(&cli.config)
-                    .expect("Failed to read configuration file");
-                Config::view_config(&config);
+                let config = load_config(&cli).with_context(|| tr!("config_read_failed"))?;
+                view_config(&config);
+            }
+            ConfigCommands::Explain => {
+                let config = load_config(&cli).with_context(|| tr!("config_read_failed"))?;
+                explain_config(&cli, &config)?;
+            }
+            ConfigCommands::Presets => {
+                let config = load_config(&cli).with_context(|| tr!("config_read_failed"))?;
+                if config.presets.is_empty() {
+                    println!("{}", tr!("no_presets"));
+                }
+                for (name, preset) in &config.presets {
+                    let description = preset.description.as_deref().unwrap_or_default();
+                    println!("{}", format!("{:<20} {}", name, description).trim_end());
+                }
+            }
+            ConfigCommands::Templates => {
+                let templates = templates::list()?;
+                if templates.is_empty() {
+                    println!("{}", tr!("no_templates"));
+                }
+                for (name, description) in templates {
+                    println!("{}", format!("{:<20} {}", name, description).trim_end());
+                }
+            }
+            ConfigCommands::SystemPrompts => {
+                let prompts = system_prompts::list()?;
+                if prompts.is_empty() {
+                    println!("{}", tr!("no_system_prompts"));
+                }
+                for (name, first_line) in prompts {
+                    println!("{}", format!("{:<20} {}", name, first_line).trim_end());
+                }
             }
             ConfigCommands::Load { file_path } => {
                 let config = Config::from_file(file_path)
-                    .expect("Failed to read configuration file");
-                println!("Configuration loaded from {}", file_path);
-                Config::view_config(&config);
+                    .with_context(|| tr!("config_read_failed"))?;
+                println!("{}", tr!("config_loaded", file_path));
+                view_config(&config);
                 // Optionally, update the default configuration file if needed.
                 // fs::copy(file_path, &cli.config).expect("Failed to set new default config file");
             }
+            ConfigCommands::Set { key, value } => {
+                Config::set_key(&cli.config, key, value)?;
+                println!("{}", tr!("key_set", key, cli.config));
+            }
+            ConfigCommands::Unset { key } => {
+                Config::unset_key(&cli.config, key)?;
+                println!("{}", tr!("key_removed", key, cli.config));
+            }
+            ConfigCommands::Export { path, passphrase } => {
+                let content = fs::read(&cli.config)
+                    .with_context(|| tr!("read_failed", cli.config))?;
+                let passphrase = match passphrase {
+                    Some(passphrase) => passphrase.clone(),
+                    None => {
+                        let passphrase = read_passphrase(&tr!("passphrase_prompt"))?;
+                        // A typo would make the bundle unreadable, so ask twice when typed.
+                        if std::io::stdin().is_terminal()
+                            && read_passphrase(&tr!("passphrase_repeat"))? != passphrase
+                        {
+                            bail!(tr!("passphrase_mismatch"));
+                        }
+                        passphrase
+                    }
+                };
+                if passphrase.is_empty() {
+                    bail!(tr!("passphrase_empty"));
+                }
+                fs::write(path, bundle::seal(&content, &passphrase)?)
+                    .with_context(|| tr!("write_failed", path.display()))?;
+                println!("{}", tr!("bundle_exported", cli.config, path.display()));
+            }
+            ConfigCommands::Import { path, passphrase, force } => {
+                if Path::new(&cli.config).exists() && !force {
+                    bail!(tr!("config_exists", cli.config));
+                }
+                let sealed = fs::read(path).with_context(|| tr!("read_failed", path.display()))?;
+                let passphrase = match passphrase {
+                    Some(passphrase) => passphrase.clone(),
+                    None => read_passphrase(&tr!("passphrase_prompt"))?,
+                };
+                let content = bundle::open(&sealed, &passphrase)?;
+                let valid = std::str::from_utf8(&content)
+                    .is_ok_and(|text| text.parse::<toml::Table>().is_ok());
+                if !valid {
+                    bail!(tr!("bundle_not_config"));
+                }
+                write_private(Path::new(&cli.config), &content)
+                    .with_context(|| tr!("write_failed", cli.config))?;
+                println!("{}", tr!("bundle_imported", path.display(), cli.config));
+            }
         },
     }
 
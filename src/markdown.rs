@@ -0,0 +1,432 @@
+use crate::error::{Error, Result};
+use crate::html::escape;
+use crate::RequestMessage;
+
+/// Returns the contents of the fenced code blocks in `text`, without the fences.
+///
+/// Both backtick and tilde fences are recognised; a block is closed by a fence of the same
+/// character that is at least as long as the opening one. An unclosed block runs to the end.
+pub fn code_blocks(text: &str) -> Vec<String> {
+    let mut blocks = Vec::new();
+    // The opening fence's character and length, while inside a block.
+    let mut fence: Option<(char, usize)> = None;
+    let mut block = String::new();
+
+    for line in text.split_inclusive('\n') {
+        let trimmed = line.trim();
+        match fence {
+            None => {
+                if let Some(opening) = fence_of(trimmed) {
+                    fence = Some(opening);
+                }
+            }
+            Some((ch, len)) => {
+                let closes = fence_of(trimmed).is_some_and(|(c, l)| {
+                    c == ch && l >= len && trimmed.trim_start_matches(ch).is_empty()
+                });
+                if closes {
+                    blocks.push(std::mem::take(&mut block));
+                    fence = None;
+                } else {
+                    block.push_str(line);
+                }
+            }
+        }
+    }
+    if fence.is_some() {
+        blocks.push(block);
+    }
+    blocks
+}
+
+/// Recognises a fence line of three or more backticks or tildes, returning its character and length.
+fn fence_of(line: &str) -> Option<(char, usize)> {
+    let ch = line.chars().next().filter(|c| *c == '`' || *c == '~')?;
+    let len = line.chars().take_while(|c| *c == ch).count();
+    (len >= 3).then_some((ch, len))
+}
+
+/// Parses a transcript of `## You`, `## Assistant` and `## System` sections into messages.
+///
+/// Each heading starts a message that runs until the next heading; blank lines around the text
+/// are dropped. Headings inside fenced code blocks are part of the text. Text before the first
+/// heading, an unknown `##` heading or an empty section is an error naming its line.
+pub fn parse_transcript(text: &str) -> Result<Vec<RequestMessage>> {
+    let mut messages: Vec<RequestMessage> = Vec::new();
+    // The line of the current section's heading, to report an empty section.
+    let mut heading_line = 0;
+    let mut fence: Option<(char, usize)> = None;
+
+    for (index, line) in text.lines().enumerate() {
+        let number = index + 1;
+        let trimmed = line.trim();
+        if let Some((ch, len)) = fence {
+            if fence_of(trimmed).is_some_and(|(c, l)| {
+                c == ch && l >= len && trimmed.trim_start_matches(ch).is_empty()
+            }) {
+                fence = None;
+            }
+        } else if let Some(title) = line.strip_prefix("## ") {
+            let role = match title.trim() {
+                "You" => "user",
+                "Assistant" => "assistant",
+                "System" => "system",
+                other => {
+                    return Err(Error::Input(format!(
+                        "line {}: unknown section `## {}` (expected `## You`, `## Assistant` or `## System`)",
+                        number, other
+                    )))
+                }
+            };
+            finish_section(&mut messages, heading_line)?;
+            messages.push(RequestMessage {
+                role: role.to_string(),
+                ..Default::default()
+            });
+            heading_line = number;
+            continue;
+        } else if let Some(opening) = fence_of(trimmed) {
+            fence = Some(opening);
+        }
+
+        match messages.last_mut() {
+            Some(message) => {
+                message.content.push_str(line);
+                message.content.push('\n');
+            }
+            None if trimmed.is_empty() => {}
+            None => {
+                return Err(Error::Input(format!(
+                    "line {}: text before the first `## You` or `## Assistant` heading",
+                    number
+                )))
+            }
+        }
+    }
+    finish_section(&mut messages, heading_line)?;
+    if messages.is_empty() {
+        return Err(Error::Input(
+            "the transcript has no `## You` or `## Assistant` sections".to_string(),
+        ));
+    }
+    Ok(messages)
+}
+
+/// Trims the blank lines around the last message's text, which must not be empty.
+fn finish_section(messages: &mut [RequestMessage], heading_line: usize) -> Result<()> {
+    if let Some(message) = messages.last_mut() {
+        let content = message.content.trim_matches(|c| c == '\n' || c == '\r');
+        if content.trim().is_empty() {
+            return Err(Error::Input(format!(
+                "line {}: the section is empty",
+                heading_line
+            )));
+        }
+        message.content = content.to_string();
+    }
+    Ok(())
+}
+
+/// Words highlighted as keywords in code blocks, across the languages answers commonly use.
+const KEYWORDS: &str = "as async await break case catch class const continue def default defer do \
+    elif else enum except export extends false False finally fn for from func function if impl \
+    import in interface let loop match mod mut new nil None null package pass pub raise return \
+    self Self static struct switch this throw trait true True try type use var where while with \
+    yield";
+
+/// Languages whose line comments start with `#` rather than `//`.
+const HASH_COMMENTS: &str = "bash py python r rb ruby sh shell toml yaml yml zsh";
+
+/// Renders Markdown as an HTML fragment.
+///
+/// Covers what chat answers use: ATX headings, paragraphs, bullet and numbered lists, block
+/// quotes, rules, fenced code blocks with their language as a class and basic highlighting, and
+/// inline code, emphasis and links. Anything else, raw HTML included, is kept as escaped text.
+pub fn to_html(text: &str) -> String {
+    let lines: Vec<&str> = text.lines().collect();
+    let mut html = String::new();
+    let mut i = 0;
+    while i < lines.len() {
+        let trimmed = lines[i].trim();
+        if trimmed.is_empty() {
+            i += 1;
+        } else if let Some((ch, len)) = fence_of(trimmed) {
+            let language = trimmed.trim_start_matches(ch).trim();
+            let mut code = String::new();
+            i += 1;
+            while let Some(line) = lines.get(i) {
+                i += 1;
+                let trimmed = line.trim();
+                if fence_of(trimmed).is_some_and(|(c, l)| {
+                    c == ch && l >= len && trimmed.trim_start_matches(ch).is_empty()
+                }) {
+                    break;
+                }
+                code.push_str(line);
+                code.push('\n');
+            }
+            html.push_str(&code_block(&code, language));
+        } else if let Some((level, title)) = heading(trimmed) {
+            html.push_str(&format!("<h{0}>{1}</h{0}>\n", level, inline(title)));
+            i += 1;
+        } else if is_rule(trimmed) {
+            html.push_str("<hr>\n");
+            i += 1;
+        } else if trimmed.starts_with('>') {
+            let mut quoted = String::new();
+            while let Some(line) = lines.get(i).and_then(|line| line.trim().strip_prefix('>')) {
+                quoted.push_str(line.strip_prefix(' ').unwrap_or(line));
+                quoted.push('\n');
+                i += 1;
+            }
+            html.push_str(&format!(
+                "<blockquote>\n{}</blockquote>\n",
+                to_html(&quoted)
+            ));
+        } else if let Some((ordered, _)) = list_item(trimmed) {
+            let mut items: Vec<String> = Vec::new();
+            while let Some(line) = lines.get(i) {
+                let trimmed = line.trim();
+                match list_item(trimmed) {
+                    Some((same, item)) if same == ordered => items.push(item.to_string()),
+                    // An indented line without a marker continues the item above it.
+                    None if !trimmed.is_empty() && line.starts_with([' ', '\t']) => {
+                        if let Some(item) = items.last_mut() {
+                            item.push(' ');
+                            item.push_str(trimmed);
+                        }
+                    }
+                    _ => break,
+                }
+                i += 1;
+            }
+            let tag = if ordered { "ol" } else { "ul" };
+            html.push_str(&format!("<{}>\n", tag));
+            for item in items {
+                html.push_str(&format!("<li>{}</li>\n", inline(&item)));
+            }
+            html.push_str(&format!("</{}>\n", tag));
+        } else {
+            let mut paragraph = Vec::new();
+            while let Some(line) = lines.get(i).map(|line| line.trim()) {
+                if line.is_empty() || (!paragraph.is_empty() && starts_block(line)) {
+                    break;
+                }
+                paragraph.push(inline(line));
+                i += 1;
+            }
+            html.push_str(&format!("<p>{}</p>\n", paragraph.join("\n")));
+        }
+    }
+    html
+}
+
+/// Whether `line` begins something other than a paragraph, ending the paragraph before it.
+fn starts_block(line: &str) -> bool {
+    fence_of(line).is_some()
+        || heading(line).is_some()
+        || is_rule(line)
+        || line.starts_with('>')
+        || list_item(line).is_some()
+}
+
+/// Recognises an ATX heading, returning its level and title.
+fn heading(line: &str) -> Option<(usize, &str)> {
+    let level = line.chars().take_while(|c| *c == '#').count();
+    let title = line[level..]
+        .strip_prefix(' ')
+        .or(line[level..].is_empty().then_some(""))?;
+    (1..=6)
+        .contains(&level)
+        .then(|| (level, title.trim_end_matches('#').trim()))
+}
+
+/// Recognises a thematic break: three or more `-`, `*` or `_`, optionally spaced.
+fn is_rule(line: &str) -> bool {
+    let marks: String = line.chars().filter(|c| *c != ' ').collect();
+    marks.len() >= 3
+        && ['-', '*', '_']
+            .iter()
+            .any(|mark| marks.chars().all(|c| c == *mark))
+}
+
+/// Recognises a list item, returning whether it is numbered and its text.
+fn list_item(line: &str) -> Option<(bool, &str)> {
+    if let Some(item) = ["- ", "* ", "+ "]
+        .iter()
+        .find_map(|marker| line.strip_prefix(marker))
+    {
+        return Some((false, item));
+    }
+    let digits = line.chars().take_while(char::is_ascii_digit).count();
+    let item = line[digits..]
+        .strip_prefix(". ")
+        .or_else(|| line[digits..].strip_prefix(") "))?;
+    (1..=9).contains(&digits).then_some((true, item))
+}
+
+/// Renders the inline markup of one line: code spans, strong and emphasised text, and links.
+fn inline(text: &str) -> String {
+    let mut html = String::new();
+    let mut rest = text;
+    while let Some(ch) = rest.chars().next() {
+        let after = &rest[ch.len_utf8()..];
+        match ch {
+            '\\' => {
+                if let Some(escaped) = after.chars().next().filter(char::is_ascii_punctuation) {
+                    html.push_str(&escape(escaped.encode_utf8(&mut [0; 4])));
+                    rest = &after[1..];
+                    continue;
+                }
+            }
+            '`' => {
+                let ticks = rest.len() - rest.trim_start_matches('`').len();
+                let fence = &rest[..ticks];
+                if let Some(end) = rest[ticks..].find(fence) {
+                    let code = &rest[ticks..ticks + end];
+                    html.push_str(&format!("<code>{}</code>", escape(code.trim())));
+                    rest = &rest[2 * ticks + end..];
+                    continue;
+                }
+            }
+            '*' | '_' => {
+                // `snake_case` names are words, not emphasis.
+                let in_word = text[..text.len() - rest.len()]
+                    .chars()
+                    .next_back()
+                    .is_some_and(char::is_alphanumeric);
+                let marker = if after.starts_with(ch) {
+                    &rest[..2]
+                } else {
+                    &rest[..1]
+                };
+                let body = &rest[marker.len()..];
+                let closing = body
+                    .find(marker)
+                    .filter(|end| *end > 0 && !body.starts_with(char::is_whitespace));
+                if let Some(end) = closing.filter(|_| ch == '*' || !in_word) {
+                    let tag = if marker.len() == 2 { "strong" } else { "em" };
+                    html.push_str(&format!("<{0}>{1}</{0}>", tag, inline(&body[..end])));
+                    rest = &body[end + marker.len()..];
+                    continue;
+                }
+            }
+            '[' => {
+                let link = after.find("](").and_then(|close| {
+                    let target = &after[close + 2..];
+                    target
+                        .find(')')
+                        .map(|end| (&after[..close], &target[..end], &target[end + 1..]))
+                });
+                if let Some((label, url, remainder)) = link {
+                    // Only links that cannot run script in the page keep their target.
+                    let safe = ["http://", "https://", "mailto:"]
+                        .iter()
+                        .any(|scheme| url.starts_with(scheme));
+                    if safe {
+                        html.push_str(&format!(
+                            "<a href=\"{}\">{}</a>",
+                            escape(url),
+                            inline(label)
+                        ));
+                    } else {
+                        html.push_str(&inline(label));
+                    }
+                    rest = remainder;
+                    continue;
+                }
+            }
+            _ => {}
+        }
+        html.push_str(&escape(ch.encode_utf8(&mut [0; 4])));
+        rest = after;
+    }
+    html
+}
+
+/// Renders a fenced code block, highlighting keywords, strings, numbers and comments when the
+/// block names its language.
+fn code_block(code: &str, language: &str) -> String {
+    let language = language.split_whitespace().next().unwrap_or_default();
+    let class = if language.is_empty() {
+        String::new()
+    } else {
+        format!(" class=\"language-{}\"", escape(language))
+    };
+    let body = match language {
+        "" | "text" | "txt" | "plain" | "markdown" | "md" => escape(code),
+        _ => highlight(
+            code,
+            HASH_COMMENTS
+                .split(' ')
+                .any(|name| name.eq_ignore_ascii_case(language)),
+        ),
+    };
+    format!("<pre><code{}>{}</code></pre>\n", class, body)
+}
+
+/// Wraps the tokens of `code` worth colouring in `<span>`s. This is a scanner, not a parser: it
+/// knows about comments, quoted strings, numbers and common keywords and nothing else.
+fn highlight(code: &str, hash_comments: bool) -> String {
+    let mut html = String::new();
+    let mut rest = code;
+    while let Some(ch) = rest.chars().next() {
+        let line_comment = if hash_comments {
+            ch == '#'
+        } else {
+            rest.starts_with("//")
+        };
+        let (class, len) = if line_comment {
+            ("comment", rest.find('\n').unwrap_or(rest.len()))
+        } else if !hash_comments && rest.starts_with("/*") {
+            ("comment", rest.find("*/").map_or(rest.len(), |end| end + 2))
+        } else if matches!(ch, '"' | '\'' | '`') {
+            quoted_len(rest, ch).map_or(("", 1), |len| ("string", len))
+        } else if ch.is_ascii_digit() {
+            let len = rest
+                .find(|c: char| !c.is_ascii_alphanumeric() && c != '.' && c != '_')
+                .unwrap_or(rest.len());
+            ("number", len)
+        } else if ch.is_alphabetic() || ch == '_' {
+            let len = rest
+                .find(|c: char| !c.is_alphanumeric() && c != '_')
+                .unwrap_or(rest.len());
+            let class = if KEYWORDS.split_whitespace().any(|word| word == &rest[..len]) {
+                "keyword"
+            } else {
+                ""
+            };
+            (class, len)
+        } else {
+            ("", ch.len_utf8())
+        };
+        if class.is_empty() {
+            html.push_str(&escape(&rest[..len]));
+        } else {
+            html.push_str(&format!(
+                "<span class=\"{}\">{}</span>",
+                class,
+                escape(&rest[..len])
+            ));
+        }
+        rest = &rest[len..];
+    }
+    html
+}
+
+/// The length of the string literal opened by `quote` at the start of `code`, up to its closing
+/// quote or the end of the line. A `'` that closes nothing on its line, like a Rust lifetime,
+/// opens no string.
+fn quoted_len(code: &str, quote: char) -> Option<usize> {
+    let mut escaped = false;
+    for (index, ch) in code.char_indices().skip(1) {
+        match ch {
+            _ if escaped => escaped = false,
+            '\\' => escaped = true,
+            '\n' if quote != '`' => break,
+            _ if ch == quote => return Some(index + 1),
+            _ => {}
+        }
+    }
+    (quote != '\'').then(|| code.find('\n').unwrap_or(code.len()))
+}
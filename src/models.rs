@@ -0,0 +1,134 @@
+use anyhow::{Context, Result};
+use dialoguer::FuzzySelect;
+use log::warn;
+use mistral_chat_cli_rs::client::ModelInfo;
+use mistral_chat_cli_rs::tr;
+use mistral_chat_cli_rs::ChatClient;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// How long the model list is reused before it is fetched again.
+const CACHE_TTL: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// The models from the last `/v1/models` request.
+#[derive(Serialize, Deserialize)]
+struct Cache {
+    /// Seconds since the Unix epoch when the list was fetched.
+    fetched: u64,
+    models: Vec<CachedModel>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct CachedModel {
+    id: String,
+    max_context_length: Option<u64>,
+}
+
+/// Location of the model list cache in the user's cache directory.
+fn cache_path() -> Result<PathBuf> {
+    let dir = dirs::cache_dir().with_context(|| tr!("cache_dir_unknown"))?;
+    Ok(dir.join("mistral-chat-cli-rs").join("models.json"))
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+/// Saves the IDs and context windows of `models`, returning them sorted by ID.
+pub fn store(models: &[ModelInfo]) -> Result<Vec<CachedModel>> {
+    let path = cache_path()?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let mut models: Vec<CachedModel> = models
+        .iter()
+        .map(|model| CachedModel {
+            id: model.id.clone(),
+            max_context_length: model.max_context_length,
+        })
+        .collect();
+    models.sort_by(|a, b| a.id.cmp(&b.id));
+    let cache = Cache {
+        fetched: now(),
+        models,
+    };
+    fs::write(&path, serde_json::to_vec(&cache)?)?;
+    Ok(cache.models)
+}
+
+/// Reads the model list cache, however old it is.
+fn read_cache() -> Option<Cache> {
+    let bytes = fs::read(cache_path().ok()?).ok()?;
+    serde_json::from_slice(&bytes).ok()
+}
+
+/// Returns whether `id` is a model ID in the cache, without fetching the list.
+pub fn is_cached(id: &str) -> bool {
+    read_cache().is_some_and(|cache| cache.models.iter().any(|model| model.id == id))
+}
+
+/// Returns the cached models, fetching them again when the cache is missing or stale.
+async fn cached(client: &ChatClient) -> Result<Vec<CachedModel>> {
+    let cached =
+        read_cache().filter(|cache| now().saturating_sub(cache.fetched) < CACHE_TTL.as_secs());
+    if let Some(cache) = cached {
+        return Ok(cache.models);
+    }
+    store(&client.list_models().await?)
+}
+
+/// Returns the context window the API reports for `model`, in tokens, if it reports one.
+pub async fn context_length(client: &ChatClient, model: &str) -> Result<Option<u64>> {
+    Ok(cached(client)
+        .await?
+        .into_iter()
+        .find(|cached| cached.id == model)
+        .and_then(|cached| cached.max_context_length))
+}
+
+/// Settles a missing or ambiguous `--model` on a terminal.
+///
+/// Without `query`, every model is offered with `default` preselected. A query that is not a
+/// known ID but matches several is offered among its matches, and one matching a single ID
+/// becomes that ID. Returns `None` to keep the model as it is, including when the list cannot
+/// be loaded or the picker is dismissed.
+pub async fn resolve(
+    client: &ChatClient,
+    query: Option<&str>,
+    default: &str,
+) -> Result<Option<String>> {
+    let ids: Vec<String> = match cached(client).await {
+        Ok(models) => models.into_iter().map(|model| model.id).collect(),
+        Err(e) => {
+            warn!("{}", tr!("model_list_failed", format!("{:#}", e)));
+            return Ok(None);
+        }
+    };
+    let candidates: Vec<&String> = match query {
+        None => ids.iter().collect(),
+        Some(query) if ids.iter().any(|id| id == query) => return Ok(None),
+        Some(query) => {
+            let query = query.to_lowercase();
+            ids.iter()
+                .filter(|id| id.to_lowercase().contains(&query))
+                .collect()
+        }
+    };
+    match candidates.as_slice() {
+        [] => Ok(None),
+        [only] if query.is_some() => Ok(Some(only.to_string())),
+        _ => {
+            let choice = FuzzySelect::new()
+                .with_prompt(tr!("pick_model"))
+                .items(&candidates)
+                .default(candidates.iter().position(|id| *id == default).unwrap_or(0))
+                .interact_opt()?;
+            Ok(choice.map(|index| candidates[index].clone()))
+        }
+    }
+}
@@ -0,0 +1,175 @@
+use anyhow::{bail, Context, Result};
+use crate::time;
+use mistral_chat_cli_rs::tr;
+use serde::{Deserialize, Serialize};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// A prompt previously sent with `chat`.
+#[derive(Serialize, Deserialize)]
+pub struct PromptEntry {
+    /// Seconds since the Unix epoch when the prompt was sent.
+    pub timestamp: u64,
+    pub prompt: String,
+    /// Model the prompt was sent to; absent in entries recorded before models were stored.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+}
+
+/// Which stored prompts `print_history` shows.
+#[derive(Default)]
+pub struct HistoryFilter<'a> {
+    /// Earliest timestamp to show.
+    pub since: Option<i64>,
+    /// Timestamp before which entries are shown (exclusive).
+    pub until: Option<i64>,
+    pub model: Option<&'a str>,
+    /// Text the prompt must contain, ignoring case.
+    pub grep: Option<&'a str>,
+}
+
+/// Location of the prompt history file in the user's data directory.
+fn history_path() -> Result<PathBuf> {
+    let dir = dirs::data_dir().with_context(|| tr!("data_dir_unknown"))?;
+    Ok(dir.join("mistral-chat-cli-rs").join("prompts.jsonl"))
+}
+
+/// Appends a prompt sent to `model` to the history file.
+pub fn record(prompt: &str, model: &str) -> Result<()> {
+    let path = history_path()?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let entry = PromptEntry {
+        timestamp: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or_default(),
+        prompt: prompt.to_string(),
+        model: Some(model.to_string()),
+    };
+    let mut file = OpenOptions::new().create(true).append(true).open(&path)?;
+    writeln!(file, "{}", serde_json::to_string(&entry)?)?;
+    Ok(())
+}
+
+/// Loads all stored prompts, oldest first. IDs are 1-based positions in this list.
+pub fn load() -> Result<Vec<PromptEntry>> {
+    let path = history_path()?;
+    let content = match fs::read_to_string(&path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e).context(tr!("read_failed", path.display())),
+    };
+    content
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| serde_json::from_str(line).with_context(|| tr!("history_corrupt")))
+        .collect()
+}
+
+/// Returns the prompt with the given ID.
+pub fn get(id: usize) -> Result<String> {
+    let entries = load()?;
+    match id
+        .checked_sub(1)
+        .and_then(|index| entries.into_iter().nth(index))
+    {
+        Some(entry) => Ok(entry.prompt),
+        None => bail!(tr!("no_prompt_id", id)),
+    }
+}
+
+/// Scores a fuzzy subsequence match of `query` in `text`; lower is better.
+///
+/// Every query character must appear in order. The score is the length of the matched span,
+/// so tighter matches rank first.
+fn fuzzy_score(query: &str, text: &str) -> Option<usize> {
+    let query: Vec<char> = query.to_lowercase().chars().collect();
+    let text: Vec<char> = text.to_lowercase().chars().collect();
+    if query.is_empty() {
+        return Some(0);
+    }
+
+    let mut best = None;
+    for start in 0..text.len() {
+        if text[start] != query[0] {
+            continue;
+        }
+        let mut matched = 1;
+        let mut end = start;
+        for (i, &ch) in text.iter().enumerate().skip(start + 1) {
+            if matched == query.len() {
+                break;
+            }
+            if ch == query[matched] {
+                matched += 1;
+                end = i;
+            }
+        }
+        if matched == query.len() {
+            let span = end - start + 1;
+            best = Some(best.map_or(span, |b: usize| b.min(span)));
+        }
+    }
+    best
+}
+
+/// Prints stored prompts that fuzzy-match `query`, best matches first.
+pub fn print_search(query: &str) -> Result<()> {
+    let entries = load()?;
+    let mut matches: Vec<(usize, usize, &PromptEntry)> = entries
+        .iter()
+        .enumerate()
+        .filter_map(|(index, entry)| {
+            fuzzy_score(query, &entry.prompt).map(|score| (score, index + 1, entry))
+        })
+        .collect();
+    // Best score first, newest first among equal scores.
+    matches.sort_by(|a, b| a.0.cmp(&b.0).then(b.1.cmp(&a.1)));
+
+    if matches.is_empty() {
+        println!("{}", tr!("no_prompt_matches", query));
+    }
+    for (_, id, entry) in matches {
+        let first_line = entry.prompt.lines().next().unwrap_or_default();
+        println!("{:>5}  {}", id, first_line);
+    }
+    Ok(())
+}
+
+/// Prints the stored prompts that pass `filter`, oldest first, with their IDs, times and models.
+pub fn print_history(filter: &HistoryFilter) -> Result<()> {
+    let entries = load()?;
+    let grep = filter.grep.map(str::to_lowercase);
+    let mut shown = 0;
+    for (index, entry) in entries.iter().enumerate() {
+        let timestamp = i64::try_from(entry.timestamp).unwrap_or(i64::MAX);
+        let matches = filter.since.is_none_or(|since| timestamp >= since)
+            && filter.until.is_none_or(|until| timestamp < until)
+            && filter
+                .model
+                .is_none_or(|model| entry.model.as_deref() == Some(model))
+            && grep
+                .as_ref()
+                .is_none_or(|grep| entry.prompt.to_lowercase().contains(grep));
+        if !matches {
+            continue;
+        }
+        let first_line = entry.prompt.lines().next().unwrap_or_default();
+        println!(
+            "{:>5}  {}  {:<24}  {}",
+            index + 1,
+            time::utc_time(timestamp),
+            entry.model.as_deref().unwrap_or("-"),
+            first_line
+        );
+        shown += 1;
+    }
+    if shown == 0 {
+        println!("{}", tr!("no_history_matches"));
+    }
+    Ok(())
+}
@@ -0,0 +1,197 @@
+//! Masking of credentials in text that may be shared, such as debug logs.
+//!
+//! Secrets the client knows about (API keys, `auth_command` tokens) are registered and masked
+//! wherever they appear; values that merely look like credentials are masked by shape:
+//! the word after `Bearer`, `sk-` style keys and 32-character alphanumeric keys.
+//!
+//! Model output can be masked as well, with [`OutputPatterns`] matched across streamed deltas.
+
+use crate::error::Result;
+use ::config::ConfigError;
+use regex::Regex;
+use std::sync::{Mutex, PoisonError};
+
+/// What a masked value is replaced with.
+pub const MASK: &str = "****";
+/// Registered values shorter than this are not masked, so a placeholder key cannot garble logs.
+const MIN_SECRET_LEN: usize = 8;
+/// Length of a Mistral API key.
+const KEY_LEN: usize = 32;
+/// What `--redact` masks when the config lists no patterns: email addresses and card-like runs
+/// of 13 to 19 digits, which may be grouped with spaces or dashes.
+pub const DEFAULT_OUTPUT_PATTERNS: [&str; 2] = [
+    r"[A-Za-z0-9._%+-]+@[A-Za-z0-9-]+(\.[A-Za-z0-9-]+)*\.[A-Za-z]{2,}",
+    r"\b\d(?:[ -]?\d){12,18}\b",
+];
+/// Characters of streamed output held back while they could still be part of a match that the
+/// next delta completes. A match longer than this may be missed when it is split.
+const HOLD_BACK: usize = 64;
+
+static SECRETS: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+/// Adds `secret` to the values `redact` masks.
+pub fn register(secret: &str) {
+    let secret = secret.trim();
+    if secret.len() < MIN_SECRET_LEN {
+        return;
+    }
+    let mut secrets = SECRETS.lock().unwrap_or_else(PoisonError::into_inner);
+    if !secrets.iter().any(|known| known == secret) {
+        secrets.push(secret.to_string());
+    }
+}
+
+/// Returns `text` with the registered secrets replaced by `MASK`.
+pub fn redact_known(text: &str) -> String {
+    let mut text = text.to_string();
+    for secret in SECRETS
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .iter()
+    {
+        if text.contains(secret.as_str()) {
+            text = text.replace(secret.as_str(), MASK);
+        }
+    }
+    text
+}
+
+/// Returns `text` with registered secrets and credential-shaped values replaced by `MASK`.
+///
+/// Shapes can match harmless text such as an MD5 hash, so this suits diagnostics rather than
+/// output the user asked for.
+pub fn redact(text: &str) -> String {
+    let text = redact_known(text);
+
+    let mut redacted = String::with_capacity(text.len());
+    let mut after_bearer = false;
+    let mut rest = text.as_str();
+    while let Some(start) = rest.find(is_word_char) {
+        redacted.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let end = rest.find(|c: char| !is_word_char(c)).unwrap_or(rest.len());
+        let (word, tail) = rest.split_at(end);
+        // A key followed by a full stop still counts, so look at the dot-separated parts.
+        let looks_like_key = word.split('.').any(|part| {
+            (part.len() == KEY_LEN && part.bytes().all(|b| b.is_ascii_alphanumeric()))
+                || (part.starts_with("sk-") && part.len() >= 20)
+        });
+        if word != MASK && (after_bearer || looks_like_key) {
+            redacted.push_str(MASK);
+        } else {
+            redacted.push_str(word);
+        }
+        after_bearer = word.eq_ignore_ascii_case("bearer") && tail.starts_with(' ');
+        rest = tail;
+    }
+    redacted.push_str(rest);
+    redacted
+}
+
+/// Characters of a token: API keys, JWTs and their base64url segments.
+fn is_word_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.' | '*')
+}
+
+/// Regular expressions whose matches are masked in model output.
+#[derive(Debug, Clone)]
+pub struct OutputPatterns(Vec<Regex>);
+
+impl OutputPatterns {
+    /// Compiles `patterns`, rejecting the first one that is not a valid regular expression.
+    pub fn new<S: AsRef<str>>(patterns: &[S]) -> Result<Self> {
+        patterns
+            .iter()
+            .map(|pattern| {
+                Regex::new(pattern.as_ref()).map_err(|e| {
+                    ConfigError::Message(format!(
+                        "invalid redact pattern {:?}: {}",
+                        pattern.as_ref(),
+                        e
+                    ))
+                    .into()
+                })
+            })
+            .collect::<Result<_>>()
+            .map(OutputPatterns)
+    }
+
+    /// Starts masking one stream of deltas.
+    pub fn redactor(&self) -> Redactor<'_> {
+        Redactor {
+            patterns: &self.0,
+            held: String::new(),
+            count: 0,
+        }
+    }
+}
+
+/// Masks matches of [`OutputPatterns`] in text that arrives in pieces.
+///
+/// The last `HOLD_BACK` characters are held until more text arrives or the stream ends, and so
+/// is any match reaching into them, so a pattern split across deltas is still masked.
+pub struct Redactor<'a> {
+    patterns: &'a [Regex],
+    held: String,
+    count: usize,
+}
+
+impl Redactor<'_> {
+    /// Adds a delta, returning the masked text that later deltas can no longer change.
+    pub fn push(&mut self, text: &str) -> String {
+        self.held.push_str(text);
+        let cut = self
+            .held
+            .char_indices()
+            .rev()
+            .nth(HOLD_BACK - 1)
+            .map_or(0, |(index, _)| index);
+        self.release(cut)
+    }
+
+    /// Returns the rest of the text, masked, once the stream has ended.
+    pub fn finish(&mut self) -> String {
+        self.release(self.held.len())
+    }
+
+    /// How many matches have been masked so far.
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    /// Masks and returns the held text before `cut`, moving `cut` back to the start of any match
+    /// that continues past it.
+    fn release(&mut self, mut cut: usize) -> String {
+        let mut matches: Vec<(usize, usize)> = self
+            .patterns
+            .iter()
+            .flat_map(|pattern| pattern.find_iter(&self.held))
+            .filter(|found| !found.is_empty())
+            .map(|found| (found.start(), found.end()))
+            .collect();
+        matches.sort_unstable();
+        // Overlapping matches of different patterns are masked as one.
+        let mut spans: Vec<(usize, usize)> = Vec::new();
+        for (start, end) in matches {
+            match spans.last_mut() {
+                Some(last) if start < last.1 => last.1 = last.1.max(end),
+                _ => spans.push((start, end)),
+            }
+        }
+        if let Some(&(start, _)) = spans.iter().find(|(_, end)| *end > cut) {
+            cut = cut.min(start);
+        }
+
+        let mut released = String::with_capacity(cut);
+        let mut position = 0;
+        for (start, end) in spans.into_iter().take_while(|(_, end)| *end <= cut) {
+            released.push_str(&self.held[position..start]);
+            released.push_str(MASK);
+            position = end;
+            self.count += 1;
+        }
+        released.push_str(&self.held[position..cut]);
+        self.held.drain(..cut);
+        released
+    }
+}
@@ -0,0 +1,162 @@
+use anyhow::{bail, Context, Result};
+use log::{debug, info, warn};
+use mistral_chat_cli_rs::client::{ChatParams, CODESTRAL_MODEL, MISTRAL_MODEL};
+use mistral_chat_cli_rs::stream::{StreamEvent, Usage};
+use mistral_chat_cli_rs::tr;
+use mistral_chat_cli_rs::{ChatClient, RequestMessage};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::os::unix::fs::PermissionsExt;
+use std::path::Path;
+use std::sync::Arc;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{UnixListener, UnixStream};
+use tokio::signal::unix::{signal, SignalKind};
+
+/// One line of the protocol: a prompt, a full messages array, or both.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Request {
+    prompt: Option<String>,
+    #[serde(default)]
+    messages: Vec<RequestMessage>,
+    model: Option<String>,
+    system: Option<String>,
+    reasoning_effort: Option<String>,
+}
+
+/// Listens on `path` until SIGTERM or Ctrl-C, answering each request line with a stream of
+/// JSON lines. Connections are served concurrently over the one shared client.
+pub async fn run(client: ChatClient, path: &Path) -> Result<()> {
+    if path.exists() {
+        // A socket left behind by a crashed server refuses connections and can be replaced.
+        if UnixStream::connect(path).await.is_ok() {
+            bail!(tr!("socket_in_use", path.display()));
+        }
+        std::fs::remove_file(path).with_context(|| tr!("socket_remove_failed", path.display()))?;
+    }
+    let listener =
+        UnixListener::bind(path).with_context(|| tr!("socket_listen_failed", path.display()))?;
+    // The socket spends tokens on the user's keys, so only the user may connect.
+    std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o600))?;
+    info!("{}", tr!("socket_listening", path.display()));
+
+    let client = Arc::new(client);
+    let mut terminate = signal(SignalKind::terminate())?;
+    loop {
+        tokio::select! {
+            accepted = listener.accept() => {
+                let (stream, _) = accepted?;
+                let client = Arc::clone(&client);
+                tokio::spawn(async move {
+                    if let Err(e) = serve_connection(&client, stream).await {
+                        debug!("Connection closed: {:#}", e);
+                    }
+                });
+            }
+            _ = terminate.recv() => break,
+            _ = tokio::signal::ctrl_c() => break,
+        }
+    }
+    std::fs::remove_file(path).with_context(|| tr!("socket_remove_failed", path.display()))?;
+    Ok(())
+}
+
+/// Answers requests on one connection in order until the client hangs up.
+async fn serve_connection(client: &ChatClient, stream: UnixStream) -> Result<()> {
+    let (reader, mut writer) = stream.into_split();
+    let mut lines = BufReader::new(reader).lines();
+    while let Some(line) = lines.next_line().await? {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let result = match serde_json::from_str::<Request>(&line) {
+            Ok(request) => answer(client, request, &mut writer).await,
+            Err(e) => Err(anyhow::anyhow!(tr!("serve_invalid_request", e))),
+        };
+        if let Err(e) = result {
+            warn!("{}", tr!("serve_request_failed", format!("{:#}", e)));
+            write_line(&mut writer, &json!({ "error": format!("{:#}", e) })).await?;
+        }
+    }
+    Ok(())
+}
+
+/// Streams the answer to `request` as `content` (and `refusal`) lines followed by a `done` line.
+async fn answer(
+    client: &ChatClient,
+    request: Request,
+    writer: &mut (impl AsyncWriteExt + Unpin),
+) -> Result<()> {
+    let mut messages = request.messages;
+    if let Some(system) = request.system {
+        messages.insert(
+            0,
+            RequestMessage {
+                role: "system".to_string(),
+                content: system,
+                ..Default::default()
+            },
+        );
+    }
+    if let Some(prompt) = request.prompt {
+        messages.push(RequestMessage {
+            role: "user".to_string(),
+            content: prompt,
+            ..Default::default()
+        });
+    }
+    if !messages.iter().any(|message| message.role != "system") {
+        bail!("a request needs a prompt or messages");
+    }
+    // Same choice as `chat` without `--model`.
+    let model = request.model.unwrap_or_else(|| {
+        let asks_for_code = messages
+            .iter()
+            .rev()
+            .find(|message| message.role == "user")
+            .is_some_and(|message| message.content.to_lowercase().contains("code"));
+        let model = if asks_for_code {
+            CODESTRAL_MODEL
+        } else {
+            MISTRAL_MODEL
+        };
+        model.to_string()
+    });
+    let params = ChatParams {
+        reasoning_effort: request.reasoning_effort,
+        ..Default::default()
+    };
+
+    let mut stream = client.chat_events(&model, messages, &params).await?;
+    let mut finish_reason = None;
+    let mut usage: Option<Usage> = None;
+    while let Some(event) = stream.next().await {
+        match event? {
+            // The first delta usually carries only the role, with empty content.
+            StreamEvent::Content(content) if content.is_empty() => {}
+            StreamEvent::Content(content) => {
+                write_line(writer, &json!({ "content": content, "index": 0 })).await?;
+            }
+            StreamEvent::Refusal(refusal) => {
+                write_line(writer, &json!({ "refusal": refusal })).await?;
+            }
+            StreamEvent::Citation(citation) => {
+                write_line(writer, &json!({ "citation": citation })).await?;
+            }
+            StreamEvent::Finish(reason) => finish_reason = Some(reason),
+            StreamEvent::Usage(u) => usage = Some(u),
+            StreamEvent::Reasoning(_) => {}
+        }
+    }
+    let done = json!({ "done": true, "finish_reason": finish_reason, "usage": usage });
+    write_line(writer, &done).await
+}
+
+async fn write_line(writer: &mut (impl AsyncWriteExt + Unpin), value: &Value) -> Result<()> {
+    let mut line = value.to_string();
+    line.push('\n');
+    writer.write_all(line.as_bytes()).await?;
+    writer.flush().await?;
+    Ok(())
+}
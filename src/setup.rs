@@ -0,0 +1,58 @@
+use crate::{read_passphrase, write_private};
+use anyhow::{bail, Context, Result};
+use dialoguer::{Confirm, Input};
+use mistral_chat_cli_rs::tr;
+use mistral_chat_cli_rs::Config;
+use std::io::IsTerminal;
+use std::path::Path;
+
+/// Makes sure a configuration file exists at `path` before a command reads it.
+///
+/// On a terminal, a missing file is offered to be created from the user's keys and a default
+/// model; elsewhere, or when the offer is declined, the command fails with how to create one.
+/// Paths the config loader would not read as TOML are left to the loader's own error.
+pub fn ensure_config(path: &str) -> Result<()> {
+    if Path::new(path).exists() || !path.ends_with(".toml") {
+        return Ok(());
+    }
+    let interactive = std::io::stdin().is_terminal() && std::io::stderr().is_terminal();
+    let accepted = interactive
+        && Confirm::new()
+            .with_prompt(tr!("setup_offer", path))
+            .default(true)
+            .interact()?;
+    if !accepted {
+        bail!(tr!("config_missing", path));
+    }
+
+    let mistral_api_key = read_key(&tr!("setup_mistral_key"))?;
+    let codestral_api_key = read_passphrase(&tr!("setup_codestral_key"))?;
+    let model: String = Input::new()
+        .with_prompt(tr!("setup_model"))
+        .allow_empty(true)
+        .interact_text()?;
+
+    let config = Config {
+        mistral_api_key,
+        codestral_api_key: codestral_api_key.trim().to_string(),
+        debug: false,
+        model: Some(model.trim().to_string()).filter(|model| !model.is_empty()),
+        ..Default::default()
+    };
+    let content = toml::to_string(&config)?;
+    write_private(Path::new(path), content.as_bytes())
+        .with_context(|| tr!("write_failed", path))?;
+    eprintln!("{}", tr!("setup_written", path));
+    Ok(())
+}
+
+/// Asks for a key until a non-empty one is entered.
+fn read_key(prompt: &str) -> Result<String> {
+    loop {
+        let key = read_passphrase(prompt)?.trim().to_string();
+        if !key.is_empty() {
+            return Ok(key);
+        }
+        eprintln!("{}", tr!("setup_key_required"));
+    }
+}
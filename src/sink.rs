@@ -0,0 +1,291 @@
+use crate::error::{Error, Result};
+use std::fs::{File, OpenOptions};
+use std::io::{IsTerminal, Write};
+use std::process::{Command, ExitStatus, Stdio};
+use std::sync::Mutex;
+
+/// A destination for streamed response content besides stdout.
+///
+/// `write` receives each content delta as it arrives; `finish` is called once the whole response,
+/// including any continuations, is complete.
+pub trait Sink: Send + Sync {
+    fn write(&self, content: &str) -> Result<()>;
+
+    fn finish(&self) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// Parses a `--sink` value: `file:PATH`, `clipboard` or `command:CMD`.
+///
+/// `stdout` is not a `Sink`, since it is written by `ChatClient::chat_stream` itself.
+pub fn from_spec(spec: &str) -> Result<Box<dyn Sink>> {
+    if let Some(path) = spec.strip_prefix("file:") {
+        return Ok(Box::new(FileSink::append(path)?));
+    }
+    if let Some(command) = spec.strip_prefix("command:") {
+        return Ok(Box::new(CommandSink::new(command)));
+    }
+    match spec {
+        "clipboard" => Ok(Box::new(ClipboardSink::default())),
+        _ => Err(Error::Input(format!(
+            "unknown sink {:?} (expected stdout, file:PATH, clipboard or command:CMD)",
+            spec
+        ))),
+    }
+}
+
+/// Appends content to a file as it arrives, so the file can be followed while streaming.
+pub struct FileSink {
+    /// The file, and the last byte written to it, if any.
+    file: Mutex<(File, Option<u8>)>,
+}
+
+impl FileSink {
+    /// Opens `path` for appending, creating it if needed.
+    pub fn append(path: &str) -> Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        Ok(FileSink {
+            file: Mutex::new((file, None)),
+        })
+    }
+}
+
+impl Sink for FileSink {
+    fn write(&self, content: &str) -> Result<()> {
+        let mut guard = self.file.lock().expect("file sink lock poisoned");
+        let (file, last) = &mut *guard;
+        file.write_all(content.as_bytes())?;
+        if let Some(&byte) = content.as_bytes().last() {
+            *last = Some(byte);
+        }
+        Ok(())
+    }
+
+    /// Ends the response with a newline, unless it already ends with one or nothing was written.
+    fn finish(&self) -> Result<()> {
+        let mut guard = self.file.lock().expect("file sink lock poisoned");
+        let (file, last) = &mut *guard;
+        if last.is_some_and(|byte| byte != b'\n') {
+            file.write_all(b"\n")?;
+            *last = Some(b'\n');
+        }
+        Ok(())
+    }
+}
+
+/// Copies the complete response to the system clipboard.
+#[derive(Default)]
+pub struct ClipboardSink {
+    content: Mutex<String>,
+}
+
+/// Clipboard tools tried in order until one succeeds.
+const CLIPBOARD_COMMANDS: [&[&str]; 5] = [
+    &["pbcopy"],
+    &["wl-copy"],
+    &["xclip", "-selection", "clipboard"],
+    &["xsel", "--clipboard", "--input"],
+    &["clip"],
+];
+
+impl Sink for ClipboardSink {
+    fn write(&self, content: &str) -> Result<()> {
+        self.content
+            .lock()
+            .expect("clipboard sink lock poisoned")
+            .push_str(content);
+        Ok(())
+    }
+
+    fn finish(&self) -> Result<()> {
+        let content = self.content.lock().expect("clipboard sink lock poisoned");
+        for tool in CLIPBOARD_COMMANDS {
+            let mut command = Command::new(tool[0]);
+            command.args(&tool[1..]);
+            if pipe_to(command, &content).is_ok_and(|status| status.success()) {
+                return Ok(());
+            }
+        }
+        Err(Error::Input(
+            "could not copy to the clipboard (tried pbcopy, wl-copy, xclip, xsel and clip)"
+                .to_string(),
+        ))
+    }
+}
+
+/// Runs a shell command once the response is complete, with the response on its stdin,
+/// e.g. to send a desktop notification.
+pub struct CommandSink {
+    command: String,
+    content: Mutex<String>,
+}
+
+impl CommandSink {
+    pub fn new(command: &str) -> Self {
+        CommandSink {
+            command: command.to_string(),
+            content: Mutex::new(String::new()),
+        }
+    }
+}
+
+impl Sink for CommandSink {
+    fn write(&self, content: &str) -> Result<()> {
+        self.content
+            .lock()
+            .expect("command sink lock poisoned")
+            .push_str(content);
+        Ok(())
+    }
+
+    fn finish(&self) -> Result<()> {
+        let content = self.content.lock().expect("command sink lock poisoned");
+        let status = pipe_to(shell(&self.command), &content)?;
+        if !status.success() {
+            return Err(Error::Input(format!(
+                "sink command {:?} failed with {}",
+                self.command, status
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// Pager used when `$PAGER` is unset or empty.
+const DEFAULT_PAGER: &str = "less -R";
+
+/// Shows the complete response in `$PAGER` when it is taller than the terminal, and prints it
+/// as is when it fits, the way `git` pages its output.
+#[derive(Default)]
+pub struct PagerSink {
+    content: Mutex<String>,
+}
+
+impl Sink for PagerSink {
+    fn write(&self, content: &str) -> Result<()> {
+        self.content
+            .lock()
+            .expect("pager sink lock poisoned")
+            .push_str(content);
+        Ok(())
+    }
+
+    fn finish(&self) -> Result<()> {
+        let mut content = self.content.lock().expect("pager sink lock poisoned");
+        if !content.ends_with('\n') {
+            content.push('\n');
+        }
+        let fits = terminal_size::terminal_size().is_some_and(|(width, height)| {
+            let width = usize::from(width.0.max(1));
+            let rows: usize = content
+                .lines()
+                .map(|line| line.chars().count().div_ceil(width).max(1))
+                .sum();
+            rows < usize::from(height.0)
+        });
+        if fits {
+            let mut stdout = std::io::stdout().lock();
+            stdout.write_all(content.as_bytes())?;
+            stdout.flush()?;
+            return Ok(());
+        }
+        let pager = std::env::var("PAGER").unwrap_or_default();
+        let pager = if pager.trim().is_empty() {
+            DEFAULT_PAGER
+        } else {
+            pager.as_str()
+        };
+        let status = pipe_to(shell(pager), &content)?;
+        if !status.success() {
+            return Err(Error::Input(format!(
+                "pager {:?} failed with {}",
+                pager, status
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// Prints the complete response on a single line: each line trimmed, blank ones dropped, and the
+/// rest joined with spaces. The line only ends with a newline on a terminal, so
+/// `$(...)` captures exactly the answer.
+#[derive(Default)]
+pub struct OnelineSink {
+    content: Mutex<String>,
+}
+
+impl Sink for OnelineSink {
+    fn write(&self, content: &str) -> Result<()> {
+        self.content
+            .lock()
+            .expect("oneline sink lock poisoned")
+            .push_str(content);
+        Ok(())
+    }
+
+    fn finish(&self) -> Result<()> {
+        let content = self.content.lock().expect("oneline sink lock poisoned");
+        let mut line = content
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+            .collect::<Vec<_>>()
+            .join(" ");
+        let mut stdout = std::io::stdout().lock();
+        if stdout.is_terminal() {
+            line.push('\n');
+        }
+        stdout.write_all(line.as_bytes())?;
+        stdout.flush()?;
+        Ok(())
+    }
+}
+
+/// Builds a command that runs `command` through the platform shell.
+pub(crate) fn shell(command: &str) -> Command {
+    let mut process = if cfg!(windows) {
+        let mut process = Command::new("cmd");
+        process.arg("/C");
+        process
+    } else {
+        let mut process = Command::new("sh");
+        process.arg("-c");
+        process
+    };
+    process.arg(command);
+    process
+}
+
+/// Runs `command` with `input` on its stdin and waits for it to exit.
+fn pipe_to(mut command: Command, input: &str) -> Result<ExitStatus> {
+    let mut child = command.stdin(Stdio::piped()).spawn()?;
+    if let Some(mut stdin) = child.stdin.take() {
+        // A command that ignores its input may exit before reading it; that is not an error.
+        let _ = stdin.write_all(input.as_bytes());
+    }
+    Ok(child.wait()?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn file_sink_adds_a_newline_only_when_missing() {
+        let path = std::env::temp_dir().join(format!(
+            "mistral-chat-cli-rs-file-sink-{}.txt",
+            std::process::id()
+        ));
+        let path_str = path.to_str().unwrap();
+        let _ = std::fs::remove_file(&path);
+        for response in ["no newline", "ends a line\n", ""] {
+            let sink = FileSink::append(path_str).unwrap();
+            sink.write(response).unwrap();
+            sink.finish().unwrap();
+        }
+        let written = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(written, "no newline\nends a line\n");
+    }
+}
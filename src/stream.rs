@@ -0,0 +1,522 @@
+use crate::error::Result;
+use bytes::Bytes;
+use futures_util::stream::BoxStream;
+use futures_util::StreamExt;
+use log::{debug, warn};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use tokio_util::sync::CancellationToken;
+
+/// Token counts reported by the API for a completed request.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize, Serialize)]
+pub struct Usage {
+    pub prompt_tokens: u32,
+    pub completion_tokens: u32,
+    pub total_tokens: u32,
+}
+
+impl std::ops::AddAssign for Usage {
+    fn add_assign(&mut self, other: Usage) {
+        self.prompt_tokens += other.prompt_tokens;
+        self.completion_tokens += other.completion_tokens;
+        self.total_tokens += other.total_tokens;
+    }
+}
+
+/// A source cited in the answer, from a `reference` or `tool_reference` content part.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Citation {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    /// The index of a document given to the model, for a `reference` part naming one.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reference_id: Option<u64>,
+}
+
+impl std::fmt::Display for Citation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match (&self.title, &self.url, self.reference_id) {
+            (Some(title), Some(url), _) => write!(f, "{} <{}>", title, url),
+            (Some(title), None, _) => write!(f, "{}", title),
+            (None, Some(url), _) => write!(f, "<{}>", url),
+            (None, None, Some(id)) => write!(f, "document {}", id),
+            (None, None, None) => write!(f, "unnamed source"),
+        }
+    }
+}
+
+/// Where a backend puts the text of each stream event, such as `choices[0].delta.content`: object
+/// keys separated by dots, each optionally followed by `[N]` array indices.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct JsonPath(Vec<PathStep>);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum PathStep {
+    Key(String),
+    Index(usize),
+}
+
+impl JsonPath {
+    /// Parses a path, describing what is wrong with it if it cannot be followed.
+    pub fn parse(path: &str) -> std::result::Result<Self, String> {
+        let mut steps = Vec::new();
+        for segment in path.split('.') {
+            let (key, mut indices) = segment.split_at(segment.find('[').unwrap_or(segment.len()));
+            // Only a path that starts with an index, like `[0].text`, begins without a key.
+            let leading_index = steps.is_empty() && !indices.is_empty();
+            if key.is_empty() && !leading_index {
+                return Err(format!("empty key in {:?}", path));
+            }
+            if !key.is_empty() {
+                steps.push(PathStep::Key(key.to_string()));
+            }
+            while !indices.is_empty() {
+                let index = indices
+                    .strip_prefix('[')
+                    .and_then(|rest| rest.split_once(']'))
+                    .and_then(|(index, rest)| Some((index.parse().ok()?, rest)));
+                let Some((index, rest)) = index else {
+                    return Err(format!("expected [N] after {:?} in {:?}", key, path));
+                };
+                steps.push(PathStep::Index(index));
+                indices = rest;
+            }
+        }
+        Ok(JsonPath(steps))
+    }
+
+    /// The value at this path in `json`, if every step along it exists.
+    pub fn get<'a>(&self, json: &'a serde_json::Value) -> Option<&'a serde_json::Value> {
+        self.0.iter().try_fold(json, |value, step| match step {
+            PathStep::Key(key) => value.get(key),
+            PathStep::Index(index) => value.get(index),
+        })
+    }
+}
+
+/// An incremental event parsed from a streaming chat response.
+#[derive(Debug, Clone, PartialEq)]
+pub enum StreamEvent {
+    /// A fragment of assistant text.
+    Content(String),
+    /// A fragment of the model's explanation for refusing the request.
+    Refusal(String),
+    /// A fragment of a reasoning model's thinking, separate from the answer.
+    Reasoning(String),
+    /// The reason generation stopped, e.g. `stop` or `length`.
+    Finish(String),
+    /// Token usage, usually sent with the final event.
+    Usage(Usage),
+    /// A source the text so far cites, at the point in the text where it is cited.
+    Citation(Citation),
+}
+
+/// A streaming chat response, yielding events as server-sent events arrive.
+///
+/// Events may be split across network chunks, so lines are buffered until complete.
+pub struct ChatStream {
+    bytes: BoxStream<'static, reqwest::Result<Bytes>>,
+    buffer: Vec<u8>,
+    /// JSON from `data:` lines that ended before the value was complete.
+    partial: String,
+    pending: VecDeque<StreamEvent>,
+    done: bool,
+    /// Whether the server sent `[DONE]`, as opposed to just closing the connection.
+    saw_done: bool,
+    debug: bool,
+    cancel: Option<CancellationToken>,
+    /// The ID the server assigned to the request, from the response headers.
+    request_id: Option<String>,
+    /// Where to find the text in each event instead of `choices[0].delta.content`.
+    content_path: Option<JsonPath>,
+}
+
+impl ChatStream {
+    pub(crate) fn new(response: reqwest::Response, debug: bool) -> Self {
+        let request_id = crate::client::request_id(&response);
+        ChatStream {
+            bytes: response.bytes_stream().boxed(),
+            buffer: Vec::new(),
+            partial: String::new(),
+            pending: VecDeque::new(),
+            done: false,
+            saw_done: false,
+            debug,
+            cancel: None,
+            request_id,
+            content_path: None,
+        }
+    }
+
+    /// Reads the text of each event from `path`, for backends that do not send it in the
+    /// standard place. Reasoning, finish reasons and usage are still read from the standard shape.
+    pub(crate) fn with_content_path(mut self, path: Option<JsonPath>) -> Self {
+        self.content_path = path;
+        self
+    }
+
+    /// Ends the stream as soon as `token` is cancelled, even while waiting for the server.
+    ///
+    /// The HTTP response is dropped on cancellation, closing the connection, and no further
+    /// events are returned.
+    pub fn with_cancellation(mut self, token: CancellationToken) -> Self {
+        self.cancel = Some(token);
+        self
+    }
+
+    /// Whether the server ended the stream with `[DONE]`. A stream that simply stops may have
+    /// been cut off by a server-side error, so its response can be incomplete.
+    pub fn saw_done(&self) -> bool {
+        self.saw_done
+    }
+
+    /// The server's ID for the request, if it sent one.
+    pub fn request_id(&self) -> Option<&str> {
+        self.request_id.as_deref()
+    }
+
+    /// Drops the response and any buffered events.
+    fn abort(&mut self) {
+        if self.debug {
+            debug!("Stream cancelled");
+        }
+        self.bytes = futures_util::stream::empty().boxed();
+        self.buffer.clear();
+        self.partial.clear();
+        self.pending.clear();
+        self.done = true;
+    }
+
+    /// Returns the next event, or `None` once the stream has ended.
+    pub async fn next(&mut self) -> Option<Result<StreamEvent>> {
+        loop {
+            if self
+                .cancel
+                .as_ref()
+                .is_some_and(|token| token.is_cancelled())
+            {
+                self.abort();
+                return None;
+            }
+            if let Some(event) = self.pending.pop_front() {
+                return Some(Ok(event));
+            }
+            if self.done {
+                return None;
+            }
+
+            let chunk = match self.cancel.clone() {
+                Some(token) => tokio::select! {
+                    _ = token.cancelled() => continue,
+                    chunk = self.bytes.next() => chunk,
+                },
+                None => self.bytes.next().await,
+            };
+            match chunk {
+                Some(Ok(bytes)) => {
+                    if self.debug {
+                        debug!("Received chunk: {}", String::from_utf8_lossy(&bytes));
+                    }
+                    self.buffer.extend_from_slice(&bytes);
+                    while let Some(end) = self.buffer.iter().position(|&b| b == b'\n') {
+                        let line: Vec<u8> = self.buffer.drain(..=end).collect();
+                        self.parse_line(&String::from_utf8_lossy(&line));
+                    }
+                }
+                Some(Err(e)) => return Some(Err(e.into())),
+                None => {
+                    // Parse whatever is left in case the final line had no newline.
+                    let rest = std::mem::take(&mut self.buffer);
+                    self.parse_line(&String::from_utf8_lossy(&rest));
+                    self.drop_partial("the stream ended");
+                    self.done = true;
+                }
+            }
+        }
+    }
+
+    fn parse_line(&mut self, line: &str) {
+        let line = line.trim_end();
+        if line.is_empty() {
+            // A blank line ends an event, so JSON still pending will never be completed.
+            self.drop_partial("the event ended");
+            return;
+        }
+        let Some(data) = line.strip_prefix("data:") else {
+            return;
+        };
+        let data = data.strip_prefix(' ').unwrap_or(data);
+        if data == "[DONE]" {
+            if self.debug {
+                debug!("Received [DONE]");
+            }
+            self.drop_partial("the stream ended");
+            self.done = true;
+            self.saw_done = true;
+            return;
+        }
+
+        // Some backends split one JSON value over several `data:` lines.
+        let data = if self.partial.is_empty() {
+            data.to_string()
+        } else {
+            format!("{}\n{}", std::mem::take(&mut self.partial), data)
+        };
+        match serde_json::from_str::<serde_json::Value>(&data) {
+            Ok(json) => self.parse_event(&json),
+            Err(e) if e.is_eof() => self.partial = data,
+            Err(e) => {
+                if self.debug {
+                    warn!("Skipping malformed stream event ({}): {}", e, data);
+                }
+            }
+        }
+    }
+
+    /// Warns about and discards JSON that was never completed.
+    fn drop_partial(&mut self, reason: &str) {
+        let partial = std::mem::take(&mut self.partial);
+        if self.debug && !partial.is_empty() {
+            warn!("Skipping incomplete stream event, {}: {}", reason, partial);
+        }
+    }
+
+    /// Queues the events in one parsed chunk; chunks without content are normal, e.g. keep-alives.
+    fn parse_event(&mut self, json: &serde_json::Value) {
+        let before = self.pending.len();
+        if let Some(path) = &self.content_path {
+            if let Some(content) = path.get(json).and_then(serde_json::Value::as_str) {
+                self.pending
+                    .push_back(StreamEvent::Content(content.to_string()));
+            }
+        }
+        let choices = match json.get("choices") {
+            Some(serde_json::Value::Array(choices)) => choices.as_slice(),
+            None | Some(serde_json::Value::Null) if json.is_object() => &[],
+            _ => {
+                if self.debug && self.pending.len() == before {
+                    warn!("Skipping stream event with an unexpected shape: {}", json);
+                }
+                return;
+            }
+        };
+        if let Some(choice) = choices.first() {
+            let delta = &choice["delta"];
+            if let Some(reasoning) = delta["reasoning_content"].as_str() {
+                self.pending
+                    .push_back(StreamEvent::Reasoning(reasoning.to_string()));
+            }
+            if self.content_path.is_some() {
+                // The text was taken from the configured path above.
+            } else if let Some(content) = delta["content"].as_str() {
+                self.pending
+                    .push_back(StreamEvent::Content(content.to_string()));
+            } else if let Some(parts) = delta["content"].as_array() {
+                // Reasoning models send content as typed parts, `thinking` and `text`, and answers
+                // grounded in web search or documents add `tool_reference` and `reference` parts.
+                for part in parts {
+                    match part["type"].as_str() {
+                        Some("text") => {
+                            let text = part["text"].as_str().unwrap_or_default();
+                            self.pending
+                                .push_back(StreamEvent::Content(text.to_string()));
+                        }
+                        Some("thinking") => {
+                            let thinking = part["thinking"].as_array().into_iter().flatten();
+                            let text: String = thinking
+                                .filter_map(|chunk| chunk["text"].as_str())
+                                .collect();
+                            self.pending.push_back(StreamEvent::Reasoning(text));
+                        }
+                        Some("tool_reference") => {
+                            let field = |name: &str| part[name].as_str().map(str::to_string);
+                            self.pending.push_back(StreamEvent::Citation(Citation {
+                                title: field("title").or_else(|| field("tool")),
+                                url: field("url"),
+                                reference_id: None,
+                            }));
+                        }
+                        Some("reference") => {
+                            let ids = part["reference_ids"].as_array().into_iter().flatten();
+                            for id in ids.filter_map(serde_json::Value::as_u64) {
+                                self.pending.push_back(StreamEvent::Citation(Citation {
+                                    title: None,
+                                    url: None,
+                                    reference_id: Some(id),
+                                }));
+                            }
+                        }
+                        _ => {}
+                    }
+                }
+            }
+            // A refusal may come alongside content, such as an empty string, so it is read apart.
+            if let Some(refusal) = delta["refusal"].as_str() {
+                self.pending
+                    .push_back(StreamEvent::Refusal(refusal.to_string()));
+            }
+            if let Some(reason) = choice["finish_reason"].as_str() {
+                self.pending
+                    .push_back(StreamEvent::Finish(reason.to_string()));
+            }
+        }
+        if let Ok(usage) = Usage::deserialize(&json["usage"]) {
+            self.pending.push_back(StreamEvent::Usage(usage));
+        }
+        if self.debug && self.pending.len() == before {
+            debug!("No content in JSON: {}", json);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A stream whose response body arrives in the given network chunks.
+    fn stream_of(chunks: &[&str]) -> ChatStream {
+        let chunks: Vec<std::io::Result<Bytes>> = chunks
+            .iter()
+            .map(|chunk| Ok(Bytes::from(chunk.to_string())))
+            .collect();
+        let body = reqwest::Body::wrap_stream(futures_util::stream::iter(chunks));
+        let response = http::Response::builder().status(200).body(body).unwrap();
+        ChatStream::new(response.into(), false)
+    }
+
+    async fn events(stream: &mut ChatStream) -> Vec<StreamEvent> {
+        let mut events = Vec::new();
+        while let Some(event) = stream.next().await {
+            events.push(event.unwrap());
+        }
+        events
+    }
+
+    fn content(text: &str) -> StreamEvent {
+        StreamEvent::Content(text.to_string())
+    }
+
+    #[tokio::test]
+    async fn role_only_delta_yields_no_event() {
+        let mut stream = stream_of(&[
+            "data: {\"choices\":[{\"index\":0,\"delta\":{\"role\":\"assistant\"}}]}\n\n",
+            "data: {\"choices\":[{\"index\":0,\"delta\":{\"content\":\"Hi\"}}]}\n\n",
+            "data: [DONE]\n\n",
+        ]);
+        assert_eq!(events(&mut stream).await, [content("Hi")]);
+    }
+
+    #[tokio::test]
+    async fn refusal_is_read_alongside_content() {
+        let mut stream = stream_of(&[
+            "data: {\"choices\":[{\"delta\":{\"content\":\"\",\"refusal\":\"I can't\"}}]}\n\n",
+            "data: {\"choices\":[{\"delta\":{\"refusal\":\" help.\"},\"finish_reason\":\"stop\"}]}\n\n",
+            "data: [DONE]\n\n",
+        ]);
+        assert_eq!(
+            events(&mut stream).await,
+            [
+                content(""),
+                StreamEvent::Refusal("I can't".to_string()),
+                StreamEvent::Refusal(" help.".to_string()),
+                StreamEvent::Finish("stop".to_string()),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn empty_choices_still_yield_usage() {
+        let usage = "{\"prompt_tokens\":3,\"completion_tokens\":2,\"total_tokens\":5}";
+        let mut stream = stream_of(&[
+            &format!("data: {{\"choices\":[],\"usage\":{}}}\n\n", usage),
+            "data: [DONE]\n\n",
+        ]);
+        assert_eq!(
+            events(&mut stream).await,
+            [StreamEvent::Usage(Usage {
+                prompt_tokens: 3,
+                completion_tokens: 2,
+                total_tokens: 5,
+            })]
+        );
+    }
+
+    #[tokio::test]
+    async fn json_split_across_chunks_and_lines_is_buffered() {
+        let mut stream = stream_of(&[
+            "data: {\"choices\":[{\"delta\":",
+            "{\"content\":\"one\"}}]}\n\n",
+            "data: {\"choices\":[{\"delta\":\n",
+            "data: {\"content\":\"two\"}}]}\n\n",
+            "data: [DONE]\n\n",
+        ]);
+        assert_eq!(events(&mut stream).await, [content("one"), content("two")]);
+    }
+
+    #[tokio::test]
+    async fn malformed_and_unfinished_events_are_skipped() {
+        let mut stream = stream_of(&[
+            "data: {not json}\n\n",
+            "data: {\"choices\":[{\"delta\":\n\n",
+            "data: {\"choices\":[{\"delta\":{\"content\":\"kept\"}}]}\n\n",
+            "data: [DONE]\n\n",
+        ]);
+        assert_eq!(events(&mut stream).await, [content("kept")]);
+    }
+
+    #[tokio::test]
+    async fn stream_ending_without_done_keeps_its_events() {
+        let mut stream = stream_of(&[
+            "data: {\"choices\":[{\"delta\":{\"content\":\"cut\"}}]}\n\n",
+            "data: {\"choices\":[{\"delta\":{\"content\":\" off\"}}]}",
+        ]);
+        assert_eq!(events(&mut stream).await, [content("cut"), content(" off")]);
+        assert!(!stream.saw_done());
+    }
+
+    #[tokio::test]
+    async fn done_is_noted_and_ends_the_stream() {
+        let mut stream = stream_of(&[
+            "data: [DONE]\n\n",
+            "data: {\"choices\":[{\"delta\":{\"content\":\"late\"}}]}\n\n",
+        ]);
+        assert_eq!(events(&mut stream).await, []);
+        assert!(stream.saw_done());
+    }
+
+    #[tokio::test]
+    async fn cancelling_drops_buffered_events() {
+        let token = CancellationToken::new();
+        let mut stream = stream_of(&[concat!(
+            "data: {\"choices\":[{\"delta\":{\"content\":\"one\"}}]}\n\n",
+            "data: {\"choices\":[{\"delta\":{\"content\":\"two\"}}]}\n\n",
+        )])
+        .with_cancellation(token.clone());
+        assert_eq!(stream.next().await.unwrap().unwrap(), content("one"));
+        token.cancel();
+        assert!(stream.next().await.is_none());
+        assert!(stream.next().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn cancelling_ends_a_stream_waiting_for_the_server() {
+        let first: std::io::Result<Bytes> = Ok(Bytes::from_static(
+            b"data: {\"choices\":[{\"delta\":{\"content\":\"one\"}}]}\n\n",
+        ));
+        // The server never sends anything after the first event.
+        let chunks = futures_util::stream::iter([first]).chain(futures_util::stream::pending());
+        let body = reqwest::Body::wrap_stream(chunks);
+        let response = http::Response::builder().status(200).body(body).unwrap();
+        let token = CancellationToken::new();
+        let mut stream = ChatStream::new(response.into(), false).with_cancellation(token.clone());
+        assert_eq!(stream.next().await.unwrap().unwrap(), content("one"));
+        let cancel = tokio::spawn(async move {
+            tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+            token.cancel();
+        });
+        assert!(stream.next().await.is_none());
+        cancel.await.unwrap();
+    }
+}
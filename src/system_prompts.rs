@@ -0,0 +1,74 @@
+use anyhow::{bail, Context, Result};
+use mistral_chat_cli_rs::tr;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+
+/// Directory holding `system_prompts.toml` and the `system_prompts/` directory.
+fn app_config_dir() -> Result<PathBuf> {
+    let dir = dirs::config_dir().with_context(|| tr!("config_dir_unknown"))?;
+    Ok(dir.join("mistral-chat-cli-rs"))
+}
+
+/// Loads every system prompt as `name -> text`.
+///
+/// Prompts come from `system_prompts.toml`, a table of `name = "text"` entries, and from
+/// `system_prompts/<name>.md` or `.txt` files; a file wins over a table entry of the same name.
+fn load() -> Result<BTreeMap<String, String>> {
+    let dir = app_config_dir()?;
+    let mut prompts = BTreeMap::new();
+
+    let table_path = dir.join("system_prompts.toml");
+    match fs::read_to_string(&table_path) {
+        Ok(content) => {
+            prompts = toml::from_str(&content)
+                .with_context(|| tr!("parse_failed", table_path.display()))?;
+        }
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+        Err(e) => return Err(e).context(tr!("read_failed", table_path.display())),
+    }
+
+    let files_dir = dir.join("system_prompts");
+    let entries = match fs::read_dir(&files_dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(prompts),
+        Err(e) => return Err(e).context(tr!("read_failed", files_dir.display())),
+    };
+    for entry in entries {
+        let path = entry?.path();
+        let is_prompt = path
+            .extension()
+            .is_some_and(|ext| ext == "md" || ext == "txt");
+        let Some(name) = path.file_stem().filter(|_| is_prompt) else {
+            continue;
+        };
+        let content = fs::read_to_string(&path)
+            .with_context(|| tr!("system_prompt_read_failed", path.display()))?;
+        prompts.insert(name.to_string_lossy().into_owned(), content);
+    }
+    Ok(prompts)
+}
+
+/// Lists available system prompts as `(name, first line)`, sorted by name.
+pub fn list() -> Result<Vec<(String, String)>> {
+    Ok(load()?
+        .into_iter()
+        .map(|(name, text)| {
+            let first_line = text
+                .lines()
+                .map(|line| line.trim_start_matches('#').trim())
+                .find(|line| !line.is_empty())
+                .unwrap_or_default()
+                .to_string();
+            (name, first_line)
+        })
+        .collect())
+}
+
+/// Returns the text of the named system prompt.
+pub fn get(name: &str) -> Result<String> {
+    match load()?.remove(name) {
+        Some(text) => Ok(text.trim().to_string()),
+        None => bail!(tr!("no_system_prompt", name, app_config_dir()?.display())),
+    }
+}
@@ -0,0 +1,69 @@
+use anyhow::{bail, Context, Result};
+use mistral_chat_cli_rs::tr;
+use std::fs;
+use std::path::PathBuf;
+
+/// Placeholder in a template that is replaced by the prompt given on the command line.
+const PROMPT_PLACEHOLDER: &str = "{{prompt}}";
+
+/// Directory holding prompt templates, one `<name>.md` or `<name>.txt` file each.
+fn templates_dir() -> Result<PathBuf> {
+    let dir = dirs::config_dir().with_context(|| tr!("config_dir_unknown"))?;
+    Ok(dir.join("mistral-chat-cli-rs").join("templates"))
+}
+
+/// Lists available templates as `(name, description)`, sorted by name.
+///
+/// The description is the first non-empty line of the file, without a leading Markdown `#`.
+pub fn list() -> Result<Vec<(String, String)>> {
+    let dir = templates_dir()?;
+    let entries = match fs::read_dir(&dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e).context(tr!("read_failed", dir.display())),
+    };
+
+    let mut templates = Vec::new();
+    for entry in entries {
+        let path = entry?.path();
+        let is_template = path
+            .extension()
+            .is_some_and(|ext| ext == "md" || ext == "txt");
+        let Some(name) = path.file_stem().filter(|_| is_template) else {
+            continue;
+        };
+        let content = fs::read_to_string(&path)
+            .with_context(|| tr!("template_read_failed", path.display()))?;
+        let description = content
+            .lines()
+            .map(|line| line.trim_start_matches('#').trim())
+            .find(|line| !line.is_empty())
+            .unwrap_or_default()
+            .to_string();
+        templates.push((name.to_string_lossy().into_owned(), description));
+    }
+    templates.sort();
+    Ok(templates)
+}
+
+/// Renders the named template, substituting `{{prompt}}` with `prompt`.
+pub fn render(name: &str, prompt: Option<&str>) -> Result<String> {
+    let dir = templates_dir()?;
+    let Some(path) = ["md", "txt"]
+        .iter()
+        .map(|ext| dir.join(format!("{}.{}", name, ext)))
+        .find(|path| path.is_file())
+    else {
+        bail!(tr!("no_template", name, dir.display()));
+    };
+    let content =
+        fs::read_to_string(&path).with_context(|| tr!("template_read_failed", path.display()))?;
+
+    match prompt {
+        Some(prompt) => Ok(content.replace(PROMPT_PLACEHOLDER, prompt)),
+        None if content.contains(PROMPT_PLACEHOLDER) => {
+            bail!(tr!("template_needs_prompt", name, PROMPT_PLACEHOLDER))
+        }
+        None => Ok(content),
+    }
+}
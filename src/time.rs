@@ -0,0 +1,134 @@
+//! Conversions between Unix timestamps and the UTC dates shown to and typed by the user.
+
+/// Days since 1970-01-01 of a proleptic Gregorian date, after Howard Hinnant's
+/// `days_from_civil`.
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let yoe = year.rem_euclid(400);
+    let doy = (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
+}
+
+/// The date of a day since 1970-01-01, the inverse of `days_from_civil`.
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    (yoe + era * 400 + i64::from(month <= 2), month, day)
+}
+
+/// Formats seconds since the Unix epoch as `YYYY-MM-DD HH:MM UTC`.
+pub fn utc_time(seconds: i64) -> String {
+    let (year, month, day) = civil_from_days(seconds.div_euclid(86_400));
+    let rest = seconds.rem_euclid(86_400);
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02} UTC",
+        year,
+        month,
+        day,
+        rest / 3600,
+        rest % 3600 / 60
+    )
+}
+
+/// Parses a point in time typed by the user, relative to `now`, into the span of seconds since
+/// the Unix epoch it stands for: `[start, end)`.
+///
+/// Accepts RFC 3339 (`2026-10-01T09:30:00Z`, `2026-10-01 09:30:00+02:00`), which names a single
+/// second; a bare date (`2026-10-01`, UTC), which names the whole day; and an age such as `30m`,
+/// `12h`, `7d` or `2w`, which names the second that long before `now`.
+pub fn parse(text: &str, now: i64) -> Option<(i64, i64)> {
+    let text = text.trim();
+    if let Some(age) = parse_age(text) {
+        let at = now - age;
+        return Some((at, at + 1));
+    }
+    let date = text.get(..10)?;
+    let day = parse_date(date)?;
+    let rest = &text[10..];
+    if rest.is_empty() {
+        return Some((day * 86_400, (day + 1) * 86_400));
+    }
+    let rest = rest.strip_prefix(['T', 't', ' '])?;
+    let number = |s: Option<&str>| -> Option<i64> {
+        s.filter(|s| s.len() == 2 && s.bytes().all(|b| b.is_ascii_digit()))?
+            .parse()
+            .ok()
+    };
+    let (hour, minute, second) = (
+        number(rest.get(..2))?,
+        number(rest.get(3..5))?,
+        number(rest.get(6..8))?,
+    );
+    if rest.get(2..3) != Some(":") || rest.get(5..6) != Some(":") {
+        return None;
+    }
+    if hour > 23 || minute > 59 || second > 60 {
+        return None;
+    }
+    // Fractions of a second do not matter at this resolution.
+    let zone = rest[8..].trim_start_matches(|c: char| c == '.' || c.is_ascii_digit());
+    let offset = match zone {
+        "Z" | "z" => 0,
+        _ => {
+            let sign = match zone.get(..1)? {
+                "+" => 1,
+                "-" => -1,
+                _ => return None,
+            };
+            if zone.len() != 6 || zone.get(3..4) != Some(":") {
+                return None;
+            }
+            sign * (number(zone.get(1..3))? * 3600 + number(zone.get(4..6))? * 60)
+        }
+    };
+    let at = day * 86_400 + hour * 3600 + minute * 60 + second - offset;
+    Some((at, at + 1))
+}
+
+/// Parses `YYYY-MM-DD` into days since 1970-01-01.
+fn parse_date(text: &str) -> Option<i64> {
+    let bytes = text.as_bytes();
+    if bytes.len() != 10 || bytes[4] != b'-' || bytes[7] != b'-' {
+        return None;
+    }
+    let year: i64 = text[..4].parse().ok()?;
+    let month: i64 = text[5..7].parse().ok()?;
+    let day: i64 = text[8..].parse().ok()?;
+    let days = days_from_civil(year, month, day);
+    // Rejects dates such as 2026-02-30 that do not round-trip.
+    (civil_from_days(days) == (year, month, day)).then_some(days)
+}
+
+/// Parses an age such as `7d` into seconds.
+fn parse_age(text: &str) -> Option<i64> {
+    let unit = match text.chars().last()? {
+        'm' => 60,
+        'h' => 3600,
+        'd' => 86_400,
+        'w' => 7 * 86_400,
+        _ => return None,
+    };
+    let count: i64 = text[..text.len() - 1].parse().ok()?;
+    (count >= 0).then(|| count.checked_mul(unit)).flatten()
+}
+
+/// Parses a duration typed by the user into seconds: a bare number of seconds, or a count with
+/// an `s`, `m` or `h` suffix, such as `90s` or `2m`.
+pub fn parse_seconds(text: &str) -> Option<u64> {
+    let text = text.trim();
+    let (count, unit) = match text.char_indices().last()? {
+        (end, 's') => (&text[..end], 1),
+        (end, 'm') => (&text[..end], 60),
+        (end, 'h') => (&text[..end], 3600),
+        _ => (text, 1),
+    };
+    count.parse::<u64>().ok()?.checked_mul(unit)
+}
@@ -0,0 +1,113 @@
+//! Timing of the phases of a request, for `chat --profile-timing`.
+//!
+//! reqwest reports no timings of its own, so the client's DNS resolver and connector are
+//! wrapped to note how long name resolution and connection setup take. TCP connect and the
+//! TLS handshake happen inside one connector call and are measured together.
+
+use reqwest::dns::{Addrs, Name, Resolve, Resolving};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex, PoisonError};
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
+use tower_layer::Layer;
+use tower_service::Service;
+
+/// How long the phases of one request took. A phase that did not happen, such as connecting
+/// when a pooled connection was reused, is `None`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RequestTimings {
+    /// Resolving the API host name.
+    pub dns: Option<Duration>,
+    /// Opening the TCP connection and completing the TLS handshake, DNS excluded.
+    pub connect: Option<Duration>,
+    /// From sending the request until the response headers arrived.
+    pub first_byte: Option<Duration>,
+    /// From sending the request until the first token of the answer arrived.
+    pub first_token: Option<Duration>,
+    /// From sending the request until the response ended.
+    pub total: Option<Duration>,
+}
+
+/// Connection phases noted by the resolver and connector since the last `take`.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct ConnectTimings(Arc<Mutex<(Option<Duration>, Option<Duration>)>>);
+
+impl ConnectTimings {
+    /// Returns the DNS and connect times noted so far and starts over.
+    pub(crate) fn take(&self) -> (Option<Duration>, Option<Duration>) {
+        std::mem::take(&mut *self.0.lock().unwrap_or_else(PoisonError::into_inner))
+    }
+
+    fn note_dns(&self, elapsed: Duration) {
+        self.0.lock().unwrap_or_else(PoisonError::into_inner).0 = Some(elapsed);
+    }
+
+    /// Notes a connection that took `elapsed` in all, of which DNS was part.
+    fn note_connect(&self, elapsed: Duration) {
+        let mut timings = self.0.lock().unwrap_or_else(PoisonError::into_inner);
+        timings.1 = Some(elapsed.saturating_sub(timings.0.unwrap_or_default()));
+    }
+}
+
+/// The system resolver, timed.
+pub(crate) struct TimedResolver(pub(crate) ConnectTimings);
+
+impl Resolve for TimedResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        let timings = self.0.clone();
+        let host = name.as_str().to_string();
+        Box::pin(async move {
+            let start = Instant::now();
+            let addrs = tokio::net::lookup_host((host, 0)).await?;
+            timings.note_dns(start.elapsed());
+            Ok(Box::new(addrs) as Addrs)
+        })
+    }
+}
+
+/// Wraps the connector so each new connection's setup time is noted.
+#[derive(Clone)]
+pub(crate) struct TimedConnectLayer(pub(crate) ConnectTimings);
+
+impl<S> Layer<S> for TimedConnectLayer {
+    type Service = TimedConnect<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        TimedConnect {
+            inner,
+            timings: self.0.clone(),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub(crate) struct TimedConnect<S> {
+    inner: S,
+    timings: ConnectTimings,
+}
+
+impl<S, R> Service<R> for TimedConnect<S>
+where
+    S: Service<R>,
+    S::Future: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<S::Response, S::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: R) -> Self::Future {
+        let timings = self.timings.clone();
+        let start = Instant::now();
+        let connecting = self.inner.call(request);
+        Box::pin(async move {
+            let connection = connecting.await?;
+            timings.note_connect(start.elapsed());
+            Ok(connection)
+        })
+    }
+}
@@ -0,0 +1,113 @@
+use crate::client::{ChatClient, ChatParams, RequestMessage};
+use crate::error::Result;
+use crate::stream::StreamEvent;
+
+/// Start of the system message that replaces summarized turns.
+const SUMMARY_PREFIX: &str = "Summary of the earlier conversation:";
+/// Tokens set aside for the summary when choosing how many turns to summarize.
+const SUMMARY_TOKENS: usize = 100;
+const SUMMARIZE_PROMPT: &str = "Summarize the following conversation in a few sentences, keeping \
+    facts, decisions and open questions that later messages may refer to.";
+
+/// How to shorten a conversation that has grown past its token budget.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TruncateStrategy {
+    /// Remove the oldest turns.
+    Drop,
+    /// Replace the oldest turns with a system message summarizing them.
+    Summarize,
+}
+
+/// A token budget for the messages sent with each request.
+#[derive(Debug, Clone, Copy)]
+pub struct HistoryLimit {
+    /// Estimated tokens the messages may take up.
+    pub tokens: usize,
+    pub strategy: TruncateStrategy,
+}
+
+/// Roughly estimates the tokens `messages` take up, at about four characters per token.
+pub fn estimate_tokens(messages: &[RequestMessage]) -> usize {
+    messages
+        .iter()
+        .map(|message| message.content.chars().count().div_ceil(4) + 4)
+        .sum()
+}
+
+impl HistoryLimit {
+    /// Shortens `messages` until they fit the budget, returning how many messages were removed.
+    ///
+    /// Leading system messages and the latest message are always kept, and a conversation never
+    /// resumes with an assistant turn. An earlier summary is folded into the next one.
+    pub async fn apply(
+        &self,
+        client: &ChatClient,
+        model: &str,
+        messages: &mut Vec<RequestMessage>,
+    ) -> Result<usize> {
+        if estimate_tokens(messages) <= self.tokens {
+            return Ok(0);
+        }
+        let start = messages
+            .iter()
+            .take_while(|m| m.role == "system" && !m.content.starts_with(SUMMARY_PREFIX))
+            .count();
+        let kept = estimate_tokens(&messages[..start])
+            + match self.strategy {
+                TruncateStrategy::Drop => 0,
+                TruncateStrategy::Summarize => SUMMARY_TOKENS,
+            };
+        let mut end = start;
+        while end + 1 < messages.len() && kept + estimate_tokens(&messages[end..]) > self.tokens {
+            end += 1;
+        }
+        while end + 1 < messages.len() && messages[end].role == "assistant" {
+            end += 1;
+        }
+        if end == start {
+            return Ok(0);
+        }
+
+        let removed: Vec<RequestMessage> = messages.drain(start..end).collect();
+        if self.strategy == TruncateStrategy::Summarize {
+            let summary = summarize(client, model, &removed).await?;
+            messages.insert(
+                start,
+                RequestMessage {
+                    role: "system".to_string(),
+                    content: format!("{} {}", SUMMARY_PREFIX, summary.trim()),
+                    ..Default::default()
+                },
+            );
+        }
+        Ok(removed.len())
+    }
+}
+
+/// Asks `model` for a summary of `messages`.
+async fn summarize(
+    client: &ChatClient,
+    model: &str,
+    messages: &[RequestMessage],
+) -> Result<String> {
+    let mut transcript = String::from(SUMMARIZE_PROMPT);
+    for message in messages {
+        transcript.push_str(&format!("\n\n[{}]\n{}", message.role, message.content));
+    }
+    let request = vec![RequestMessage {
+        role: "user".to_string(),
+        content: transcript,
+        ..Default::default()
+    }];
+
+    let mut stream = client
+        .chat_events(model, request, &ChatParams::default())
+        .await?;
+    let mut summary = String::new();
+    while let Some(event) = stream.next().await {
+        if let StreamEvent::Content(content) = event? {
+            summary.push_str(&content);
+        }
+    }
+    Ok(summary)
+}
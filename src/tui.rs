@@ -0,0 +1,294 @@
+use anyhow::Result;
+use crossterm::event::{Event, EventStream, KeyCode, KeyEventKind, KeyModifiers};
+use futures_util::StreamExt;
+use mistral_chat_cli_rs::client::ChatParams;
+use mistral_chat_cli_rs::stream::Usage;
+use mistral_chat_cli_rs::tr;
+use mistral_chat_cli_rs::truncate::HistoryLimit;
+use mistral_chat_cli_rs::{ChatClient, ChatStream, RequestMessage, StreamEvent};
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Modifier, Style};
+use ratatui::text::{Line, Text};
+use ratatui::widgets::{Block, Paragraph, Wrap};
+use ratatui::{DefaultTerminal, Frame};
+
+/// State of the interactive chat screen.
+struct App {
+    model: String,
+    messages: Vec<RequestMessage>,
+    input: String,
+    /// How many lines the history is scrolled up from the bottom.
+    scroll: u16,
+    /// Rows available to the history pane on the last draw.
+    page: u16,
+    /// Tokens used by completed turns.
+    usage: Usage,
+    /// Content deltas seen in the current turn, a rough token estimate until usage arrives.
+    streamed: u32,
+    status: Status,
+}
+
+/// What the status bar reports about the conversation.
+enum Status {
+    Ready,
+    Shortening,
+    Waiting,
+    Streaming,
+    Finished(String),
+    Failed(String),
+    /// A `/usage` summary.
+    Usage(String),
+}
+
+impl std::fmt::Display for Status {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let text = match self {
+            Status::Ready => tr!("tui_ready"),
+            Status::Shortening => tr!("tui_shortening"),
+            Status::Waiting => tr!("tui_waiting"),
+            Status::Streaming => tr!("tui_streaming"),
+            Status::Finished(reason) => tr!("tui_finished", reason),
+            Status::Failed(error) => tr!("tui_error", error),
+            Status::Usage(summary) => summary.clone(),
+        };
+        f.write_str(&text)
+    }
+}
+
+impl App {
+    fn draw(&mut self, frame: &mut Frame) {
+        let [history_area, input_area, status_area] = Layout::vertical([
+            Constraint::Min(1),
+            Constraint::Length(3),
+            Constraint::Length(1),
+        ])
+        .areas(frame.area());
+
+        let mut text = Text::default();
+        for message in &self.messages {
+            let header = match message.role.as_str() {
+                "user" => "You",
+                "assistant" => "Assistant",
+                role => role,
+            };
+            text.push_line(Line::styled(
+                header,
+                Style::default().add_modifier(Modifier::BOLD),
+            ));
+            for line in message.content.lines() {
+                text.push_line(Line::raw(line.to_string()));
+            }
+            text.push_line(Line::default());
+        }
+
+        let block = Block::bordered().title("History");
+        let paragraph = Paragraph::new(text).wrap(Wrap { trim: false });
+        let inner = block.inner(history_area);
+        self.page = inner.height;
+        let total = paragraph.line_count(inner.width) as u16;
+        let max_scroll = total.saturating_sub(inner.height);
+        self.scroll = self.scroll.min(max_scroll);
+        let offset = max_scroll - self.scroll;
+        frame.render_widget(paragraph.block(block).scroll((offset, 0)), history_area);
+
+        let input = Paragraph::new(self.input.as_str()).block(Block::bordered().title("Message"));
+        frame.render_widget(input, input_area);
+        frame.set_cursor_position((
+            input_area.x + 1 + self.input.chars().count() as u16,
+            input_area.y + 1,
+        ));
+
+        let status = tr!(
+            "tui_status",
+            self.model,
+            self.usage.total_tokens + self.streamed,
+            self.status
+        );
+        frame.render_widget(
+            Paragraph::new(status).style(Style::default().add_modifier(Modifier::REVERSED)),
+            status_area,
+        );
+    }
+
+    /// Appends text to the assistant message currently being streamed.
+    fn append(&mut self, content: &str) {
+        if let Some(message) = self.messages.last_mut() {
+            message.content.push_str(content);
+        }
+    }
+}
+
+/// Approximate list prices in USD per million (input, output) tokens.
+const PRICES: [(&str, f64, f64); 2] = [
+    ("mistral-large-latest", 2.0, 6.0),
+    ("codestral-latest", 0.3, 0.9),
+];
+
+/// Summarizes session token usage, with an estimated cost for models with a known price.
+fn describe_usage(model: &str, usage: &Usage) -> String {
+    let mut summary = tr!(
+        "usage_summary",
+        usage.prompt_tokens,
+        usage.completion_tokens,
+        usage.total_tokens
+    );
+    if let Some((_, input, output)) = PRICES.iter().find(|(name, _, _)| *name == model) {
+        let cost = (usage.prompt_tokens as f64 * input + usage.completion_tokens as f64 * output)
+            / 1_000_000.0;
+        summary.push_str(&tr!("usage_cost", format!("{:.4}", cost)));
+    }
+    summary
+}
+
+/// Waits for the next event of the active stream, or forever when idle.
+async fn next_event(
+    stream: &mut Option<ChatStream>,
+) -> Option<mistral_chat_cli_rs::Result<StreamEvent>> {
+    match stream {
+        Some(stream) => stream.next().await,
+        None => std::future::pending().await,
+    }
+}
+
+/// Runs the full-screen chat interface until the user presses Esc, then prints the session usage.
+///
+/// With a `history_limit`, the conversation is shortened before each request once it grows past
+/// the limit; the history pane then shows what is actually sent.
+pub async fn run(
+    client: &ChatClient,
+    model: &str,
+    messages: Vec<RequestMessage>,
+    params: &ChatParams,
+    history_limit: Option<HistoryLimit>,
+) -> Result<()> {
+    let mut terminal = ratatui::init();
+    let result = event_loop(
+        &mut terminal,
+        client,
+        model,
+        messages,
+        params,
+        history_limit,
+    )
+    .await;
+    ratatui::restore();
+    println!("{}", tr!("session_usage", describe_usage(model, &result?)));
+    Ok(())
+}
+
+async fn event_loop(
+    terminal: &mut DefaultTerminal,
+    client: &ChatClient,
+    model: &str,
+    messages: Vec<RequestMessage>,
+    params: &ChatParams,
+    history_limit: Option<HistoryLimit>,
+) -> Result<Usage> {
+    let mut app = App {
+        model: model.to_string(),
+        messages,
+        input: String::new(),
+        scroll: 0,
+        page: 0,
+        usage: Usage::default(),
+        streamed: 0,
+        status: Status::Ready,
+    };
+    let mut events = EventStream::new();
+    let mut stream: Option<ChatStream> = None;
+    // An initial prompt from the command line is sent straight away.
+    let mut send = app.messages.last().is_some_and(|m| m.role == "user");
+
+    loop {
+        if send {
+            send = false;
+            if let Some(limit) = &history_limit {
+                app.status = Status::Shortening;
+                terminal.draw(|frame| app.draw(frame))?;
+                if let Err(e) = limit.apply(client, model, &mut app.messages).await {
+                    app.status = Status::Failed(e.to_string());
+                    continue;
+                }
+            }
+            app.status = Status::Waiting;
+            terminal.draw(|frame| app.draw(frame))?;
+            match client
+                .chat_events(model, app.messages.clone(), params)
+                .await
+            {
+                Ok(started) => {
+                    app.messages.push(RequestMessage {
+                        role: "assistant".to_string(),
+                        content: String::new(),
+                        ..Default::default()
+                    });
+                    app.status = Status::Streaming;
+                    stream = Some(started);
+                }
+                Err(e) => app.status = Status::Failed(e.to_string()),
+            }
+        }
+        terminal.draw(|frame| app.draw(frame))?;
+
+        tokio::select! {
+            event = events.next() => {
+                let Some(Ok(Event::Key(key))) = event else {
+                    continue;
+                };
+                if key.kind != KeyEventKind::Press {
+                    continue;
+                }
+                match key.code {
+                    KeyCode::Esc => return Ok(app.usage),
+                    KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                        return Ok(app.usage)
+                    }
+                    KeyCode::PageUp => app.scroll = app.scroll.saturating_add(app.page.max(1)),
+                    KeyCode::PageDown => app.scroll = app.scroll.saturating_sub(app.page.max(1)),
+                    KeyCode::Enter if app.input.trim() == "/usage" => {
+                        app.input.clear();
+                        app.status = Status::Usage(describe_usage(model, &app.usage));
+                    }
+                    KeyCode::Enter if stream.is_none() && !app.input.trim().is_empty() => {
+                        app.messages.push(RequestMessage {
+                            role: "user".to_string(),
+                            content: std::mem::take(&mut app.input),
+                            ..Default::default()
+                        });
+                        app.scroll = 0;
+                        send = true;
+                    }
+                    KeyCode::Backspace => {
+                        app.input.pop();
+                    }
+                    KeyCode::Char(c) => app.input.push(c),
+                    _ => {}
+                }
+            }
+            event = next_event(&mut stream) => match event {
+                Some(Ok(StreamEvent::Content(content))) => {
+                    app.append(&content);
+                    app.streamed += 1;
+                }
+                Some(Ok(StreamEvent::Refusal(refusal))) => app.append(&refusal),
+                Some(Ok(StreamEvent::Reasoning(_) | StreamEvent::Citation(_))) => {}
+                Some(Ok(StreamEvent::Usage(usage))) => {
+                    app.usage += usage;
+                    app.streamed = 0;
+                }
+                Some(Ok(StreamEvent::Finish(reason))) => app.status = Status::Finished(reason),
+                Some(Err(e)) => app.status = Status::Failed(e.to_string()),
+                None => {
+                    stream = None;
+                    // Without reported usage, count the deltas as completion tokens.
+                    let streamed = std::mem::take(&mut app.streamed);
+                    app.usage.completion_tokens += streamed;
+                    app.usage.total_tokens += streamed;
+                    if matches!(app.status, Status::Streaming) {
+                        app.status = Status::Ready;
+                    }
+                }
+            },
+        }
+    }
+}
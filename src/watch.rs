@@ -0,0 +1,68 @@
+use notify_debouncer_full::notify::{RecommendedWatcher, RecursiveMode};
+use notify_debouncer_full::{new_debouncer, DebounceEventResult, Debouncer, RecommendedCache};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tokio::sync::mpsc;
+
+/// How long the file must go without events before a change counts, so that an editor saving
+/// in several writes, or replacing the file, triggers one run instead of several.
+const DEBOUNCE: Duration = Duration::from_millis(300);
+
+/// Watches one file for changes through the platform's notification API.
+pub struct Watcher {
+    path: PathBuf,
+    changes: mpsc::UnboundedReceiver<()>,
+    // Dropping the debouncer stops the watch.
+    _debouncer: Debouncer<RecommendedWatcher, RecommendedCache>,
+}
+
+impl Watcher {
+    /// Starts watching `path`, which must exist.
+    ///
+    /// The directory holding the file is watched rather than the file itself: editors that save
+    /// by writing a new file and renaming it over the old one would otherwise end the watch.
+    pub fn new(path: &Path) -> io::Result<Watcher> {
+        let path = path.canonicalize()?;
+        let dir = path.parent().unwrap_or(Path::new("/")).to_path_buf();
+        let (sender, changes) = mpsc::unbounded_channel();
+        let file = path.clone();
+        let mut debouncer = new_debouncer(DEBOUNCE, None, move |result: DebounceEventResult| {
+            let Ok(events) = result else {
+                return;
+            };
+            // Opening and reading the file, as each run does, is not a change.
+            let changed = events
+                .iter()
+                .any(|event| !event.kind.is_access() && event.paths.contains(&file));
+            if changed {
+                let _ = sender.send(());
+            }
+        })
+        .map_err(io::Error::other)?;
+        debouncer
+            .watch(&dir, RecursiveMode::NonRecursive)
+            .map_err(io::Error::other)?;
+        Ok(Watcher {
+            path,
+            changes,
+            _debouncer: debouncer,
+        })
+    }
+
+    /// Waits until the file has changed and settled.
+    pub async fn next_change(&mut self) {
+        loop {
+            if self.changes.recv().await.is_none() {
+                // The debouncer is owned by `self`, so its sender outlives every call.
+                return std::future::pending().await;
+            }
+            // The debouncer may hand over one save's events in several batches; wait for them all.
+            while let Ok(Some(())) = tokio::time::timeout(DEBOUNCE, self.changes.recv()).await {}
+            // A file that was removed, or replaced by a failed save, is waited on again.
+            if self.path.exists() {
+                return;
+            }
+        }
+    }
+}
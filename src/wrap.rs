@@ -0,0 +1,97 @@
+/// Reflows streamed text to a fixed width, breaking only on word boundaries.
+///
+/// Only the word currently being received is held back. Explicit newlines are kept and
+/// fenced code blocks are passed through untouched.
+pub struct Wrapper {
+    width: usize,
+    column: usize,
+    word: String,
+    pending_space: bool,
+    at_line_start: bool,
+    line: String,
+    in_code_block: bool,
+}
+
+impl Wrapper {
+    pub fn new(width: usize) -> Self {
+        Wrapper {
+            width,
+            column: 0,
+            word: String::new(),
+            pending_space: false,
+            at_line_start: true,
+            line: String::new(),
+            in_code_block: false,
+        }
+    }
+
+    /// Width of the attached terminal, falling back to 80 columns.
+    pub fn terminal_width() -> usize {
+        terminal_size::terminal_size()
+            .map(|(terminal_size::Width(width), _)| width as usize)
+            .unwrap_or(80)
+    }
+
+    /// Feeds a content fragment and returns the text that is ready to print.
+    pub fn push(&mut self, content: &str) -> String {
+        let mut out = String::new();
+        for ch in content.chars() {
+            if ch == '\n' {
+                self.flush_word(&mut out);
+                out.push('\n');
+                if self.line.trim_start().starts_with("```") {
+                    self.in_code_block = !self.in_code_block;
+                }
+                self.line.clear();
+                self.column = 0;
+                self.pending_space = false;
+                self.at_line_start = true;
+                continue;
+            }
+            self.line.push(ch);
+
+            if self.in_code_block {
+                out.push(ch);
+            } else if ch == ' ' || ch == '\t' {
+                if self.at_line_start {
+                    // Keep indentation so nested lists and quotes survive.
+                    out.push(ch);
+                    self.column += 1;
+                } else {
+                    self.flush_word(&mut out);
+                    self.pending_space = true;
+                }
+            } else {
+                self.at_line_start = false;
+                self.word.push(ch);
+            }
+        }
+        out
+    }
+
+    /// Returns any text still held back at the end of the stream.
+    pub fn finish(&mut self) -> String {
+        let mut out = String::new();
+        self.flush_word(&mut out);
+        out
+    }
+
+    fn flush_word(&mut self, out: &mut String) {
+        if self.word.is_empty() {
+            return;
+        }
+        let len = self.word.chars().count();
+        let space = usize::from(self.pending_space);
+        if self.column > 0 && self.column + space + len > self.width {
+            out.push('\n');
+            self.column = 0;
+        } else if self.pending_space {
+            out.push(' ');
+            self.column += 1;
+        }
+        out.push_str(&self.word);
+        self.column += len;
+        self.word.clear();
+        self.pending_space = false;
+    }
+}